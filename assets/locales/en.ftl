@@ -0,0 +1,366 @@
+# English messages of the bot. Every other catalog falls back to these
+
+## durations
+
+duration-seconds =
+    { $count ->
+        [one] { $count } second
+       *[other] { $count } seconds
+    }
+duration-minutes =
+    { $count ->
+        [one] { $count } minute
+       *[other] { $count } minutes
+    }
+duration-hours =
+    { $count ->
+        [one] { $count } hour
+       *[other] { $count } hours
+    }
+duration-days =
+    { $count ->
+        [one] { $count } day
+       *[other] { $count } days
+    }
+eta-minutes = ~{ $minutes } min
+eta-hours = ~{ $hours } h { $minutes } min
+time-just-now = just now
+time-ago = { $duration } ago
+progress-estimate = running for { $running }, { $percent }% done (ETA { $eta })
+progress-overdue = running for { $running }, taking longer than usual ({ $eta })
+progress-unknown = running for { $running }, no estimate yet
+
+## job notifications
+
+job-running = 🚧 { $repo }'s job is running...
+job-success = ✅ { $repo }'s job has completed
+job-flaky-failure = 🎲 { $repo }'s job encountered a flaky failure
+job-failure = 🚨 { $repo }'s job encountered failure
+job-cancelled = ⛔️ { $repo }'s job was cancelled
+job-eta = ETA { $eta }
+job-elapsed = elapsed: { $elapsed }
+job-by = by:
+job-link = link:
+job-slowdown = ⚠️ { $ratio }× slower than usual
+job-owners = owners: { $owners }
+email-failure-subject = [{ $repo }] job #{ $job } failed
+email-elapsed = elapsed
+email-by = by
+email-link = link
+email-digest-subject = [{ $repo }] daily CI summary
+email-digest-title = { $repo } daily summary
+email-jobs = jobs
+email-success = success
+email-failure = failure
+email-cancelled = cancelled
+email-running = running
+email-pass-rate = pass rate
+email-failed-jobs = failed jobs
+email-failed-by = by { $name }
+
+## job listings
+
+job-took = took { $duration }
+job-running-for = running for { $duration }
+job-triggered-by = by { $name }
+job-link-text = link
+today-header = today's jobs
+today-empty = No running job today. Start running job to see them here.
+latest-header = latest job
+latest-empty = No latest running job. Start running job to see them here.
+running-empty = No running job configured. Start running job to see them here.
+running-unacknowledged = unacknowledged failures:
+running-failure = #{ $job } failed { $duration } ago
+browser-header = { $repo }'s jobs
+browser-page = (page { $page } of { $pages })
+browser-empty = No job was run yet.
+search-header = Jobs matching { $query }
+search-header-all = Job history
+search-empty = No job matches the search.
+search-gone-short = The search is no longer available
+
+## repos
+
+start = Let's start by configuring your first repo. Type /help for more info
+help-header = These commands are supported:
+invalid-command = Invalid command. see /help for more info.
+repos-empty = No repo configured. Type /help to get started.
+picker-header = Select a repo to manipulate:
+picker-header-page = Select a repo to manipulate (page { $page } of { $pages }):
+repo-created = Successfully added repo: { $name }
+repo-name-taken = A repo named { $name } already exists. Pick another name.
+repo-selecting = Selecting repo name: { $name }
+repo-selected = Selected repo { $name }. Type /help to see what you can do with it.
+repo-missing = Requested repo does not exists.
+repo-gone = The selected repo no longer exists. Use /list to pick another one.
+repo-gone-short = The repo no longer exists
+info-name = name: { $name }
+info-key = key:
+rename-success = Successfully updated repo name
+delete-confirm = Delete repo { $repo }? It can be restored with /undo for { $retention }.
+delete-cancelled = Nothing was deleted.
+confirm-delete = 🗑 Delete
+confirm-keep = Keep
+delete-owner-required = Only an owner of this repo can delete it
+delete-success = 🗑 Deleted repo { $repo }. Use /undo within { $retention } to restore it.
+delete-already = The repo was already deleted.
+reset-owner-required = Only an owner of every repo of this chat can reset it.
+reset-confirm = [DEBUG] Delete every repo of this chat? They can be restored with /undo for { $retention }.
+reset-success = [DEBUG] Deleted { $count } repo(s). Use /undo within { $retention } to restore them.
+undo-owner-required = Only an owner of the deleted repo can restore it.
+undo-empty = Nothing to undo.
+undo-restored = Successfully restored repo: { $names }
+undo-taken = Could not restore { $names }, a repo of the same name was created since. Rename it and /undo again.
+dora-all-repos = all repos
+dora-header = 📈 DORA metrics of { $title } over the last { $days } day(s)
+dora-frequency = deployment frequency: { $frequency }/day ({ $deployments } deployment(s))
+dora-lead-time = lead time for changes: { $lead_time }
+dora-failure-rate = change failure rate: { $rate }
+dora-restore = time to restore service: { $restore } ({ $failures } failure(s), { $recoveries } recovered)
+dora-none = n/a
+
+## roles
+
+role-required = This command requires the { $required } role but you are a { $role } of this repo.
+role-own = You cannot change your own role.
+role-granted = Successfully granted { $role } to { $name }
+role-revoked = Successfully revoked the role of { $name }
+role-none = { $name } has no granted role.
+roles-footer = Anyone without a granted role is an owner if they created the chat, a maintainer if they administer it, and a viewer otherwise.
+
+## notifiers and emails
+
+notifier-added = Successfully added { $kind } notifier
+notifiers-empty = No notifier configured. Type /help to see how to add one.
+notifier-removed = Successfully removed notifier
+notifier-missing = Requested notifier does not exists.
+email-not-configured = Email is not set up on this bot. Ask whoever runs it to set SMTP_HOST and SMTP_FROM first.
+email-subscribed = Successfully subscribed { $address } to { $subscription } emails
+emails-empty = No email subscribed. Type /help to see how to add one.
+email-missing = Requested email does not exists.
+email-unsubscribed = Successfully unsubscribed { $address }
+
+## repo settings
+
+chart-empty = No job was run in this range.
+flaky-none = 🎲 No job failed in this range.
+flaky-rate = 🎲 { $flaky } of { $failures } failed job(s) were flaky ({ $percent }%)
+flaky-tests = flakiest tests:
+flaky-test = { $name } ({ $flakes } flake(s))
+audit-empty = No change was recorded for this repo or its chat yet.
+audit-no-page = There is no page { $page }.
+audit-header = 📜 audit log (page { $page } of { $pages })
+audit-older = use /audit { $page } for older changes
+slowdown-off = Slowdown alerts are off
+slowdown-threshold = Jobs { $threshold }× slower than their usual duration are flagged
+slowdown-updated = Successfully updated slowdown threshold
+oncall-empty = No on-call user configured. Type /help to see how to add one.
+oncall-added = Successfully added @{ $username } to on-call
+oncall-missing = @{ $username } is not on-call
+oncall-removed = Successfully removed @{ $username } from on-call
+escalation-off = Escalation is off
+escalation-oncall = Unacknowledged failures are escalated to on-call users after { $minutes } minute(s)
+escalation-fallback = Unacknowledged failures are escalated to on-call users after { $minutes } minute(s) and to chat { $chat } after another { $minutes } minute(s)
+escalation-updated = Successfully updated escalation
+codeowners-empty = No ownership rules were set for this repo
+codeowners-rules =
+    Ownership rules of this repo:
+    { $rules }
+codeowners-updated = Successfully updated ownership rules
+templates-empty = No template was set for this repo, its notifications use the default wording.
+templates-header = Templates of this repo:
+template-missing = No { $kind } template was set for this repo.
+template-no-job = No job was run yet to preview the template against.
+template-blank = The template comes out blank for the latest job, so the default wording would be used.
+template-set = Successfully set the { $kind } template
+template-set-preview = , this is how the latest job would look:
+template-reset = The { $kind } notification uses the default wording again
+
+## digests
+
+digests-empty = No digest scheduled. Type /help to see how to schedule one.
+digest-line = { $period }: { $schedule } ({ $timezone }) next at { $next }
+digest-never = never
+digest-none = No digest scheduled.
+digest-stopped = Successfully stopped digest
+digest-scheduled = Successfully scheduled { $period } digest. next digest at { $next }
+digest-daily-title = 📊 Daily digest
+digest-weekly-title = 📊 Weekly digest
+digest-no-job = No job was run since the last digest.
+digest-jobs = jobs run: { $total } (✅ { $success } 🚨 { $failure } ⛔️ { $cancelled } 🚧 { $running })
+digest-pass-rate = pass rate: { $rate }%
+digest-slowest = slowest jobs:
+digest-red-streak = longest red streak: { $repo } ({ $length } failure(s) in a row)
+digest-committers = top committers:
+digest-committer = { $name } ({ $count } job(s))
+
+## identities
+
+link-success = Successfully linked { $ci_username } to { $name }. Start a private chat with me if you want failures sent to you with /mentions dm
+link-taken = { $ci_username } is already linked to { $name } in this chat. They, or the owner of the chat, have to /unlink_me it first
+link-success-private = Successfully linked { $ci_username } to { $name }. Use /notify_me failures or /notify_me all to get jobs of { $ci_username } from any repo here
+link-taken-private = { $ci_username } is already linked to { $name } in another chat. Link it in the chat of your repo instead
+link-other = You are already linked to { $ci_username } in this chat. Use /unlink_me { $ci_username } first
+unlink-missing = { $ci_username } is not linked in this chat
+unlink-owner-required = { $ci_username } is linked to { $name }. Only they or the owner of the chat can unlink it
+unlink-success = Successfully unlinked { $ci_username }
+identity-missing = No CI username is linked to you. Use /link_me <ci_username> first.
+mentions-set = Successfully set mentions to { $mode }
+notify-private = Start a private chat with me and send /notify_me there so that I can message you.
+notify-set = Successfully set job notifications to { $preference }
+
+## acknowledgements
+
+ack-on-it = 🙋 { $name } is on it: { $repo } #{ $job }
+ack-thanks = Thanks, the failure is yours
+ack-already = { $name } is already on it
+ack-resolved = The failure was already resolved
+ack-not-found = The failure no longer exists
+ack-button = 🙋 I'm on it #{ $job }
+escalated-oncall = ⏰ { $repo } #{ $job } failed { $duration } ago and nobody is on it yet
+escalated-fallback = ⏰ { $repo } #{ $job } failed { $duration } ago and is still unacknowledged
+
+## languages
+
+language-current = This chat is in English. Use /language th to switch to Thai.
+language-set = This chat is in English from now on.
+
+## timezones
+
+timezone-current = This chat is in { $timezone } time. Use /timezone <timezone> to change it.
+timezone-set = This chat is in { $timezone } time from now on.
+
+## invalid arguments
+
+invalid-user = Invalid user: { $error }
+invalid-role = Invalid role: { $error }
+invalid-page = Invalid page: { $error }
+invalid-notifier = Invalid notifier: { $error }
+invalid-email = Invalid email address: { $error }
+invalid-subscription = Invalid subscription: { $error }
+invalid-window = Invalid window: { $error }
+invalid-chart = Invalid chart: { $error }
+invalid-threshold = Invalid threshold: { $error }
+invalid-oncall = Invalid on-call: { $error }
+invalid-escalation = Invalid escalation: { $error }
+invalid-codeowners = Invalid ownership rules: { $error }
+invalid-template = Invalid template: { $error }
+invalid-digest = Invalid digest schedule: { $error }
+invalid-ci-username = Invalid CI username: { $error }
+invalid-mentions = Invalid mention preference: { $error }
+invalid-notify = Invalid notification preference: { $error }
+invalid-language = Invalid language: { $error }
+invalid-timezone = Invalid timezone: { $error }
+invalid-search = Invalid search: { $error }
+
+## descriptions of the commands of a chat without a selected repo
+general-cmd-help = display this text.
+general-cmd-list = display all configured repos to select one.
+general-cmd-today = display all jobs that was created today.
+general-cmd-history =
+    browse the job history of all repos, optionally filtered in the following format: /history [status:<status>] [by:<ci_username>] [branch:<branch>] [since:<YYYY-MM-DD>] [until:<YYYY-MM-DD>]
+    i.e. /history status:failure by:alice branch:production
+general-cmd-search =
+    search jobs of all repos by text in their description, with the filters of /history, in the following format: /search <text> [filters]
+    i.e. /search deploy status:failure since:2022-12-01
+general-cmd-create =
+    create new repo in the following format: /create <repo_name>
+    i.e. /create Turbo Incubator Prototype
+general-cmd-select_repo =
+    select repo for manipulation from a list, or by its position in the list in the following format: /select_repo [index]
+    i.e. /select_repo 1
+general-cmd-digest =
+    schedule a digest of all repos in the following format: /digest daily <HH:MM> [timezone] or /digest weekly <weekday> <HH:MM> [timezone]
+    i.e. /digest daily 09:00 Asia/Bangkok
+    the timezone defaults to the one of this chat, use /digest off [daily|weekly] to stop and /digest to display schedules
+general-cmd-dora =
+    display DORA metrics of all repos over a window in the following format: /dora [<n>d|<n>w]
+    i.e. /dora 30d
+general-cmd-link_me =
+    link your CI username to your telegram account to be mentioned on failures of the repos of this chat in the following format: /link_me <ci_username>
+    i.e. /link_me octocat
+general-cmd-unlink_me = unlink your CI username, or as the owner of the chat anyone's, in the following format: /unlink_me <ci_username>
+general-cmd-mentions = choose how you are told about failures of your jobs in the following format: /mentions <on|off|dm>
+general-cmd-notify_me = receive a private message when your jobs complete in the following format: /notify_me <failures|all|off>
+general-cmd-language =
+    display the language of this chat, or change it in the following format: /language <en|th>
+    i.e. /language th
+general-cmd-timezone =
+    display the timezone of this chat used for /today and every time shown, or change it in the following format: /timezone <timezone>
+    i.e. /timezone Asia/Bangkok
+general-cmd-undo = restore the repo(s) deleted last.
+general-cmd-reset = [DEBUG] Successfully reset all state.
+
+## descriptions of the commands of a chat with a selected repo
+repo-cmd-help = { general-cmd-help }
+repo-cmd-get_info = display current repo info.
+repo-cmd-today = display all jobs that was created today for current repo.
+repo-cmd-running = display all running jobs for current repo.
+repo-cmd-latest = get latest jobs created for this repo.
+repo-cmd-jobs = browse the job history of current repo.
+repo-cmd-history =
+    browse the job history of current repo, optionally filtered in the following format: /history [status:<status>] [by:<ci_username>] [branch:<branch>] [since:<YYYY-MM-DD>] [until:<YYYY-MM-DD>]
+    i.e. /history status:failure by:alice branch:production
+repo-cmd-search =
+    search jobs of current repo by text in their description, with the filters of /history, in the following format: /search <text> [filters]
+    i.e. /search deploy status:failure since:2022-12-01
+repo-cmd-rename = rename current repo.
+repo-cmd-add_notifier =
+    add notifier for current repo in the following format: /add_notifier <slack|discord|webhook> <https url> [token]
+    i.e. /add_notifier slack https://hooks.slack.com/services/T000/B000/XXXX
+    or /add_notifier matrix <homeserver> <room_id> <access_token>
+repo-cmd-notifiers = display all notifiers configured for current repo.
+repo-cmd-remove_notifier =
+    remove notifier by id in the following format: /remove_notifier <id>
+    i.e. /remove_notifier 1
+repo-cmd-add_email =
+    subscribe an email address to current repo in the following format: /add_email <address> [failure|digest|all]
+    i.e. /add_email lead@example.com digest
+repo-cmd-emails = display all email addresses subscribed to current repo.
+repo-cmd-remove_email = unsubscribe an email address from current repo in the following format: /remove_email <address>
+repo-cmd-dora =
+    display DORA metrics of current repo over a window in the following format: /dora [<n>d|<n>w]
+    i.e. /dora 30d
+repo-cmd-chart =
+    render a chart of current repo in the following format: /chart <duration|passrate|heatmap> [<n>d|<n>w]
+    i.e. /chart duration 14d
+repo-cmd-slowdown =
+    alert when a job is slower than its usual duration by the given ratio in the following format: /slowdown <ratio|off>
+    i.e. /slowdown 2.5
+    use /slowdown to display the current threshold
+repo-cmd-flaky =
+    display flaky jobs and the flakiest tests of current repo over a window in the following format: /flaky [<n>d|<n>w]
+    i.e. /flaky 14d
+repo-cmd-oncall =
+    manage on-call users mentioned when a failure is not acknowledged in the following format: /oncall add <@username> or /oncall remove <@username>
+    use /oncall to display on-call users
+repo-cmd-escalation =
+    escalate unacknowledged failures after a number of minutes in the following format: /escalation <minutes> [fallback_chat_id]
+    i.e. /escalation 30 -1001234567890
+    use /escalation off to stop and /escalation to display the current setting
+repo-cmd-codeowners =
+    set the owners mentioned when a job that changed their files fails, in CODEOWNERS format with telegram usernames on the lines after the command:
+    /codeowners
+    /libs/shared/ @alice @bob
+    {"*"}.sql @carol
+    use /codeowners clear to remove the rules and /codeowners to display them
+repo-cmd-template =
+    customize a notification of current repo with {"{{"}repo{"}}"}, {"{{"}status{"}}"}, {"{{"}elapsed{"}}"}, {"{{"}by{"}}"}, {"{{"}url{"}}"}, {"{{"}branch{"}}"}, {"{{"}sha{"}}"}, {"{{"}description{"}}"}, {"{{"}flaky{"}}"} and {"{{"}eta{"}}"} in the following format: /template <created|success|failure|cancelled> <template>
+    i.e. /template failure 💥 {"{{"}repo{"}}"} broke {"{{"}branch{"}}"} at {"{{"}sha{"}}"}, see {"{{"}url{"}}"}
+    use /template <kind> to preview it against the latest job, /template <kind> reset to restore the default and /template to display templates
+repo-cmd-link_me = { general-cmd-link_me }
+repo-cmd-unlink_me = { general-cmd-unlink_me }
+repo-cmd-mentions = { general-cmd-mentions }
+repo-cmd-notify_me = { general-cmd-notify_me }
+repo-cmd-language = { general-cmd-language }
+repo-cmd-timezone = { general-cmd-timezone }
+repo-cmd-grant =
+    grant a role of current repo to the user of the replied message or by user id in the following format: /grant <owner|maintainer|viewer> [user_id]
+    i.e. /grant maintainer
+repo-cmd-revoke = revoke the granted role of the user of the replied message or by user id in the following format: /revoke [user_id]
+repo-cmd-roles = display roles granted for current repo.
+repo-cmd-audit =
+    display who changed the configuration of current repo, the latest first, in the following format: /audit [page]
+    i.e. /audit 2
+repo-cmd-delete = delete selected repo.
+repo-cmd-cancel = deselect current repo for manipulation.
@@ -0,0 +1,350 @@
+# Thai messages of the bot. A message missing here is shown in English
+
+## durations
+
+duration-seconds = { $count } วินาที
+duration-minutes = { $count } นาที
+duration-hours = { $count } ชั่วโมง
+duration-days = { $count } วัน
+eta-minutes = ~{ $minutes } นาที
+eta-hours = ~{ $hours } ชม. { $minutes } นาที
+time-just-now = เมื่อสักครู่
+time-ago = { $duration } ที่แล้ว
+progress-estimate = รันมาแล้ว { $running } เสร็จไป { $percent }% (คาดว่าอีก { $eta })
+progress-overdue = รันมาแล้ว { $running } นานกว่าปกติ ({ $eta })
+progress-unknown = รันมาแล้ว { $running } ยังไม่มีเวลาโดยประมาณ
+
+## job notifications
+
+job-running = 🚧 job ของ { $repo } กำลังรัน...
+job-success = ✅ job ของ { $repo } เสร็จสมบูรณ์
+job-flaky-failure = 🎲 job ของ { $repo } ล้มเหลวแบบ flaky
+job-failure = 🚨 job ของ { $repo } ล้มเหลว
+job-cancelled = ⛔️ job ของ { $repo } ถูกยกเลิก
+job-eta = คาดว่าใช้เวลา { $eta }
+job-elapsed = ใช้เวลา: { $elapsed }
+job-by = โดย:
+job-link = ลิงก์:
+job-slowdown = ⚠️ ช้ากว่าปกติ { $ratio }×
+job-owners = เจ้าของ: { $owners }
+email-failure-subject = [{ $repo }] job #{ $job } ล้มเหลว
+email-elapsed = ใช้เวลา
+email-by = โดย
+email-link = ลิงก์
+email-digest-subject = [{ $repo }] สรุป CI ประจำวัน
+email-digest-title = สรุปประจำวันของ { $repo }
+email-jobs = job
+email-success = สำเร็จ
+email-failure = ล้มเหลว
+email-cancelled = ยกเลิก
+email-running = กำลังรัน
+email-pass-rate = อัตราการผ่าน
+email-failed-jobs = job ที่ล้มเหลว
+email-failed-by = โดย { $name }
+
+## job listings
+
+job-took = ใช้เวลา { $duration }
+job-running-for = รันมาแล้ว { $duration }
+job-triggered-by = โดย { $name }
+job-link-text = ลิงก์
+today-header = job ของวันนี้
+today-empty = วันนี้ยังไม่มี job เริ่มรัน job เพื่อดูที่นี่
+latest-header = job ล่าสุด
+latest-empty = ยังไม่มี job ล่าสุด เริ่มรัน job เพื่อดูที่นี่
+running-empty = ไม่มี job ที่กำลังรัน เริ่มรัน job เพื่อดูที่นี่
+running-unacknowledged = ความล้มเหลวที่ยังไม่มีผู้รับเรื่อง:
+running-failure = #{ $job } ล้มเหลวเมื่อ { $duration } ที่แล้ว
+browser-header = job ของ { $repo }
+browser-page = (หน้า { $page } จาก { $pages })
+browser-empty = ยังไม่เคยมี job รัน
+search-header = job ที่ตรงกับ { $query }
+search-header-all = ประวัติ job
+search-empty = ไม่มี job ที่ตรงกับการค้นหา
+search-gone-short = การค้นหานี้ไม่มีอยู่แล้ว
+
+## repos
+
+start = เริ่มต้นด้วยการตั้งค่า repo แรกของคุณ พิมพ์ /help เพื่อดูข้อมูลเพิ่มเติม
+help-header = คำสั่งที่ใช้ได้:
+invalid-command = คำสั่งไม่ถูกต้อง ดู /help สำหรับข้อมูลเพิ่มเติม
+repos-empty = ยังไม่มี repo พิมพ์ /help เพื่อเริ่มต้น
+picker-header = เลือก repo ที่ต้องการจัดการ:
+picker-header-page = เลือก repo ที่ต้องการจัดการ (หน้า { $page } จาก { $pages }):
+repo-created = เพิ่ม repo สำเร็จ: { $name }
+repo-name-taken = มี repo ชื่อ { $name } อยู่แล้ว ให้ใช้ชื่ออื่น
+repo-selecting = กำลังเลือก repo: { $name }
+repo-selected = เลือก repo { $name } แล้ว พิมพ์ /help เพื่อดูว่าทำอะไรกับมันได้บ้าง
+repo-missing = ไม่พบ repo ที่ต้องการ
+repo-gone = repo ที่เลือกไว้ไม่มีอยู่แล้ว ใช้ /list เพื่อเลือก repo อื่น
+repo-gone-short = repo นี้ไม่มีอยู่แล้ว
+info-name = ชื่อ: { $name }
+info-key = คีย์:
+rename-success = เปลี่ยนชื่อ repo สำเร็จ
+delete-confirm = ลบ repo { $repo } หรือไม่? กู้คืนได้ด้วย /undo ภายใน { $retention }
+delete-cancelled = ไม่มีอะไรถูกลบ
+confirm-delete = 🗑 ลบ
+confirm-keep = เก็บไว้
+delete-owner-required = เฉพาะ owner ของ repo นี้เท่านั้นที่ลบได้
+delete-success = 🗑 ลบ repo { $repo } แล้ว ใช้ /undo ภายใน { $retention } เพื่อกู้คืน
+delete-already = repo นี้ถูกลบไปแล้ว
+reset-owner-required = เฉพาะ owner ของทุก repo ในแชทนี้เท่านั้นที่รีเซ็ตได้
+reset-confirm = [DEBUG] ลบทุก repo ของแชทนี้หรือไม่? กู้คืนได้ด้วย /undo ภายใน { $retention }
+reset-success = [DEBUG] ลบ { $count } repo แล้ว ใช้ /undo ภายใน { $retention } เพื่อกู้คืน
+undo-owner-required = เฉพาะ owner ของ repo ที่ถูกลบเท่านั้นที่กู้คืนได้
+undo-empty = ไม่มีอะไรให้ย้อนกลับ
+undo-restored = กู้คืน repo สำเร็จ: { $names }
+undo-taken = กู้คืน { $names } ไม่ได้ เพราะมี repo ชื่อเดียวกันถูกสร้างขึ้นแล้ว เปลี่ยนชื่อ repo นั้นแล้ว /undo อีกครั้ง
+dora-all-repos = ทุก repo
+dora-header = 📈 DORA metrics ของ { $title } ในช่วง { $days } วันที่ผ่านมา
+dora-frequency = ความถี่ในการ deploy: { $frequency }/วัน ({ $deployments } ครั้ง)
+dora-lead-time = lead time ของการเปลี่ยนแปลง: { $lead_time }
+dora-failure-rate = อัตราการเปลี่ยนแปลงที่ล้มเหลว: { $rate }
+dora-restore = เวลาในการกู้คืนบริการ: { $restore } (ล้มเหลว { $failures } ครั้ง กู้คืน { $recoveries } ครั้ง)
+dora-none = ไม่มีข้อมูล
+
+## roles
+
+role-required = คำสั่งนี้ต้องมีบทบาท { $required } แต่คุณเป็น { $role } ของ repo นี้
+role-own = คุณเปลี่ยนบทบาทของตัวเองไม่ได้
+role-granted = มอบบทบาท { $role } ให้ { $name } สำเร็จ
+role-revoked = ถอนบทบาทของ { $name } สำเร็จ
+role-none = { $name } ไม่มีบทบาทที่ได้รับมอบ
+roles-footer = ผู้ที่ไม่ได้รับมอบบทบาทจะเป็น owner หากเป็นผู้สร้างแชท เป็น maintainer หากเป็นผู้ดูแลแชท และเป็น viewer ในกรณีอื่น
+
+## notifiers and emails
+
+notifier-added = เพิ่ม notifier { $kind } สำเร็จ
+notifiers-empty = ยังไม่มี notifier พิมพ์ /help เพื่อดูวิธีเพิ่ม
+notifier-removed = ลบ notifier สำเร็จ
+notifier-missing = ไม่พบ notifier ที่ต้องการ
+email-not-configured = บอทนี้ยังไม่ได้ตั้งค่าอีเมล ให้ผู้ดูแลบอทตั้งค่า SMTP_HOST และ SMTP_FROM ก่อน
+email-subscribed = สมัครรับอีเมล { $subscription } ให้ { $address } สำเร็จ
+emails-empty = ยังไม่มีอีเมลที่สมัครรับ พิมพ์ /help เพื่อดูวิธีเพิ่ม
+email-missing = ไม่พบอีเมลที่ต้องการ
+email-unsubscribed = ยกเลิกการรับอีเมลของ { $address } สำเร็จ
+
+## repo settings
+
+chart-empty = ไม่มี job ที่รันในช่วงเวลานี้
+flaky-none = 🎲 ไม่มี job ที่ล้มเหลวในช่วงนี้
+flaky-rate = 🎲 { $flaky } จาก { $failures } job ที่ล้มเหลวเป็นแบบ flaky ({ $percent }%)
+flaky-tests = test ที่ flaky ที่สุด:
+flaky-test = { $name } (flaky { $flakes } ครั้ง)
+audit-empty = ยังไม่มีการเปลี่ยนแปลงของ repo นี้หรือแชทนี้ที่ถูกบันทึกไว้
+audit-no-page = ไม่มีหน้า { $page }
+audit-header = 📜 บันทึกการเปลี่ยนแปลง (หน้า { $page } จาก { $pages })
+audit-older = ใช้ /audit { $page } เพื่อดูการเปลี่ยนแปลงที่เก่ากว่า
+slowdown-off = ปิดการแจ้งเตือนเมื่อ job ช้าอยู่
+slowdown-threshold = job ที่ช้ากว่าปกติ { $threshold }× จะถูกแจ้งเตือน
+slowdown-updated = ปรับเกณฑ์การแจ้งเตือนเมื่อ job ช้าสำเร็จ
+oncall-empty = ยังไม่มีผู้ใช้ on-call พิมพ์ /help เพื่อดูวิธีเพิ่ม
+oncall-added = เพิ่ม @{ $username } เข้า on-call สำเร็จ
+oncall-missing = @{ $username } ไม่ได้อยู่ใน on-call
+oncall-removed = นำ @{ $username } ออกจาก on-call สำเร็จ
+escalation-off = ปิดการส่งต่อความล้มเหลวอยู่
+escalation-oncall = ความล้มเหลวที่ไม่มีผู้รับเรื่องจะถูกส่งต่อให้ผู้ใช้ on-call หลังจาก { $minutes } นาที
+escalation-fallback = ความล้มเหลวที่ไม่มีผู้รับเรื่องจะถูกส่งต่อให้ผู้ใช้ on-call หลังจาก { $minutes } นาที และส่งต่อไปยังแชท { $chat } หลังจากนั้นอีก { $minutes } นาที
+escalation-updated = ปรับการส่งต่อความล้มเหลวสำเร็จ
+codeowners-empty = repo นี้ยังไม่มีกฎความเป็นเจ้าของ
+codeowners-rules =
+    กฎความเป็นเจ้าของของ repo นี้:
+    { $rules }
+codeowners-updated = ปรับกฎความเป็นเจ้าของสำเร็จ
+templates-empty = repo นี้ยังไม่มี template การแจ้งเตือนใช้ข้อความเริ่มต้น
+templates-header = template ของ repo นี้:
+template-missing = repo นี้ยังไม่มี template { $kind }
+template-no-job = ยังไม่มี job ที่รันเพื่อใช้แสดงตัวอย่าง template
+template-blank = template นี้ว่างเปล่าสำหรับ job ล่าสุด จึงจะใช้ข้อความเริ่มต้นแทน
+template-set = ตั้ง template { $kind } สำเร็จ
+template-set-preview = {" "}job ล่าสุดจะแสดงแบบนี้:
+template-reset = การแจ้งเตือน { $kind } กลับมาใช้ข้อความเริ่มต้นแล้ว
+
+## digests
+
+digests-empty = ยังไม่มีสรุปที่ตั้งเวลาไว้ พิมพ์ /help เพื่อดูวิธีตั้งเวลา
+digest-line = { $period }: { $schedule } ({ $timezone }) ครั้งถัดไป { $next }
+digest-never = ไม่มี
+digest-none = ยังไม่มีสรุปที่ตั้งเวลาไว้
+digest-stopped = หยุดส่งสรุปสำเร็จ
+digest-scheduled = ตั้งเวลาสรุป { $period } สำเร็จ สรุปครั้งถัดไป { $next }
+digest-daily-title = 📊 สรุปประจำวัน
+digest-weekly-title = 📊 สรุปประจำสัปดาห์
+digest-no-job = ไม่มี job ที่รันตั้งแต่การสรุปครั้งก่อน
+digest-jobs = job ที่รัน: { $total } (✅ { $success } 🚨 { $failure } ⛔️ { $cancelled } 🚧 { $running })
+digest-pass-rate = อัตราการผ่าน: { $rate }%
+digest-slowest = job ที่ช้าที่สุด:
+digest-red-streak = ล้มเหลวติดต่อกันนานที่สุด: { $repo } ({ $length } ครั้งติดกัน)
+digest-committers = ผู้รัน job มากที่สุด:
+digest-committer = { $name } ({ $count } job)
+
+## identities
+
+link-success = เชื่อม { $ci_username } กับ { $name } สำเร็จ เริ่มแชทส่วนตัวกับบอทหากต้องการรับแจ้งความล้มเหลวด้วย /mentions dm
+link-taken = { $ci_username } ถูกเชื่อมกับ { $name } ในแชทนี้อยู่แล้ว ต้องให้เขาหรือเจ้าของแชท /unlink_me ก่อน
+link-success-private = เชื่อม { $ci_username } กับ { $name } สำเร็จ ใช้ /notify_me failures หรือ /notify_me all เพื่อรับ job ของ { $ci_username } จากทุก repo ที่นี่
+link-taken-private = { $ci_username } ถูกเชื่อมกับ { $name } ในแชทอื่นอยู่แล้ว ให้เชื่อมในแชทของ repo แทน
+link-other = คุณเชื่อมกับ { $ci_username } ในแชทนี้อยู่แล้ว ใช้ /unlink_me { $ci_username } ก่อน
+unlink-missing = { $ci_username } ไม่ได้เชื่อมในแชทนี้
+unlink-owner-required = { $ci_username } เชื่อมกับ { $name } อยู่ มีเพียงเขาหรือเจ้าของแชทที่ยกเลิกการเชื่อมได้
+unlink-success = ยกเลิกการเชื่อม { $ci_username } สำเร็จ
+identity-missing = ยังไม่มีชื่อผู้ใช้ CI ที่เชื่อมกับคุณ ใช้ /link_me <ci_username> ก่อน
+mentions-set = ตั้งค่าการกล่าวถึงเป็น { $mode } สำเร็จ
+notify-private = เริ่มแชทส่วนตัวกับบอทแล้วส่ง /notify_me ในแชทนั้นเพื่อให้บอทส่งข้อความหาคุณได้
+notify-set = ตั้งค่าการแจ้งเตือน job เป็น { $preference } สำเร็จ
+
+## acknowledgements
+
+ack-on-it = 🙋 { $name } รับเรื่องแล้ว: { $repo } #{ $job }
+ack-thanks = ขอบคุณ ความล้มเหลวนี้เป็นของคุณแล้ว
+ack-already = { $name } รับเรื่องนี้ไปแล้ว
+ack-resolved = ความล้มเหลวนี้ได้รับการแก้ไขแล้ว
+ack-not-found = ความล้มเหลวนี้ไม่มีอยู่แล้ว
+ack-button = 🙋 รับเรื่อง #{ $job }
+escalated-oncall = ⏰ { $repo } #{ $job } ล้มเหลวเมื่อ { $duration } ที่แล้วและยังไม่มีใครรับเรื่อง
+escalated-fallback = ⏰ { $repo } #{ $job } ล้มเหลวเมื่อ { $duration } ที่แล้วและยังไม่มีผู้รับเรื่อง
+
+## languages
+
+language-current = แชทนี้ใช้ภาษาไทย ใช้ /language en เพื่อเปลี่ยนเป็นภาษาอังกฤษ
+language-set = แชทนี้จะใช้ภาษาไทยตั้งแต่นี้ไป
+
+## timezones
+
+timezone-current = แชทนี้ใช้เวลา { $timezone } ใช้ /timezone <timezone> เพื่อเปลี่ยน
+timezone-set = แชทนี้จะใช้เวลา { $timezone } ตั้งแต่นี้ไป
+
+## invalid arguments
+
+invalid-user = ผู้ใช้ไม่ถูกต้อง: { $error }
+invalid-role = บทบาทไม่ถูกต้อง: { $error }
+invalid-page = หน้าไม่ถูกต้อง: { $error }
+invalid-notifier = notifier ไม่ถูกต้อง: { $error }
+invalid-email = อีเมลไม่ถูกต้อง: { $error }
+invalid-subscription = ประเภทการสมัครรับไม่ถูกต้อง: { $error }
+invalid-window = ช่วงเวลาไม่ถูกต้อง: { $error }
+invalid-chart = กราฟไม่ถูกต้อง: { $error }
+invalid-threshold = เกณฑ์ไม่ถูกต้อง: { $error }
+invalid-oncall = on-call ไม่ถูกต้อง: { $error }
+invalid-escalation = การส่งต่อไม่ถูกต้อง: { $error }
+invalid-codeowners = กฎความเป็นเจ้าของไม่ถูกต้อง: { $error }
+invalid-template = template ไม่ถูกต้อง: { $error }
+invalid-digest = กำหนดเวลาสรุปไม่ถูกต้อง: { $error }
+invalid-ci-username = ชื่อผู้ใช้ CI ไม่ถูกต้อง: { $error }
+invalid-mentions = การตั้งค่าการกล่าวถึงไม่ถูกต้อง: { $error }
+invalid-notify = การตั้งค่าการแจ้งเตือนไม่ถูกต้อง: { $error }
+invalid-language = ภาษาไม่ถูกต้อง: { $error }
+invalid-timezone = เขตเวลาไม่ถูกต้อง: { $error }
+invalid-search = การค้นหาไม่ถูกต้อง: { $error }
+
+## descriptions of the commands of a chat without a selected repo
+general-cmd-help = แสดงข้อความนี้
+general-cmd-list = แสดง repo ทั้งหมดเพื่อเลือก
+general-cmd-today = แสดง job ทั้งหมดที่สร้างวันนี้
+general-cmd-history =
+    ดูประวัติ job ของทุก repo โดยกรองได้ในรูปแบบ: /history [status:<status>] [by:<ci_username>] [branch:<branch>] [since:<YYYY-MM-DD>] [until:<YYYY-MM-DD>]
+    เช่น /history status:failure by:alice branch:production
+general-cmd-search =
+    ค้นหา job ของทุก repo จากข้อความในคำอธิบาย และกรองแบบเดียวกับ /history ได้ในรูปแบบ: /search <text> [filters]
+    เช่น /search deploy status:failure since:2022-12-01
+general-cmd-create =
+    สร้าง repo ใหม่ในรูปแบบ: /create <repo_name>
+    เช่น /create Turbo Incubator Prototype
+general-cmd-select_repo =
+    เลือก repo ที่ต้องการจัดการจากรายการ หรือจากลำดับในรายการในรูปแบบ: /select_repo [index]
+    เช่น /select_repo 1
+general-cmd-digest =
+    ตั้งเวลาสรุปของทุก repo ในรูปแบบ: /digest daily <HH:MM> [timezone] หรือ /digest weekly <weekday> <HH:MM> [timezone]
+    เช่น /digest daily 09:00 Asia/Bangkok
+    เขตเวลาเริ่มต้นเป็นของแชทนี้ ใช้ /digest off [daily|weekly] เพื่อหยุด และ /digest เพื่อแสดงกำหนดเวลา
+general-cmd-dora =
+    แสดงค่า DORA ของทุก repo ในช่วงเวลาหนึ่งในรูปแบบ: /dora [<n>d|<n>w]
+    เช่น /dora 30d
+general-cmd-link_me =
+    เชื่อมชื่อผู้ใช้ CI กับบัญชี telegram เพื่อถูกกล่าวถึงเมื่อ repo ของแชทนี้ล้มเหลวในรูปแบบ: /link_me <ci_username>
+    เช่น /link_me octocat
+general-cmd-unlink_me = ยกเลิกการเชื่อมชื่อผู้ใช้ CI ของคุณ หรือของใครก็ได้หากเป็นเจ้าของแชท ในรูปแบบ: /unlink_me <ci_username>
+general-cmd-mentions = เลือกวิธีรับแจ้งเมื่อ job ของคุณล้มเหลวในรูปแบบ: /mentions <on|off|dm>
+general-cmd-notify_me = รับข้อความส่วนตัวเมื่อ job ของคุณเสร็จในรูปแบบ: /notify_me <failures|all|off>
+general-cmd-language =
+    แสดงภาษาของแชทนี้ หรือเปลี่ยนในรูปแบบ: /language <en|th>
+    เช่น /language en
+general-cmd-timezone =
+    แสดงเขตเวลาของแชทนี้ที่ใช้กับ /today และเวลาที่แสดงทั้งหมด หรือเปลี่ยนในรูปแบบ: /timezone <timezone>
+    เช่น /timezone Asia/Bangkok
+general-cmd-undo = กู้คืน repo ที่ถูกลบล่าสุด
+general-cmd-reset = [DEBUG] รีเซ็ตสถานะทั้งหมด
+
+## descriptions of the commands of a chat with a selected repo
+repo-cmd-help = { general-cmd-help }
+repo-cmd-get_info = แสดงข้อมูลของ repo ปัจจุบัน
+repo-cmd-today = แสดง job ทั้งหมดของ repo ปัจจุบันที่สร้างวันนี้
+repo-cmd-running = แสดง job ที่กำลังรันทั้งหมดของ repo ปัจจุบัน
+repo-cmd-latest = แสดง job ล่าสุดของ repo นี้
+repo-cmd-jobs = ดูประวัติ job ของ repo ปัจจุบัน
+repo-cmd-history =
+    ดูประวัติ job ของ repo ปัจจุบัน โดยกรองได้ในรูปแบบ: /history [status:<status>] [by:<ci_username>] [branch:<branch>] [since:<YYYY-MM-DD>] [until:<YYYY-MM-DD>]
+    เช่น /history status:failure by:alice branch:production
+repo-cmd-search =
+    ค้นหา job ของ repo ปัจจุบันจากข้อความในคำอธิบาย และกรองแบบเดียวกับ /history ได้ในรูปแบบ: /search <text> [filters]
+    เช่น /search deploy status:failure since:2022-12-01
+repo-cmd-rename = เปลี่ยนชื่อ repo ปัจจุบัน
+repo-cmd-add_notifier =
+    เพิ่ม notifier ให้ repo ปัจจุบันในรูปแบบ: /add_notifier <slack|discord|webhook> <https url> [token]
+    เช่น /add_notifier slack https://hooks.slack.com/services/T000/B000/XXXX
+    หรือ /add_notifier matrix <homeserver> <room_id> <access_token>
+repo-cmd-notifiers = แสดง notifier ทั้งหมดของ repo ปัจจุบัน
+repo-cmd-remove_notifier =
+    ลบ notifier ตาม id ในรูปแบบ: /remove_notifier <id>
+    เช่น /remove_notifier 1
+repo-cmd-add_email =
+    สมัครรับอีเมลของ repo ปัจจุบันในรูปแบบ: /add_email <address> [failure|digest|all]
+    เช่น /add_email lead@example.com digest
+repo-cmd-emails = แสดงอีเมลทั้งหมดที่สมัครรับ repo ปัจจุบัน
+repo-cmd-remove_email = ยกเลิกการรับอีเมลของ repo ปัจจุบันในรูปแบบ: /remove_email <address>
+repo-cmd-dora =
+    แสดงค่า DORA ของ repo ปัจจุบันในช่วงเวลาหนึ่งในรูปแบบ: /dora [<n>d|<n>w]
+    เช่น /dora 30d
+repo-cmd-chart =
+    สร้างกราฟของ repo ปัจจุบันในรูปแบบ: /chart <duration|passrate|heatmap> [<n>d|<n>w]
+    เช่น /chart duration 14d
+repo-cmd-slowdown =
+    แจ้งเตือนเมื่อ job ช้ากว่าปกติตามอัตราส่วนที่กำหนดในรูปแบบ: /slowdown <ratio|off>
+    เช่น /slowdown 2.5
+    ใช้ /slowdown เพื่อแสดงเกณฑ์ปัจจุบัน
+repo-cmd-flaky =
+    แสดง job ที่ flaky และเทสต์ที่ flaky ที่สุดของ repo ปัจจุบันในช่วงเวลาหนึ่งในรูปแบบ: /flaky [<n>d|<n>w]
+    เช่น /flaky 14d
+repo-cmd-oncall =
+    จัดการผู้ใช้ on-call ที่ถูกกล่าวถึงเมื่อความล้มเหลวไม่มีผู้รับเรื่องในรูปแบบ: /oncall add <@username> หรือ /oncall remove <@username>
+    ใช้ /oncall เพื่อแสดงผู้ใช้ on-call
+repo-cmd-escalation =
+    ส่งต่อความล้มเหลวที่ไม่มีผู้รับเรื่องหลังจากจำนวนนาทีที่กำหนดในรูปแบบ: /escalation <minutes> [fallback_chat_id]
+    เช่น /escalation 30 -1001234567890
+    ใช้ /escalation off เพื่อหยุด และ /escalation เพื่อแสดงการตั้งค่าปัจจุบัน
+repo-cmd-codeowners =
+    กำหนดเจ้าของที่ถูกกล่าวถึงเมื่อ job ที่แก้ไฟล์ของพวกเขาล้มเหลว ในรูปแบบ CODEOWNERS ด้วยชื่อผู้ใช้ telegram ในบรรทัดถัดจากคำสั่ง:
+    /codeowners
+    /libs/shared/ @alice @bob
+    {"*"}.sql @carol
+    ใช้ /codeowners clear เพื่อลบกฎ และ /codeowners เพื่อแสดงกฎ
+repo-cmd-template =
+    ปรับแต่งการแจ้งเตือนของ repo ปัจจุบันด้วย {"{{"}repo{"}}"}, {"{{"}status{"}}"}, {"{{"}elapsed{"}}"}, {"{{"}by{"}}"}, {"{{"}url{"}}"}, {"{{"}branch{"}}"}, {"{{"}sha{"}}"}, {"{{"}description{"}}"}, {"{{"}flaky{"}}"} และ {"{{"}eta{"}}"} ในรูปแบบ: /template <created|success|failure|cancelled> <template>
+    เช่น /template failure 💥 {"{{"}repo{"}}"} พัง {"{{"}branch{"}}"} ที่ {"{{"}sha{"}}"} ดู {"{{"}url{"}}"}
+    ใช้ /template <kind> เพื่อดูตัวอย่างกับ job ล่าสุด, /template <kind> reset เพื่อกลับไปใช้ค่าเริ่มต้น และ /template เพื่อแสดง template
+repo-cmd-link_me = { general-cmd-link_me }
+repo-cmd-unlink_me = { general-cmd-unlink_me }
+repo-cmd-mentions = { general-cmd-mentions }
+repo-cmd-notify_me = { general-cmd-notify_me }
+repo-cmd-language = { general-cmd-language }
+repo-cmd-timezone = { general-cmd-timezone }
+repo-cmd-grant =
+    มอบบทบาทของ repo ปัจจุบันให้ผู้ใช้ของข้อความที่ตอบกลับ หรือตาม user id ในรูปแบบ: /grant <owner|maintainer|viewer> [user_id]
+    เช่น /grant maintainer
+repo-cmd-revoke = ถอนบทบาทที่มอบให้ผู้ใช้ของข้อความที่ตอบกลับ หรือตาม user id ในรูปแบบ: /revoke [user_id]
+repo-cmd-roles = แสดงบทบาทที่มอบไว้ของ repo ปัจจุบัน
+repo-cmd-audit =
+    แสดงว่าใครเปลี่ยนการตั้งค่าของ repo ปัจจุบัน ล่าสุดก่อน ในรูปแบบ: /audit [page]
+    เช่น /audit 2
+repo-cmd-delete = ลบ repo ที่เลือก
+repo-cmd-cancel = ยกเลิกการเลือก repo ปัจจุบัน
@@ -0,0 +1,4 @@
+-- Add down migration script here
+DROP INDEX IF EXISTS notifier_repo;
+
+DROP TABLE IF EXISTS main.notifiers;
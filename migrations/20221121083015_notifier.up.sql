@@ -0,0 +1,15 @@
+-- Add up migration script here
+CREATE TABLE IF NOT EXISTS main.notifiers (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  kind TEXT CHECK (kind IN ('SLACK', 'DISCORD', 'MATRIX', 'WEBHOOK')) NOT NULL,
+  target TEXT NOT NULL,
+  room TEXT,
+  token TEXT,
+  repo_id TEXT NOT NULL,
+  FOREIGN KEY (repo_id) 
+   REFERENCES repos (id) 
+      ON DELETE CASCADE 
+      ON UPDATE NO ACTION
+);
+
+CREATE INDEX IF NOT EXISTS notifier_repo ON notifiers (repo_id);
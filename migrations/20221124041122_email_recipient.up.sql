@@ -0,0 +1,11 @@
+-- Add up migration script here
+CREATE TABLE IF NOT EXISTS main.email_recipients (
+  address TEXT NOT NULL,
+  subscription TEXT CHECK (subscription IN ('FAILURE', 'DIGEST', 'ALL')) NOT NULL DEFAULT 'ALL',
+  repo_id TEXT NOT NULL,
+  PRIMARY KEY (repo_id, address),
+  FOREIGN KEY (repo_id) 
+   REFERENCES repos (id) 
+      ON DELETE CASCADE 
+      ON UPDATE NO ACTION
+) WITHOUT ROWID;
@@ -0,0 +1,5 @@
+-- Add down migration script here
+DROP INDEX IF EXISTS outbox_chat;
+DROP INDEX IF EXISTS outbox_pending;
+
+DROP TABLE IF EXISTS main.outbox;
@@ -0,0 +1,20 @@
+-- Add up migration script here
+CREATE TABLE IF NOT EXISTS main.outbox (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  chat_id INTEGER NOT NULL,
+  event TEXT NOT NULL,
+  repo_id TEXT NOT NULL,
+  attempts INTEGER NOT NULL DEFAULT 0,
+  last_error TEXT,
+  created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+  next_attempt_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+  sent_at TIMESTAMP,
+  failed_at TIMESTAMP,
+  FOREIGN KEY (repo_id) 
+   REFERENCES repos (id) 
+      ON DELETE CASCADE 
+      ON UPDATE NO ACTION
+);
+
+CREATE INDEX IF NOT EXISTS outbox_pending ON outbox (sent_at, failed_at, next_attempt_at);
+CREATE INDEX IF NOT EXISTS outbox_chat ON outbox (chat_id, id);
@@ -0,0 +1,9 @@
+-- Add up migration script here
+CREATE TABLE IF NOT EXISTS main.digests (
+  chat_id INTEGER NOT NULL,
+  period TEXT CHECK (period IN ('DAILY', 'WEEKLY')) NOT NULL,
+  schedule TEXT NOT NULL,
+  timezone TEXT NOT NULL DEFAULT 'UTC',
+  last_run_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+  PRIMARY KEY (chat_id, period)
+) WITHOUT ROWID;
@@ -0,0 +1,2 @@
+-- Add down migration script here
+ALTER TABLE main.jobs DROP COLUMN committed_at;
@@ -0,0 +1,2 @@
+-- Add up migration script here
+ALTER TABLE main.jobs ADD COLUMN committed_at TIMESTAMP;
@@ -0,0 +1,5 @@
+-- Add down migration script here
+DROP INDEX IF EXISTS main.job_branch_started_date;
+
+ALTER TABLE main.repos DROP COLUMN slowdown_threshold;
+ALTER TABLE main.jobs DROP COLUMN branch;
@@ -0,0 +1,5 @@
+-- Add up migration script here
+ALTER TABLE main.jobs ADD COLUMN branch TEXT;
+ALTER TABLE main.repos ADD COLUMN slowdown_threshold REAL DEFAULT 2.0;
+
+CREATE INDEX IF NOT EXISTS job_branch_started_date ON jobs (repo_id, branch, started_at);
@@ -0,0 +1,5 @@
+-- Add down migration script here
+DROP TABLE IF EXISTS main.test_results;
+DROP INDEX IF EXISTS main.job_commit_sha;
+
+ALTER TABLE main.jobs DROP COLUMN commit_sha;
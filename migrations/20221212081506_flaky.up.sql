@@ -0,0 +1,18 @@
+-- Add up migration script here
+ALTER TABLE main.jobs ADD COLUMN commit_sha TEXT;
+
+CREATE INDEX IF NOT EXISTS job_commit_sha ON jobs (repo_id, commit_sha);
+
+CREATE TABLE IF NOT EXISTS main.test_results (
+  repo_id TEXT NOT NULL,
+  job_id INTEGER NOT NULL,
+  name TEXT NOT NULL,
+  outcome TEXT CHECK (outcome IN ('PASSED', 'FAILED', 'SKIPPED')) NOT NULL,
+  PRIMARY KEY (repo_id, job_id, name),
+  FOREIGN KEY (repo_id) 
+   REFERENCES repos (id) 
+      ON DELETE CASCADE 
+      ON UPDATE NO ACTION
+) WITHOUT ROWID;
+
+CREATE INDEX IF NOT EXISTS test_result_name ON test_results (repo_id, name, outcome);
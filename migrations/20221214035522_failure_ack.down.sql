@@ -0,0 +1,6 @@
+-- Add down migration script here
+DROP TABLE IF EXISTS main.oncall_users;
+DROP TABLE IF EXISTS main.failure_acks;
+
+ALTER TABLE main.repos DROP COLUMN fallback_chat_id;
+ALTER TABLE main.repos DROP COLUMN ack_timeout;
@@ -0,0 +1,31 @@
+-- Add up migration script here
+ALTER TABLE main.repos ADD COLUMN ack_timeout INTEGER DEFAULT 30;
+ALTER TABLE main.repos ADD COLUMN fallback_chat_id INTEGER;
+
+CREATE TABLE IF NOT EXISTS main.failure_acks (
+  job_id INTEGER PRIMARY KEY,
+  repo_id TEXT NOT NULL,
+  failed_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+  acked_by INTEGER,
+  acked_by_name TEXT,
+  acked_at TIMESTAMP,
+  resolved_at TIMESTAMP,
+  escalation_level INTEGER NOT NULL DEFAULT 0,
+  escalated_at TIMESTAMP,
+  FOREIGN KEY (repo_id) 
+   REFERENCES repos (id) 
+      ON DELETE CASCADE 
+      ON UPDATE NO ACTION
+);
+
+CREATE INDEX IF NOT EXISTS failure_ack_open ON failure_acks (repo_id, acked_at, resolved_at);
+
+CREATE TABLE IF NOT EXISTS main.oncall_users (
+  repo_id TEXT NOT NULL,
+  username TEXT NOT NULL,
+  PRIMARY KEY (repo_id, username),
+  FOREIGN KEY (repo_id) 
+   REFERENCES repos (id) 
+      ON DELETE CASCADE 
+      ON UPDATE NO ACTION
+) WITHOUT ROWID;
@@ -0,0 +1,10 @@
+-- Add up migration script here
+CREATE TABLE IF NOT EXISTS main.identities (
+  ci_username TEXT PRIMARY KEY COLLATE NOCASE,
+  user_id INTEGER NOT NULL,
+  name TEXT NOT NULL,
+  mention TEXT CHECK (mention IN ('MENTION', 'OFF', 'DM')) NOT NULL DEFAULT 'MENTION',
+  linked_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL
+) WITHOUT ROWID;
+
+CREATE INDEX IF NOT EXISTS identity_user ON identities (user_id);
@@ -0,0 +1,2 @@
+-- Add up migration script here
+ALTER TABLE main.identities ADD COLUMN notify TEXT CHECK (notify IN ('FAILURES', 'ALL', 'OFF')) NOT NULL DEFAULT 'OFF';
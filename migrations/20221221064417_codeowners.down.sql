@@ -0,0 +1,2 @@
+-- Add down migration script here
+ALTER TABLE main.repos DROP COLUMN codeowners;
@@ -0,0 +1,2 @@
+-- Add up migration script here
+ALTER TABLE main.repos ADD COLUMN codeowners TEXT;
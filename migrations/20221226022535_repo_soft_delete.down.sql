@@ -0,0 +1,3 @@
+-- Add down migration script here
+DROP INDEX IF EXISTS repo_deleted;
+ALTER TABLE main.repos DROP COLUMN deleted_at;
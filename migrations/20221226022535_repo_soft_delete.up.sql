@@ -0,0 +1,4 @@
+-- Add up migration script here
+ALTER TABLE main.repos ADD COLUMN deleted_at TIMESTAMP;
+
+CREATE INDEX IF NOT EXISTS repo_deleted ON repos (message_id, deleted_at);
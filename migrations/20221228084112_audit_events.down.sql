@@ -0,0 +1,3 @@
+-- Add down migration script here
+DROP INDEX IF EXISTS audit_event_repo;
+DROP TABLE IF EXISTS main.audit_events;
@@ -0,0 +1,15 @@
+-- Add up migration script here
+-- events outlive the repo they are about so repo_id is not a foreign key
+CREATE TABLE IF NOT EXISTS main.audit_events (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  repo_id TEXT,
+  chat_id INTEGER NOT NULL,
+  actor_id INTEGER,
+  actor_name TEXT NOT NULL,
+  command TEXT NOT NULL,
+  old_value TEXT,
+  new_value TEXT,
+  created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS audit_event_repo ON audit_events (repo_id, id);
@@ -0,0 +1,11 @@
+-- Add up migration script here
+CREATE TABLE IF NOT EXISTS main.repo_templates (
+  repo_id TEXT NOT NULL,
+  kind TEXT CHECK (kind IN ('CREATED', 'SUCCESS', 'FAILURE', 'CANCELLED')) NOT NULL,
+  template TEXT NOT NULL,
+  PRIMARY KEY (repo_id, kind),
+  FOREIGN KEY (repo_id) 
+   REFERENCES repos (id) 
+      ON DELETE CASCADE 
+      ON UPDATE NO ACTION
+) WITHOUT ROWID;
@@ -0,0 +1,5 @@
+-- Add up migration script here
+CREATE TABLE IF NOT EXISTS main.chat_settings (
+  chat_id INTEGER PRIMARY KEY NOT NULL,
+  language TEXT CHECK (language IN ('EN', 'TH')) NOT NULL DEFAULT 'EN'
+);
@@ -0,0 +1,2 @@
+-- Add down migration script here
+ALTER TABLE main.chat_settings DROP COLUMN timezone;
@@ -0,0 +1,2 @@
+-- Add up migration script here
+ALTER TABLE main.chat_settings ADD COLUMN timezone TEXT NOT NULL DEFAULT 'UTC';
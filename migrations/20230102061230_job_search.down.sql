@@ -0,0 +1,7 @@
+-- Add down migration script here
+DROP INDEX IF EXISTS main.job_status_started_date;
+DROP INDEX IF EXISTS main.job_created_by;
+CREATE INDEX IF NOT EXISTS job_created_by ON jobs (repo_id, triggered_by);
+
+DROP INDEX IF EXISTS main.job_search_chat;
+DROP TABLE IF EXISTS main.job_searches;
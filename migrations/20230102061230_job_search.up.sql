@@ -0,0 +1,19 @@
+-- Add up migration script here
+-- searches are kept so the buttons of their results can page through them
+CREATE TABLE IF NOT EXISTS main.job_searches (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  chat_id INTEGER NOT NULL,
+  repo_id TEXT,
+  query TEXT NOT NULL,
+  created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+  FOREIGN KEY (repo_id)
+   REFERENCES repos (id)
+      ON DELETE CASCADE
+      ON UPDATE NO ACTION
+);
+
+CREATE INDEX IF NOT EXISTS job_search_chat ON job_searches (chat_id, id);
+
+DROP INDEX IF EXISTS main.job_created_by;
+CREATE INDEX IF NOT EXISTS job_created_by ON jobs (repo_id, triggered_by, started_at);
+CREATE INDEX IF NOT EXISTS job_status_started_date ON jobs (repo_id, status, started_at);
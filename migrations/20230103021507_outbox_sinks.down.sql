@@ -0,0 +1,6 @@
+-- Add down migration script here
+DROP INDEX IF EXISTS main.outbox_sink;
+
+DELETE FROM main.outbox WHERE sink <> 'TELEGRAM';
+ALTER TABLE main.outbox DROP COLUMN notifier_id;
+ALTER TABLE main.outbox DROP COLUMN sink;
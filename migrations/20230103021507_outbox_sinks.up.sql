@@ -0,0 +1,7 @@
+-- Add up migration script here
+-- every sink of a job event has its own row so that each one is retried or abandoned on its own
+ALTER TABLE main.outbox ADD COLUMN sink TEXT CHECK (sink IN ('TELEGRAM', 'NOTIFIER', 'EMAIL')) NOT NULL DEFAULT 'TELEGRAM';
+-- a notifier removed before its rows were delivered has nothing left to deliver to
+ALTER TABLE main.outbox ADD COLUMN notifier_id INTEGER;
+
+CREATE INDEX IF NOT EXISTS outbox_sink ON outbox (sink, chat_id, id);
@@ -0,0 +1,23 @@
+-- Add down migration script here
+ALTER TABLE main.identities RENAME TO identities_chat;
+
+CREATE TABLE IF NOT EXISTS main.identities (
+  ci_username TEXT PRIMARY KEY COLLATE NOCASE,
+  user_id INTEGER NOT NULL,
+  name TEXT NOT NULL,
+  mention TEXT CHECK (mention IN ('MENTION', 'OFF', 'DM')) NOT NULL DEFAULT 'MENTION',
+  linked_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+  notify TEXT CHECK (notify IN ('FAILURES', 'ALL', 'OFF')) NOT NULL DEFAULT 'OFF'
+) WITHOUT ROWID;
+
+-- a username linked in several chats keeps its oldest link
+INSERT OR IGNORE INTO main.identities
+(ci_username, user_id, name, mention, linked_at, notify)
+SELECT ci_username, user_id, name, mention, linked_at, notify
+FROM main.identities_chat
+ORDER BY linked_at;
+
+DROP INDEX IF EXISTS main.identity_user;
+DROP TABLE main.identities_chat;
+
+CREATE INDEX IF NOT EXISTS identity_user ON identities (user_id);
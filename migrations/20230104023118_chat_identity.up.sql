@@ -0,0 +1,35 @@
+-- Add up migration script here
+-- a link only reaches the repos of the chat it was made in, and a user has one link per chat
+ALTER TABLE main.identities RENAME TO identities_global;
+
+CREATE TABLE IF NOT EXISTS main.identities (
+  chat_id INTEGER NOT NULL,
+  ci_username TEXT NOT NULL COLLATE NOCASE,
+  user_id INTEGER NOT NULL,
+  name TEXT NOT NULL,
+  mention TEXT CHECK (mention IN ('MENTION', 'OFF', 'DM')) NOT NULL DEFAULT 'MENTION',
+  notify TEXT CHECK (notify IN ('FAILURES', 'ALL', 'OFF')) NOT NULL DEFAULT 'OFF',
+  linked_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+  PRIMARY KEY (chat_id, ci_username),
+  UNIQUE (chat_id, user_id)
+) WITHOUT ROWID;
+
+-- an existing link is kept in the chats of the repos the username triggered jobs of
+INSERT OR IGNORE INTO main.identities
+(chat_id, ci_username, user_id, name, mention, notify, linked_at)
+SELECT DISTINCT repos.message_id,
+  identities_global.ci_username,
+  identities_global.user_id,
+  identities_global.name,
+  identities_global.mention,
+  identities_global.notify,
+  identities_global.linked_at
+FROM main.identities_global
+JOIN main.jobs ON identities_global.ci_username = jobs.triggered_by
+JOIN main.repos ON jobs.repo_id = repos.id
+ORDER BY identities_global.linked_at;
+
+DROP INDEX IF EXISTS main.identity_user;
+DROP TABLE main.identities_global;
+
+CREATE INDEX IF NOT EXISTS identity_user ON identities (user_id);
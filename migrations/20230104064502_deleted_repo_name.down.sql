@@ -0,0 +1,4 @@
+-- Add down migration script here
+UPDATE OR IGNORE main.repos SET name = substr(name, length(id) + 2) WHERE deleted_at IS NOT NULL;
+-- a deleted repo whose name was taken since cannot get it back
+DELETE FROM main.repos WHERE deleted_at IS NOT NULL AND name = id || ':' || substr(name, length(id) + 2);
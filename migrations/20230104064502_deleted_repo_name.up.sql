@@ -0,0 +1,3 @@
+-- Add up migration script here
+-- a deleted repo keeps its name behind its id so that a new repo can take the name
+UPDATE main.repos SET name = id || ':' || name WHERE deleted_at IS NOT NULL;
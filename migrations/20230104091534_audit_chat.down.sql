@@ -0,0 +1,2 @@
+-- Add down migration script here
+DROP INDEX IF EXISTS main.audit_event_chat;
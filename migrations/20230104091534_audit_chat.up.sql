@@ -0,0 +1,3 @@
+-- Add up migration script here
+-- changes to a chat rather than one of its repos are read along with the repos of the chat
+CREATE INDEX IF NOT EXISTS audit_event_chat ON audit_events (chat_id, id);
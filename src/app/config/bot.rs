@@ -0,0 +1,15 @@
+use teloxide::{
+    adaptors::{throttle::Limits, Throttle},
+    requests::RequesterExt,
+    Bot,
+};
+
+/// every request to telegram goes through the throttle adaptor so that the dispatcher and the
+/// outbox share a single view of telegram's per-chat and global rate limits
+pub type CiBot = Throttle<Bot>;
+
+/// setup a bot from `TELOXIDE_TOKEN`. This spawns the throttle worker so it has to be called
+/// within the tokio runtime
+pub fn init_bot() -> CiBot {
+    Bot::from_env().throttle(Limits::default())
+}
@@ -12,5 +12,5 @@ use sqlx::{Pool, Sqlite, SqlitePool};
 //         .expect("A valid connection manager")
 // }
 pub async fn init_sqlite() -> Result<Pool<Sqlite>, sqlx::Error> {
-    Ok(SqlitePool::connect(&*DATABASE_URL).await?)
+    SqlitePool::connect(&DATABASE_URL).await
 }
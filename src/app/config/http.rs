@@ -0,0 +1,18 @@
+use crate::app::util::error::ServiceError;
+use reqwest::{redirect::Policy, Client};
+use std::time::Duration;
+
+/// longest time a notifier request may take, so that an endpoint that never answers only holds
+/// back its own notifications
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// build the http client notifiers are sent with. Redirects are not followed because a
+/// notifier target is only checked before the request is made
+pub fn init_http_client() -> Result<Client, ServiceError> {
+    Ok(Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .connect_timeout(CONNECT_TIMEOUT)
+        .redirect(Policy::none())
+        .build()?)
+}
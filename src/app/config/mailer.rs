@@ -0,0 +1,73 @@
+use crate::{
+    app::util::error::ServiceError, SMTP_FROM, SMTP_HOST, SMTP_PASSWORD, SMTP_PORT, SMTP_SECURITY,
+    SMTP_USERNAME,
+};
+use lettre::{
+    message::Mailbox, transport::smtp::authentication::Credentials, AsyncSmtpTransport,
+    Tokio1Executor,
+};
+use std::time::Duration;
+
+/// longest time a command to the smtp server may take, so that a server that stops answering
+/// does not hold back the email notifications behind it for long
+const SMTP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// an smtp transport along with the mailbox every email is sent from
+#[derive(Clone)]
+pub struct Mailer {
+    pub transport: AsyncSmtpTransport<Tokio1Executor>,
+    pub from: Mailbox,
+}
+
+/// whether email notification is set up, `init_mailer` returns a mailer exactly when it is
+pub fn mailer_configured() -> bool {
+    SMTP_HOST.is_some()
+}
+
+/// build an smtp transport from env. email notification is an optional feature so `None` is
+/// returned when `SMTP_HOST` is not set. `SMTP_FROM` is checked here so that a missing sender
+/// fails the start of the app rather than the first email
+pub fn init_mailer() -> Result<Option<Mailer>, ServiceError> {
+    let Some(host) = &*SMTP_HOST else {
+        return Ok(None);
+    };
+    let from = SMTP_FROM
+        .as_deref()
+        .ok_or_else(|| ServiceError::ValidateFailure {
+            field: "SMTP_FROM",
+            reason: "expect SMTP_FROM to be set when SMTP_HOST is set i.e. CI Bot <ci@example.com>"
+                .to_string(),
+        })?
+        .parse::<Mailbox>()
+        .map_err(|e| ServiceError::ValidateFailure {
+            field: "SMTP_FROM",
+            reason: e.to_string(),
+        })?;
+    let mut builder = match SMTP_SECURITY.as_str() {
+        "starttls" => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host)?,
+        "plain" => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(host),
+        security => {
+            return Err(ServiceError::TryFrom {
+                field: "SMTP_SECURITY",
+                from: security.to_string(),
+                into: "Tls",
+                expect: "starttls or plain",
+            })
+        }
+    };
+
+    builder = builder.timeout(Some(SMTP_TIMEOUT));
+
+    if let Some(port) = *SMTP_PORT {
+        builder = builder.port(port);
+    }
+
+    if let (Some(username), Some(password)) = (&*SMTP_USERNAME, &*SMTP_PASSWORD) {
+        builder = builder.credentials(Credentials::new(username.clone(), password.clone()));
+    }
+
+    Ok(Some(Mailer {
+        transport: builder.build(),
+        from,
+    }))
+}
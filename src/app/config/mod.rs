@@ -1,2 +1,5 @@
-pub mod task;
+pub mod bot;
 pub mod database;
+pub mod http;
+pub mod mailer;
+pub mod task;
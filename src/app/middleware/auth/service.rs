@@ -91,6 +91,7 @@ async fn inspect_request_metadata(req: &mut hyper::Request<Body>) -> Result<(),
                 r#"
                 SELECT * from main.repos
                 WHERE id = ?
+                AND deleted_at IS NULL
                 "#,
                 sid
             )
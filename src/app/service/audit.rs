@@ -0,0 +1,343 @@
+//! Audit log of configuration changes. Every command that changes what the bot stores records
+//! who made the change, where, and the value before and after it. Jobs reported over HTTP are
+//! the job history rather than changes, so they are not recorded.
+use super::{
+    escalation::display_name, i18n::Language, message::MessageBuilder, timezone::format_time,
+};
+use crate::app::{middleware::auth::service::SessionContainer, util::error::ServiceError};
+use axum::{extract::Query, response::IntoResponse, Extension, Json};
+use chrono::NaiveDateTime;
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use sqlx::{query, query_as, Executor, Pool, Sqlite};
+use teloxide::types::{ChatId, Message, User};
+
+/// number of events in a page of `/audit`
+pub const PAGE_SIZE: i64 = 10;
+/// the largest page the HTTP endpoint returns
+const MAX_PER_PAGE: i64 = 100;
+/// the most characters of an old or new value `/audit` shows, so that a page of events with
+/// long values still fits in a single message
+const SHOWN_VALUE_LENGTH: usize = 150;
+
+pub struct AuditEntry {
+    pub repo_id: Option<String>,
+    pub chat_id: i64,
+    pub actor_id: Option<i64>,
+    pub actor_name: String,
+    pub command: String,
+    pub old_value: Option<String>,
+    pub new_value: Option<String>,
+}
+
+impl AuditEntry {
+    /// a change made by a telegram user
+    pub fn by_user(user: &User, chat_id: ChatId, repo_id: Option<&str>, command: &str) -> Self {
+        Self {
+            repo_id: repo_id.map(str::to_string),
+            chat_id: chat_id.0,
+            actor_id: Some(user.id.0 as i64),
+            actor_name: display_name(user),
+            command: command.to_string(),
+            old_value: None,
+            new_value: None,
+        }
+    }
+
+    /// a change made by the sender of a message. An anonymous administrator is recorded as the
+    /// chat they sent on behalf of
+    pub fn by_sender(msg: &Message, repo_id: Option<&str>, command: &str) -> Self {
+        match msg.from() {
+            Some(user) if msg.sender_chat().is_none() => {
+                Self::by_user(user, msg.chat.id, repo_id, command)
+            }
+            _ => Self {
+                repo_id: repo_id.map(str::to_string),
+                chat_id: msg.chat.id.0,
+                actor_id: None,
+                actor_name: msg
+                    .sender_chat()
+                    .and_then(|chat| chat.title())
+                    .unwrap_or("anonymous")
+                    .to_string(),
+                command: command.to_string(),
+                old_value: None,
+                new_value: None,
+            },
+        }
+    }
+
+    pub fn before(mut self, value: impl ToString) -> Self {
+        self.old_value = Some(value.to_string());
+        self
+    }
+
+    pub fn after(mut self, value: impl ToString) -> Self {
+        self.new_value = Some(value.to_string());
+        self
+    }
+
+    pub async fn record<'c, E>(self, executor: E) -> Result<(), ServiceError>
+    where
+        E: Executor<'c, Database = Sqlite>,
+    {
+        query!(
+            r#"
+            INSERT INTO main.audit_events
+            (repo_id, chat_id, actor_id, actor_name, command, old_value, new_value)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            "#,
+            self.repo_id,
+            self.chat_id,
+            self.actor_id,
+            self.actor_name,
+            self.command,
+            self.old_value,
+            self.new_value
+        )
+        .execute(executor)
+        .await?;
+
+        Ok(())
+    }
+}
+
+/// an audit event of `main.audit_events`
+#[derive(sqlx::FromRow, Serialize, Debug)]
+pub struct AuditEvent {
+    pub id: i64,
+    pub repo_id: Option<String>,
+    pub chat_id: i64,
+    pub actor_id: Option<i64>,
+    pub actor_name: String,
+    pub command: String,
+    pub old_value: Option<String>,
+    pub new_value: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+/// the number of events before a page. Pages start at 1
+fn page_offset(page: i64, per_page: i64) -> Result<i64, ServiceError> {
+    (page - 1)
+        .checked_mul(per_page)
+        .ok_or_else(|| ServiceError::ValidateFailure {
+            field: "page",
+            reason: format!("{page} is past the last page"),
+        })
+}
+
+/// a page of the audit events of a repo, along with the changes to the chat of the repo that
+/// are not about any repo, the latest first. Pages start at 1
+pub async fn audit_events(
+    pool: &Pool<Sqlite>,
+    repo_id: &str,
+    page: i64,
+    per_page: i64,
+) -> Result<Vec<AuditEvent>, ServiceError> {
+    let offset = page_offset(page, per_page)?;
+
+    Ok(query_as::<_, AuditEvent>(
+        r#"
+        SELECT id, repo_id, chat_id, actor_id, actor_name, command, old_value, new_value,
+            created_at
+        FROM main.audit_events
+        WHERE repo_id = ?
+        OR (
+            repo_id IS NULL
+            AND chat_id = (SELECT message_id FROM main.repos WHERE id = ?)
+        )
+        ORDER BY id DESC
+        LIMIT ?
+        OFFSET ?
+        "#,
+    )
+    .bind(repo_id)
+    .bind(repo_id)
+    .bind(per_page)
+    .bind(offset)
+    .fetch_all(pool)
+    .await?)
+}
+
+pub async fn count_audit_events(pool: &Pool<Sqlite>, repo_id: &str) -> Result<i64, ServiceError> {
+    let record = query!(
+        r#"
+        SELECT COUNT(*) AS "total!: i64"
+        FROM main.audit_events
+        WHERE repo_id = ?
+        OR (
+            repo_id IS NULL
+            AND chat_id = (SELECT message_id FROM main.repos WHERE id = ?)
+        )
+        "#,
+        repo_id,
+        repo_id
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(record.total)
+}
+
+/// parse the argument of `/audit` which is an optional page number
+pub fn parse_page(args: &str) -> Result<i64, ServiceError> {
+    match args.trim() {
+        "" => Ok(1),
+        page => match page.parse::<i64>() {
+            Ok(page) if page > 0 => page_offset(page, PAGE_SIZE).map(|_| page),
+            _ => Err(ServiceError::ValidateFailure {
+                field: "page",
+                reason: format!("{page} is not a page number"),
+            }),
+        },
+    }
+}
+
+/// a value shown on a single line of `/audit`, with at most `SHOWN_VALUE_LENGTH` characters.
+/// Whole values such as the body of a template are only given by `GET /audit`
+fn shorten(value: &str) -> String {
+    let value = value.split_whitespace().collect::<Vec<&str>>().join(" ");
+
+    match value.char_indices().nth(SHOWN_VALUE_LENGTH) {
+        Some((end, _)) => format!("{}…", &value[..end]),
+        None => value,
+    }
+}
+
+/// the lines of a page of `/audit`, which are sent in as many messages as they need
+pub fn format_audit(
+    language: Language,
+    events: &[AuditEvent],
+    page: i64,
+    total: i64,
+    timezone: Tz,
+) -> Vec<MessageBuilder> {
+    if events.is_empty() {
+        return vec![MessageBuilder::new().text(match page {
+            1 => language.tr("audit-empty"),
+            page => language.tr_with("audit-no-page", &[("page", page.into())]),
+        })];
+    }
+
+    let pages = (total + PAGE_SIZE - 1) / PAGE_SIZE;
+    let mut lines = vec![MessageBuilder::new().text(language.tr_with(
+        "audit-header",
+        &[("page", page.into()), ("pages", pages.into())],
+    ))];
+
+    for event in events {
+        let change = match (&event.old_value, &event.new_value) {
+            (Some(old), Some(new)) => format!(": {} → {}", shorten(old), shorten(new)),
+            (Some(old), None) => format!(": {} →", shorten(old)),
+            (None, Some(new)) => format!(": {}", shorten(new)),
+            (None, None) => String::new(),
+        };
+
+        lines.push(MessageBuilder::new().text(format!(
+            "{} {} /{}{change}",
+            format_time(timezone, event.created_at),
+            event.actor_name,
+            event.command
+        )));
+    }
+
+    if page < pages {
+        lines.push(MessageBuilder::new());
+        lines.push(
+            MessageBuilder::new()
+                .text(language.tr_with("audit-older", &[("page", (page + 1).into())])),
+        );
+    }
+
+    lines
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AuditQuery {
+    page: Option<i64>,
+    per_page: Option<i64>,
+}
+
+#[derive(Serialize)]
+pub struct AuditPage {
+    pub events: Vec<AuditEvent>,
+    pub page: i64,
+    pub per_page: i64,
+    pub total: i64,
+}
+
+pub async fn audit_handler(
+    Extension(SessionContainer(session)): Extension<SessionContainer>,
+    Extension(pool): Extension<Pool<Sqlite>>,
+    Query(AuditQuery { page, per_page }): Query<AuditQuery>,
+) -> impl IntoResponse {
+    if let Some(session) = session {
+        let page = page.unwrap_or(1);
+        let per_page = per_page.unwrap_or(PAGE_SIZE);
+
+        if page < 1 || !(1..=MAX_PER_PAGE).contains(&per_page) {
+            return Err(ServiceError::ValidateFailure {
+                field: "page",
+                reason: format!("expect page >= 1 and per_page between 1 and {MAX_PER_PAGE}"),
+            });
+        }
+
+        Ok(Json(AuditPage {
+            events: audit_events(&pool, &session.sid, page, per_page).await?,
+            page,
+            per_page,
+            total: count_audit_events(&pool, &session.sid).await?,
+        }))
+    } else {
+        Err(ServiceError::BadCredential)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::service::message::MESSAGE_LIMIT;
+
+    fn event(value: &str) -> AuditEvent {
+        AuditEvent {
+            id: 1,
+            repo_id: Some("repo".to_string()),
+            chat_id: -100,
+            actor_id: Some(1),
+            actor_name: "@alice".to_string(),
+            command: "template".to_string(),
+            old_value: Some(value.to_string()),
+            new_value: Some(value.to_string()),
+            created_at: NaiveDateTime::default(),
+        }
+    }
+
+    #[test]
+    fn shorten_keeps_short_values() {
+        assert_eq!(
+            shorten("failure {repo}\n{status}"),
+            "failure {repo} {status}"
+        );
+    }
+
+    #[test]
+    fn shorten_cuts_long_values() {
+        let shortened = shorten(&"🚨".repeat(1000));
+
+        assert_eq!(shortened.chars().count(), SHOWN_VALUE_LENGTH + 1);
+        assert!(shortened.ends_with('…'));
+    }
+
+    #[test]
+    fn format_audit_shortens_every_line() {
+        let events = (0..PAGE_SIZE)
+            .map(|_| event(&"x".repeat(4000)))
+            .collect::<Vec<AuditEvent>>();
+        let lines = format_audit(Language::En, &events, 1, 25, Tz::UTC);
+
+        assert_eq!(lines.len(), PAGE_SIZE as usize + 3);
+        // every event and the header and footer fit in a single message
+        assert!(lines.iter().map(MessageBuilder::len).sum::<usize>() < MESSAGE_LIMIT);
+    }
+}
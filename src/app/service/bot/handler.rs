@@ -1,76 +1,230 @@
 use super::state::{BotState, DeployStatus, GeneralCommand, RepoCommand};
-use crate::app::util::error::ServiceError;
+use crate::app::{
+    config::{bot::CiBot, mailer::mailer_configured},
+    service::{
+        audit::{
+            audit_events, count_audit_events, format_audit, parse_page, AuditEntry, PAGE_SIZE,
+        },
+        browser::{job_page, repo_picker, search_page, BrowserAction},
+        chart::{load_chart_jobs, parse_chart_command, render_chart},
+        codeowners::parse_rules,
+        digest::{next_run, DigestCommand, DigestPeriod},
+        dora::{command_window, compute_dora, format_dora, start_of_window, Scope},
+        escalation::{
+            acknowledge, display_name, parse_ack_data, parse_escalation, parse_oncall,
+            unacknowledged_failures, Acknowledgement,
+        },
+        flaky::format_flaky,
+        format::{format_jobs, JobListing},
+        i18n::{chat_language, set_chat_language, Language},
+        identity::{
+            link, linked_user, parse_ci_username, unlink, Link, MentionMode, NotifyPreference,
+        },
+        job::{format_duration, format_progress},
+        message::{
+            edit_message_text, send_message, send_text, split_messages, MessageBuilder,
+            MESSAGE_LIMIT,
+        },
+        notifier::{email::EmailSubscription, load_notifiers, NotifierConfig, NotifierKind},
+        regression::{parse_threshold, rolling_baseline},
+        role::{
+            grant, granted_roles, member_role, parse_grant, required_role, revoke,
+            sender_chat_role, sender_role, target_user, Role,
+        },
+        search::{find_search, parse_history, parse_search, save_search, JobFilter},
+        template::{
+            find_template, latest_context, repo_templates, set_template, Template, TemplateCommand,
+        },
+        timezone::{chat_timezone, format_time, parse_timezone, set_chat_timezone, start_of_day},
+        trash::{
+            delete_chat_repos, delete_repo, latest_deleted, restore_latest, retention,
+            Confirmation, Restore,
+        },
+    },
+    util::error::ServiceError,
+};
 use chrono::prelude::*;
-use sqlx::{query, query_as, sqlite::SqliteRow, Pool, Row, Sqlite};
+use sqlx::{query, Pool, Sqlite};
 use teloxide::{
-    dispatching::dialogue::ErasedStorage, prelude::*, types::MenuButton,
+    dispatching::dialogue::ErasedStorage,
+    prelude::*,
+    types::{BotCommand, InlineKeyboardMarkup, InputFile, MenuButton},
     utils::command::BotCommands,
+    ApiError, RequestError,
 };
-use tracing::info;
+use tracing::{info, warn};
 use uuid::Uuid;
 
 pub type MyDialogue = Dialogue<BotState, ErasedStorage<BotState>>;
 pub type HandlerResult = Result<(), Box<dyn std::error::Error + Send + Sync>>;
 
-pub async fn start(bot: Bot, dialogue: MyDialogue, msg: Message) -> HandlerResult {
-    bot.send_message(
-        msg.chat.id,
-        "Let's start by configuring your first repo. Type /help for more info",
-    )
-    .await?;
+pub async fn start(
+    bot: CiBot,
+    dialogue: MyDialogue,
+    sqlite_pool: Pool<Sqlite>,
+    msg: Message,
+) -> HandlerResult {
+    let language = chat_language(&sqlite_pool, msg.chat.id.0).await?;
+    send_text(&bot, msg.chat.id, language.tr("start")).await?;
     bot.set_chat_menu_button()
         .menu_button(MenuButton::Commands)
         .chat_id(msg.chat.id)
         .await?;
-    dialogue.update(BotState::NormalMode(vec![])).await?;
+    dialogue.update(BotState::Normal).await?;
     Ok(())
 }
 
+/// persist the upgrade of a state stored by an older version. The upgraded state replaces the
+/// stored one for the rest of the update so that it reaches the handlers of this version
+pub async fn upgrade_state(dialogue: MyDialogue, state: BotState) -> BotState {
+    let Some(upgraded) = state.upgrade() else {
+        return state;
+    };
+
+    if let Err(e) = dialogue.update(upgraded.clone()).await {
+        warn!(
+            "failed to upgrade the state of chat {}: {e}",
+            dialogue.chat_id()
+        );
+    }
+
+    upgraded
+}
+
+/// ids and names of the repos of a chat, in the order the repo picker shows them and
+/// `/select_repo` picks them
+async fn chat_repos(
+    pool: &Pool<Sqlite>,
+    chat_id: ChatId,
+) -> Result<Vec<(String, String)>, ServiceError> {
+    Ok(query!(
+        r#"
+        SELECT id, name
+        FROM main.repos
+        WHERE message_id = ?
+        AND deleted_at IS NULL
+        ORDER BY name
+        "#,
+        chat_id.0
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|record| (record.id, record.name))
+    .collect())
+}
+
 pub async fn config_mode_handler(
-    bot: Bot,
+    bot: CiBot,
     dialogue: MyDialogue,
-    (repos, repo_key): (Vec<String>, String),
+    repo_key: String,
     sqlite_pool: Pool<Sqlite>,
     msg: Message,
     cmd: RepoCommand,
 ) -> HandlerResult {
     info!("received message: {}", msg.chat.id);
+    let language = chat_language(&sqlite_pool, msg.chat.id.0).await?;
+
+    // the selected repo may have been deleted since it was selected
+    if !chat_repos(&sqlite_pool, msg.chat.id)
+        .await?
+        .iter()
+        .any(|(id, _)| id == &repo_key)
+    {
+        dialogue.update(BotState::Normal).await?;
+        send_text(&bot, msg.chat.id, language.tr("repo-gone")).await?;
+        return Ok(());
+    }
+
+    let required = required_role(&cmd);
+
+    if required > Role::Viewer {
+        let role = sender_role(&bot, &sqlite_pool, &repo_key, &msg).await?;
+
+        if role < required {
+            send_text(
+                &bot,
+                msg.chat.id,
+                language.tr_with(
+                    "role-required",
+                    &[
+                        ("required", required.to_string().into()),
+                        ("role", role.to_string().into()),
+                    ],
+                ),
+            )
+            .await?;
+            return Ok(());
+        }
+    }
+
     match cmd {
         RepoCommand::Help => {
-            bot.send_message(msg.chat.id, RepoCommand::descriptions().to_string())
-                .await?;
+            send_text(
+                &bot,
+                msg.chat.id,
+                help_text(language, "repo-cmd", RepoCommand::bot_commands()),
+            )
+            .await?;
         }
         RepoCommand::Today => {
             let date_time = Utc::now().naive_utc();
-            let Some(beginning_of_today) = date_time.with_hour(0) else { return Err(Box::new(ServiceError::ChronoDatetime)); };
-            let records = query!(
+            let timezone = chat_timezone(&sqlite_pool, msg.chat.id.0).await?;
+            let Some(beginning_of_today) = start_of_day(timezone, date_time) else {
+                return Err(Box::new(ServiceError::ChronoDatetime));
+            };
+            let jobs = query!(
                 r#"
-                SELECT *
+                SELECT id AS "id!",
+                    status AS "status!: DeployStatus",
+                    branch,
+                    triggered_by,
+                    callback_url,
+                    started_at,
+                    elapsed
                 FROM main.jobs
                 WHERE repo_id = ?
                 AND started_at >= ?
+                ORDER BY started_at
                 "#,
                 repo_key,
                 beginning_of_today
             )
             .fetch_all(&sqlite_pool)
-            .await?;
+            .await?
+            .into_iter()
+            .map(|record| JobListing {
+                id: record.id,
+                status: record.status,
+                repo_name: None,
+                branch: record.branch,
+                triggered_by: record.triggered_by,
+                url: record.callback_url,
+                started_at: record.started_at,
+                elapsed: record.elapsed,
+            })
+            .collect::<Vec<JobListing>>();
 
-            if records.is_empty() {
-                bot.send_message(
-                    msg.chat.id,
-                    "No running job today. Start running job to see them here.",
-                )
-                .await?;
+            if jobs.is_empty() {
+                send_text(&bot, msg.chat.id, language.tr("today-empty")).await?;
             } else {
-                bot.send_message(msg.chat.id, format!("{:?}", records))
-                    .await?;
+                let header = MessageBuilder::new()
+                    .text("📅 ")
+                    .bold(language.tr("today-header"));
+                let lines = format_jobs(language, header, &jobs, date_time);
+                send_lines(&bot, msg.chat.id, lines).await?;
             }
         }
         RepoCommand::Latest => {
-            let record = query!(
+            let job = query!(
                 r#"
-                SELECT *
+                SELECT id AS "id!",
+                    status AS "status!: DeployStatus",
+                    branch,
+                    triggered_by,
+                    callback_url,
+                    started_at,
+                    elapsed
                 FROM main.jobs
                 WHERE repo_id = ?
                 ORDER BY started_at DESC
@@ -78,42 +232,227 @@ pub async fn config_mode_handler(
                 repo_key,
             )
             .fetch_optional(&sqlite_pool)
-            .await?;
+            .await?
+            .map(|record| JobListing {
+                id: record.id,
+                status: record.status,
+                repo_name: None,
+                branch: record.branch,
+                triggered_by: record.triggered_by,
+                url: record.callback_url,
+                started_at: record.started_at,
+                elapsed: record.elapsed,
+            });
 
-            if record.is_none() {
-                bot.send_message(
+            match job {
+                Some(job) => {
+                    let header = MessageBuilder::new()
+                        .text("🕒 ")
+                        .bold(language.tr("latest-header"));
+                    let lines = format_jobs(language, header, &[job], Utc::now().naive_utc());
+                    send_lines(&bot, msg.chat.id, lines).await?;
+                }
+                None => {
+                    send_text(&bot, msg.chat.id, language.tr("latest-empty")).await?;
+                }
+            }
+        }
+        RepoCommand::Jobs => {
+            let record = query!(
+                r#"
+                SELECT name
+                FROM main.repos
+                WHERE id = ?
+                "#,
+                repo_key
+            )
+            .fetch_one(&sqlite_pool)
+            .await?;
+            let (message, keyboard) =
+                job_page(language, &sqlite_pool, &repo_key, &record.name, 0).await?;
+            send_message(&bot, msg.chat.id, &message)
+                .reply_markup(keyboard)
+                .await?;
+        }
+        RepoCommand::History(args) => {
+            search(
+                &bot,
+                &sqlite_pool,
+                &msg,
+                language,
+                Some(&repo_key),
+                &args,
+                parse_history,
+            )
+            .await?
+        }
+        RepoCommand::Search(args) => {
+            search(
+                &bot,
+                &sqlite_pool,
+                &msg,
+                language,
+                Some(&repo_key),
+                &args,
+                parse_search,
+            )
+            .await?
+        }
+        RepoCommand::Grant(args) => match parse_grant(&args) {
+            Ok((role, user_id)) => match target_user(&bot, &msg, user_id).await {
+                Ok(user) if msg.from().map(|sender| sender.id) == Some(user.id) => {
+                    send_text(&bot, msg.chat.id, language.tr("role-own")).await?;
+                }
+                Ok(user) => {
+                    let before =
+                        member_role(&bot, &sqlite_pool, &repo_key, &msg.chat, &user).await?;
+                    grant(&sqlite_pool, &repo_key, &user, role).await?;
+                    AuditEntry::by_sender(&msg, Some(&repo_key), "grant")
+                        .before(format!("{} ({before})", display_name(&user)))
+                        .after(format!("{} ({role})", display_name(&user)))
+                        .record(&sqlite_pool)
+                        .await?;
+                    send_text(
+                        &bot,
+                        msg.chat.id,
+                        language.tr_with(
+                            "role-granted",
+                            &[
+                                ("role", role.to_string().into()),
+                                ("name", display_name(&user).into()),
+                            ],
+                        ),
+                    )
+                    .await?;
+                }
+                Err(e) => {
+                    send_text(
+                        &bot,
+                        msg.chat.id,
+                        language.tr_with("invalid-user", &[("error", e.to_string().into())]),
+                    )
+                    .await?;
+                }
+            },
+            Err(e) => {
+                send_text(
+                    &bot,
                     msg.chat.id,
-                    "No latest running job. Start running job to see them here.",
+                    language.tr_with("invalid-role", &[("error", e.to_string().into())]),
                 )
                 .await?;
-            } else {
-                bot.send_message(msg.chat.id, format!("{:?}", record))
+            }
+        },
+        RepoCommand::Revoke(args) => {
+            let user_id = args.split_whitespace().next();
+
+            match target_user(&bot, &msg, user_id).await {
+                Ok(user) if msg.from().map(|sender| sender.id) == Some(user.id) => {
+                    send_text(&bot, msg.chat.id, language.tr("role-own")).await?;
+                }
+                Ok(user) => {
+                    let name = display_name(&user);
+                    let before =
+                        member_role(&bot, &sqlite_pool, &repo_key, &msg.chat, &user).await?;
+
+                    if revoke(&sqlite_pool, &repo_key, &user).await? {
+                        let after =
+                            member_role(&bot, &sqlite_pool, &repo_key, &msg.chat, &user).await?;
+                        AuditEntry::by_sender(&msg, Some(&repo_key), "revoke")
+                            .before(format!("{name} ({before})"))
+                            .after(format!("{name} ({after})"))
+                            .record(&sqlite_pool)
+                            .await?;
+                        send_text(
+                            &bot,
+                            msg.chat.id,
+                            language.tr_with("role-revoked", &[("name", name.into())]),
+                        )
+                        .await?;
+                    } else {
+                        send_text(
+                            &bot,
+                            msg.chat.id,
+                            language.tr_with("role-none", &[("name", name.into())]),
+                        )
+                        .await?;
+                    }
+                }
+                Err(e) => {
+                    send_text(
+                        &bot,
+                        msg.chat.id,
+                        language.tr_with("invalid-user", &[("error", e.to_string().into())]),
+                    )
                     .await?;
+                }
             }
         }
+        RepoCommand::Roles => {
+            let roles = granted_roles(&sqlite_pool, &repo_key).await?;
+            let text = roles
+                .into_iter()
+                .map(|(name, role)| format!("{name}: {role}"))
+                .collect::<Vec<String>>()
+                .join("\n");
+
+            send_text(
+                &bot,
+                msg.chat.id,
+                format!("{text}\n\n{}", language.tr("roles-footer")).trim_start(),
+            )
+            .await?;
+        }
+        RepoCommand::Audit(args) => match parse_page(&args) {
+            Ok(page) => {
+                let events = audit_events(&sqlite_pool, &repo_key, page, PAGE_SIZE).await?;
+                let total = count_audit_events(&sqlite_pool, &repo_key).await?;
+                let timezone = chat_timezone(&sqlite_pool, msg.chat.id.0).await?;
+
+                send_lines(
+                    &bot,
+                    msg.chat.id,
+                    format_audit(language, &events, page, total, timezone),
+                )
+                .await?;
+            }
+            Err(e) => {
+                send_text(
+                    &bot,
+                    msg.chat.id,
+                    language.tr_with("invalid-page", &[("error", e.to_string().into())]),
+                )
+                .await?;
+            }
+        },
         RepoCommand::Delete => {
-            let mut transaction = sqlite_pool.begin().await?;
-            let result = query!(
+            let record = query!(
                 r#"
-                DELETE FROM main.repos
+                SELECT name
+                FROM main.repos
                 WHERE id = ?
                 "#,
                 repo_key
             )
-            .execute(&mut transaction)
+            .fetch_one(&sqlite_pool)
+            .await?;
+
+            send_text(
+                &bot,
+                msg.chat.id,
+                language.tr_with(
+                    "delete-confirm",
+                    &[
+                        ("repo", record.name.into()),
+                        ("retention", format_duration(language, retention()).into()),
+                    ],
+                ),
+            )
+            .reply_markup(Confirmation::Delete(repo_key).keyboard(language))
             .await?;
-            info!(
-                "Query success with affacted rows: {}",
-                result.rows_affected()
-            );
-            bot.send_message(msg.chat.id, format!("Successfully deleted repo."))
-                .await?;
-            let repos = repos.into_iter().filter(|repo| repo == &repo_key).collect();
-            dialogue.update(BotState::NormalMode(repos)).await?;
-            transaction.commit().await?;
         }
         RepoCommand::Cancel => {
-            dialogue.update(BotState::NormalMode(repos)).await?;
+            dialogue.update(BotState::Normal).await?;
             bot.set_chat_menu_button()
                 .menu_button(MenuButton::Commands)
                 .chat_id(msg.chat.id)
@@ -130,18 +469,29 @@ pub async fn config_mode_handler(
             )
             .fetch_one(&sqlite_pool)
             .await?;
-            bot.send_message(msg.chat.id, format!("name: {}", record.name))
-                .await?;
-            bot.send_message(msg.chat.id, format!("key: ||{}||", record.id))
-                .await?;
+            send_text(
+                &bot,
+                msg.chat.id,
+                language.tr_with("info-name", &[("name", record.name.into())]),
+            )
+            .await?;
+            send_message(
+                &bot,
+                msg.chat.id,
+                &MessageBuilder::new()
+                    .text(format!("{} ", language.tr("info-key")))
+                    .spoiler(record.id),
+            )
+            .await?;
         }
         RepoCommand::Running => {
             let records = query!(
                 r#"
-                SELECT *
+                SELECT id, branch, started_at
                 FROM main.jobs
                 WHERE repo_id = ?
                 AND status = ?
+                ORDER BY started_at
                 "#,
                 repo_key,
                 DeployStatus::Running
@@ -149,18 +499,101 @@ pub async fn config_mode_handler(
             .fetch_all(&sqlite_pool)
             .await?;
 
-            if records.is_empty() {
-                bot.send_message(
-                    msg.chat.id,
-                    "No running job configured. Start running job to see them here.",
-                )
-                .await?;
+            let mut connection = sqlite_pool.acquire().await?;
+            let failures = unacknowledged_failures(&mut connection, &repo_key).await?;
+
+            if records.is_empty() && failures.is_empty() {
+                send_text(&bot, msg.chat.id, language.tr("running-empty")).await?;
             } else {
-                bot.send_message(msg.chat.id, format!("{:?}", records))
+                let now = Utc::now().naive_utc();
+                let mut text = String::new();
+
+                for record in records {
+                    let estimate = rolling_baseline(
+                        &mut connection,
+                        &repo_key,
+                        record.branch.as_deref(),
+                        record.id as i32,
+                    )
                     .await?;
+                    let branch = record
+                        .branch
+                        .map_or(String::new(), |branch| format!(" ({branch})"));
+
+                    text = format!(
+                        "{text}🚧 #{}{branch} {}\n",
+                        record.id,
+                        format_progress(language, now - record.started_at, estimate)
+                    );
+                }
+
+                if !failures.is_empty() {
+                    text = format!("{text}\n{}\n", language.tr("running-unacknowledged"));
+
+                    for failure in failures {
+                        text = format!(
+                            "{text}🚨 {}\n",
+                            language.tr_with(
+                                "running-failure",
+                                &[
+                                    ("job", failure.job_id.to_string().into()),
+                                    (
+                                        "duration",
+                                        format_duration(language, now - failure.failed_at).into()
+                                    ),
+                                ],
+                            )
+                        );
+                    }
+                }
+
+                let lines = text
+                    .trim_end()
+                    .lines()
+                    .map(|line| MessageBuilder::new().text(line))
+                    .collect();
+                send_lines(&bot, msg.chat.id, lines).await?;
             }
         }
         RepoCommand::Rename(new_name) => {
+            let mut transaction = sqlite_pool.begin().await?;
+            let record = query!(
+                r#"
+                SELECT name
+                FROM main.repos
+                WHERE id = ?
+                "#,
+                repo_key
+            )
+            .fetch_one(&mut transaction)
+            .await?;
+            // repo names are unique across every chat
+            let taken = query!(
+                r#"
+                SELECT EXISTS (
+                    SELECT 1
+                    FROM main.repos
+                    WHERE name = ?
+                    AND id <> ?
+                ) AS "taken!: bool"
+                "#,
+                new_name,
+                repo_key
+            )
+            .fetch_one(&mut transaction)
+            .await?
+            .taken;
+
+            if taken {
+                send_text(
+                    &bot,
+                    msg.chat.id,
+                    language.tr_with("repo-name-taken", &[("name", new_name.as_str().into())]),
+                )
+                .await?;
+                return Ok(());
+            }
+
             query!(
                 r#"
                 UPDATE main.repos
@@ -170,170 +603,1941 @@ pub async fn config_mode_handler(
                 new_name,
                 repo_key
             )
-            .execute(&sqlite_pool)
+            .execute(&mut transaction)
             .await?;
-            bot.send_message(msg.chat.id, "Successfully updated repo name")
+            AuditEntry::by_sender(&msg, Some(&repo_key), "rename")
+                .before(record.name)
+                .after(&new_name)
+                .record(&mut transaction)
                 .await?;
+            transaction.commit().await?;
+            send_text(&bot, msg.chat.id, language.tr("rename-success")).await?;
         }
-    };
-    Ok(())
-}
-
-#[derive(sqlx::FromRow, Debug)]
-struct JobProp<T> {
-    id: i32,
-    status: DeployStatus,
-    triggered_by: Option<String>,
-    description: Option<String>,
-    callback_url: Option<String>,
-    repo_id: T,
-    started_at: DateTime<Utc>,
-    elapsed: i32,
-}
-
-pub async fn normal_mode_handler(
-    bot: Bot,
-    dialogue: MyDialogue,
-    mut repos: Vec<String>,
-    sqlite_pool: Pool<Sqlite>,
-    msg: Message,
-    cmd: GeneralCommand,
-) -> HandlerResult {
-    match cmd {
-        GeneralCommand::Help => {
-            bot.send_message(msg.chat.id, GeneralCommand::descriptions().to_string())
+        RepoCommand::AddNotifier(args) => match NotifierConfig::parse_checked(&args).await {
+            Ok(NotifierConfig {
+                kind,
+                target,
+                room,
+                token,
+                ..
+            }) => {
+                let mut transaction = sqlite_pool.begin().await?;
+                let record = query!(
+                    r#"
+                    INSERT INTO main.notifiers
+                    (kind, target, room, token, repo_id)
+                    VALUES (?, ?, ?, ?, ?)
+                    RETURNING id AS "id!"
+                    "#,
+                    kind,
+                    target,
+                    room,
+                    token,
+                    repo_key
+                )
+                .fetch_one(&mut transaction)
                 .await?;
-        }
-        GeneralCommand::List => {
-            let records: Vec<(String, String)> = query(&format!(
-                r#"
-                SELECT id, name
-                FROM main.repos
-                WHERE id IN ({})
-                "#,
-                repos
-                    .into_iter()
-                    .map(|repo| format!("\"{repo}\""))
-                    .collect::<Vec<String>>()
-                    .join(",")
-            ))
-            .map(|row: SqliteRow| (row.get(0), row.get(1)))
-            .fetch_all(&sqlite_pool)
-            .await?;
-
-            if records.is_empty() {
-                bot.send_message(
+                // targets and tokens are secrets so only the notifier itself is recorded
+                AuditEntry::by_sender(&msg, Some(&repo_key), "add_notifier")
+                    .after(format!("{kind} notifier {}", record.id))
+                    .record(&mut transaction)
+                    .await?;
+                transaction.commit().await?;
+                send_text(
+                    &bot,
                     msg.chat.id,
-                    "No repo configured. Type /help to get started.",
+                    language.tr_with("notifier-added", &[("kind", kind.to_string().into())]),
                 )
                 .await?;
-            } else {
-                bot.send_message(msg.chat.id, format!("{:?}", records))
-                    .await?;
             }
-        }
-        GeneralCommand::Today => {
-            let date_time = Utc::now().naive_utc();
-            let Some(beginning_of_today) = date_time.with_hour(0) else { return Err(Box::new(ServiceError::ChronoDatetime)); };
-            let records = query_as::<_, JobProp<String>>(&format!(
-                r#"
-                SELECT *
-                FROM main.jobs
-                WHERE repo_id IN ({})
-                AND started_at >= ?
-                "#,
-                repos
-                    .into_iter()
-                    .map(|repo| format!("\"{repo}\""))
-                    .collect::<Vec<String>>()
-                    .join(",")
-            ))
-            .bind(beginning_of_today)
-            .fetch_all(&sqlite_pool)
-            .await?;
-
-            if records.is_empty() {
-                bot.send_message(
+            Err(e) => {
+                send_text(
+                    &bot,
                     msg.chat.id,
-                    "No running job today. Start running job to see them here.",
+                    language.tr_with("invalid-notifier", &[("error", e.to_string().into())]),
                 )
                 .await?;
+            }
+        },
+        RepoCommand::Notifiers => {
+            let notifiers = load_notifiers(&sqlite_pool, &repo_key).await?;
+
+            if notifiers.is_empty() {
+                send_text(&bot, msg.chat.id, language.tr("notifiers-empty")).await?;
             } else {
-                bot.send_message(msg.chat.id, format!("{:?}", records))
-                    .await?;
+                send_text(
+                    &bot,
+                    msg.chat.id,
+                    notifiers
+                        .iter()
+                        .map(|notifier| notifier.to_string())
+                        .collect::<Vec<String>>()
+                        .join("\n"),
+                )
+                .await?;
             }
         }
-        GeneralCommand::Create(name) => {
+        RepoCommand::RemoveNotifier(id) => {
             let mut transaction = sqlite_pool.begin().await?;
-            let uuid = Uuid::new_v4().simple().to_string();
-            query!(
+            let record = query!(
                 r#"
-                INSERT INTO main.repos 
-                (id, name, message_id)
-                VALUES (?, ?, ?)
+                DELETE FROM main.notifiers
+                WHERE id = ?
+                AND repo_id = ?
+                RETURNING kind AS "kind!: NotifierKind"
                 "#,
-                uuid,
-                name,
-                msg.chat.id.0
+                id,
+                repo_key
             )
-            .execute(&mut transaction)
+            .fetch_optional(&mut transaction)
             .await?;
 
-            bot.send_message(msg.chat.id, format!("Successfully added repo: {name}"))
-                .await?;
-            bot.send_message(msg.chat.id, format!("key: {uuid}"))
-                .await?;
-            repos.push(uuid);
-            dialogue.update(BotState::NormalMode(repos)).await?;
-            transaction.commit().await?;
+            if let Some(record) = record {
+                AuditEntry::by_sender(&msg, Some(&repo_key), "remove_notifier")
+                    .before(format!("{} notifier {id}", record.kind))
+                    .record(&mut transaction)
+                    .await?;
+                transaction.commit().await?;
+                send_text(&bot, msg.chat.id, language.tr("notifier-removed")).await?;
+            } else {
+                send_text(&bot, msg.chat.id, language.tr("notifier-missing")).await?;
+            }
         }
-        GeneralCommand::SelectRepo(index) => {
-            match repos.get(index - 1) {
-                Some(repo) => {
+        // nothing would ever be sent to an address added while email is not set up
+        RepoCommand::AddEmail(_) if !mailer_configured() => {
+            send_text(&bot, msg.chat.id, language.tr("email-not-configured")).await?;
+        }
+        RepoCommand::AddEmail(args) => {
+            let mut args = args.split_whitespace();
+            let address = args.next().unwrap_or_default();
+            let subscription = args
+                .next()
+                .map_or(Ok(EmailSubscription::All), EmailSubscription::try_from);
+
+            match (address.parse::<lettre::Address>(), subscription) {
+                (Ok(address), Ok(subscription)) => {
+                    let address = address.to_string();
+                    let mut transaction = sqlite_pool.begin().await?;
                     let record = query!(
                         r#"
-                        SELECT id, name
-                        FROM main.repos
-                        WHERE id = ?
+                        SELECT subscription AS "subscription: EmailSubscription"
+                        FROM main.email_recipients
+                        WHERE repo_id = ?
+                        AND address = ?
                         "#,
-                        repo
+                        repo_key,
+                        address
                     )
-                    .fetch_one(&sqlite_pool)
+                    .fetch_optional(&mut transaction)
                     .await?;
+                    query!(
+                        r#"
+                        INSERT INTO main.email_recipients
+                        (address, subscription, repo_id)
+                        VALUES (?, ?, ?)
+                        ON CONFLICT (repo_id, address)
+                        DO UPDATE SET subscription = excluded.subscription
+                        "#,
+                        address,
+                        subscription,
+                        repo_key
+                    )
+                    .execute(&mut transaction)
+                    .await?;
+                    let mut entry = AuditEntry::by_sender(&msg, Some(&repo_key), "add_email")
+                        .after(format!("{address} ({subscription})"));
 
-                    bot.send_message(msg.chat.id, format!("Seleceing repo name: {}", record.name))
-                        .await?;
-                    dialogue
-                        .update(BotState::ConfigMode(repos, record.id))
-                        .await?;
+                    if let Some(record) = record {
+                        entry = entry.before(format!("{address} ({})", record.subscription));
+                    }
+
+                    entry.record(&mut transaction).await?;
+                    transaction.commit().await?;
+                    send_text(
+                        &bot,
+                        msg.chat.id,
+                        language.tr_with(
+                            "email-subscribed",
+                            &[
+                                ("address", address.into()),
+                                ("subscription", subscription.to_string().into()),
+                            ],
+                        ),
+                    )
+                    .await?;
                 }
-                None => {
-                    bot.send_message(msg.chat.id, "Requested repo does not exists.")
-                        .await?;
+                (Err(e), _) => {
+                    send_text(
+                        &bot,
+                        msg.chat.id,
+                        language.tr_with("invalid-email", &[("error", e.to_string().into())]),
+                    )
+                    .await?;
                 }
-            };
+                (_, Err(e)) => {
+                    send_text(
+                        &bot,
+                        msg.chat.id,
+                        language
+                            .tr_with("invalid-subscription", &[("error", e.to_string().into())]),
+                    )
+                    .await?;
+                }
+            }
         }
-        GeneralCommand::Reset => {
-            query!(
+        RepoCommand::Emails => {
+            let records = query!(
                 r#"
-                DELETE FROM main.repos
-                WHERE message_id = ?
+                SELECT address, subscription
+                FROM main.email_recipients
+                WHERE repo_id = ?
+                ORDER BY address
                 "#,
-                msg.chat.id.0
+                repo_key
             )
-            .execute(&sqlite_pool)
+            .fetch_all(&sqlite_pool)
             .await?;
-            bot.send_message(msg.chat.id, "[DEBUG] Successfully reset all state")
+
+            if records.is_empty() {
+                send_text(&bot, msg.chat.id, language.tr("emails-empty")).await?;
+            } else {
+                send_text(
+                    &bot,
+                    msg.chat.id,
+                    records
+                        .into_iter()
+                        .map(|record| {
+                            format!(
+                                "{} ({})",
+                                record.address,
+                                record.subscription.to_lowercase()
+                            )
+                        })
+                        .collect::<Vec<String>>()
+                        .join("\n"),
+                )
                 .await?;
-            dialogue.update(BotState::Start).await?;
+            }
         }
-    };
-    Ok(())
-}
+        RepoCommand::Dora(args) => match command_window(&args) {
+            Ok(window) => {
+                let record = query!(
+                    r#"
+                    SELECT name
+                    FROM main.repos
+                    WHERE id = ?
+                    "#,
+                    repo_key
+                )
+                .fetch_one(&sqlite_pool)
+                .await?;
+                let metrics = compute_dora(&sqlite_pool, Scope::Repo(&repo_key), window).await?;
 
-pub async fn invalid_command(bot: Bot, msg: Message) -> HandlerResult {
-    info!("invalid command: {}", msg.chat.id);
-    bot.send_message(msg.chat.id, "Invalid command. see /help for more info.")
-        .await?;
+                send_text(
+                    &bot,
+                    msg.chat.id,
+                    format_dora(language, &record.name, &metrics),
+                )
+                .await?;
+            }
+            Err(e) => {
+                send_text(
+                    &bot,
+                    msg.chat.id,
+                    language.tr_with("invalid-window", &[("error", e.to_string().into())]),
+                )
+                .await?;
+            }
+        },
+        RepoCommand::Chart(args) => match parse_chart_command(&args) {
+            Ok((kind, window)) => {
+                let record = query!(
+                    r#"
+                    SELECT name
+                    FROM main.repos
+                    WHERE id = ?
+                    "#,
+                    repo_key
+                )
+                .fetch_one(&sqlite_pool)
+                .await?;
+                let since = start_of_window(Utc::now().naive_utc(), window)?;
+                let jobs = load_chart_jobs(&sqlite_pool, &repo_key, since).await?;
+                let timezone = chat_timezone(&sqlite_pool, msg.chat.id.0).await?;
+
+                if jobs.is_empty() {
+                    send_text(&bot, msg.chat.id, language.tr("chart-empty")).await?;
+                } else {
+                    let png = tokio::task::spawn_blocking(move || {
+                        render_chart(kind, &record.name, &jobs, since, timezone)
+                    })
+                    .await
+                    .map_err(ServiceError::from)??;
+
+                    bot.send_photo(
+                        msg.chat.id,
+                        InputFile::memory(png).file_name(format!("{kind}.png")),
+                    )
+                    .await?;
+                }
+            }
+            Err(e) => {
+                send_text(
+                    &bot,
+                    msg.chat.id,
+                    language.tr_with("invalid-chart", &[("error", e.to_string().into())]),
+                )
+                .await?;
+            }
+        },
+        RepoCommand::Slowdown(args) if args.trim().is_empty() => {
+            let record = query!(
+                r#"
+                SELECT slowdown_threshold
+                FROM main.repos
+                WHERE id = ?
+                "#,
+                repo_key
+            )
+            .fetch_one(&sqlite_pool)
+            .await?;
+
+            send_text(
+                &bot,
+                msg.chat.id,
+                record
+                    .slowdown_threshold
+                    .map_or(language.tr("slowdown-off"), |threshold| {
+                        language.tr_with(
+                            "slowdown-threshold",
+                            &[("threshold", format!("{threshold:.1}").into())],
+                        )
+                    }),
+            )
+            .await?;
+        }
+        RepoCommand::Slowdown(args) => match parse_threshold(&args) {
+            Ok(threshold) => {
+                let mut transaction = sqlite_pool.begin().await?;
+                let record = query!(
+                    r#"
+                    SELECT slowdown_threshold
+                    FROM main.repos
+                    WHERE id = ?
+                    "#,
+                    repo_key
+                )
+                .fetch_one(&mut transaction)
+                .await?;
+                query!(
+                    r#"
+                    UPDATE main.repos
+                    SET slowdown_threshold = ?
+                    WHERE id = ?
+                    "#,
+                    threshold,
+                    repo_key
+                )
+                .execute(&mut transaction)
+                .await?;
+                let format_threshold = |threshold: Option<f64>| {
+                    threshold.map_or("off".to_string(), |threshold| format!("{threshold:.1}"))
+                };
+                AuditEntry::by_sender(&msg, Some(&repo_key), "slowdown")
+                    .before(format_threshold(record.slowdown_threshold))
+                    .after(format_threshold(threshold))
+                    .record(&mut transaction)
+                    .await?;
+                transaction.commit().await?;
+                send_text(&bot, msg.chat.id, language.tr("slowdown-updated")).await?;
+            }
+            Err(e) => {
+                send_text(
+                    &bot,
+                    msg.chat.id,
+                    language.tr_with("invalid-threshold", &[("error", e.to_string().into())]),
+                )
+                .await?;
+            }
+        },
+        RepoCommand::Flaky(args) => match command_window(&args) {
+            Ok(window) => {
+                let mut connection = sqlite_pool.acquire().await?;
+                let since = start_of_window(Utc::now().naive_utc(), window)?;
+
+                send_text(
+                    &bot,
+                    msg.chat.id,
+                    format_flaky(&mut connection, language, &repo_key, since).await?,
+                )
+                .await?;
+            }
+            Err(e) => {
+                send_text(
+                    &bot,
+                    msg.chat.id,
+                    language.tr_with("invalid-window", &[("error", e.to_string().into())]),
+                )
+                .await?;
+            }
+        },
+        RepoCommand::Oncall(args) if args.trim().is_empty() => {
+            let records = query!(
+                r#"
+                SELECT username
+                FROM main.oncall_users
+                WHERE repo_id = ?
+                ORDER BY username
+                "#,
+                repo_key
+            )
+            .fetch_all(&sqlite_pool)
+            .await?;
+
+            if records.is_empty() {
+                send_text(&bot, msg.chat.id, language.tr("oncall-empty")).await?;
+            } else {
+                send_text(
+                    &bot,
+                    msg.chat.id,
+                    records
+                        .into_iter()
+                        .map(|record| format!("@{}", record.username))
+                        .collect::<Vec<String>>()
+                        .join("\n"),
+                )
+                .await?;
+            }
+        }
+        RepoCommand::Oncall(args) => match parse_oncall(&args) {
+            Ok((true, username)) => {
+                let mut transaction = sqlite_pool.begin().await?;
+                let result = query!(
+                    r#"
+                    INSERT OR IGNORE INTO main.oncall_users
+                    (repo_id, username)
+                    VALUES (?, ?)
+                    "#,
+                    repo_key,
+                    username
+                )
+                .execute(&mut transaction)
+                .await?;
+
+                if result.rows_affected() > 0 {
+                    AuditEntry::by_sender(&msg, Some(&repo_key), "oncall")
+                        .after(format!("@{username}"))
+                        .record(&mut transaction)
+                        .await?;
+                }
+
+                transaction.commit().await?;
+                send_text(
+                    &bot,
+                    msg.chat.id,
+                    language.tr_with("oncall-added", &[("username", username.into())]),
+                )
+                .await?;
+            }
+            Ok((false, username)) => {
+                let mut transaction = sqlite_pool.begin().await?;
+                let result = query!(
+                    r#"
+                    DELETE FROM main.oncall_users
+                    WHERE repo_id = ?
+                    AND username = ?
+                    "#,
+                    repo_key,
+                    username
+                )
+                .execute(&mut transaction)
+                .await?;
+
+                if result.rows_affected() > 0 {
+                    AuditEntry::by_sender(&msg, Some(&repo_key), "oncall")
+                        .before(format!("@{username}"))
+                        .record(&mut transaction)
+                        .await?;
+                }
+
+                transaction.commit().await?;
+
+                if result.rows_affected() == 0 {
+                    send_text(
+                        &bot,
+                        msg.chat.id,
+                        language.tr_with("oncall-missing", &[("username", username.into())]),
+                    )
+                    .await?;
+                } else {
+                    send_text(
+                        &bot,
+                        msg.chat.id,
+                        language.tr_with("oncall-removed", &[("username", username.into())]),
+                    )
+                    .await?;
+                }
+            }
+            Err(e) => {
+                send_text(
+                    &bot,
+                    msg.chat.id,
+                    language.tr_with("invalid-oncall", &[("error", e.to_string().into())]),
+                )
+                .await?;
+            }
+        },
+        RepoCommand::Escalation(args) if args.trim().is_empty() => {
+            let record = query!(
+                r#"
+                SELECT ack_timeout, fallback_chat_id
+                FROM main.repos
+                WHERE id = ?
+                "#,
+                repo_key
+            )
+            .fetch_one(&sqlite_pool)
+            .await?;
+
+            send_text(
+                &bot,
+                msg.chat.id,
+                match (record.ack_timeout, record.fallback_chat_id) {
+                    (None, _) => language.tr("escalation-off"),
+                    (Some(minutes), None) => {
+                        language.tr_with("escalation-oncall", &[("minutes", minutes.into())])
+                    }
+                    (Some(minutes), Some(chat_id)) => language.tr_with(
+                        "escalation-fallback",
+                        &[
+                            ("minutes", minutes.into()),
+                            ("chat", chat_id.to_string().into()),
+                        ],
+                    ),
+                },
+            )
+            .await?;
+        }
+        RepoCommand::Escalation(args) => match parse_escalation(&args) {
+            Ok(escalation) => {
+                let (ack_timeout, fallback_chat_id) =
+                    escalation.map_or((None, None), |(minutes, chat_id)| (Some(minutes), chat_id));
+                let mut transaction = sqlite_pool.begin().await?;
+                let record = query!(
+                    r#"
+                    SELECT ack_timeout, fallback_chat_id
+                    FROM main.repos
+                    WHERE id = ?
+                    "#,
+                    repo_key
+                )
+                .fetch_one(&mut transaction)
+                .await?;
+                query!(
+                    r#"
+                    UPDATE main.repos
+                    SET ack_timeout = ?,
+                        fallback_chat_id = ?
+                    WHERE id = ?
+                    "#,
+                    ack_timeout,
+                    fallback_chat_id,
+                    repo_key
+                )
+                .execute(&mut transaction)
+                .await?;
+                let format_escalation =
+                    |ack_timeout: Option<i64>, fallback_chat_id: Option<i64>| match (
+                        ack_timeout,
+                        fallback_chat_id,
+                    ) {
+                        (None, _) => "off".to_string(),
+                        (Some(minutes), None) => format!("{minutes}"),
+                        (Some(minutes), Some(chat_id)) => format!("{minutes} {chat_id}"),
+                    };
+                AuditEntry::by_sender(&msg, Some(&repo_key), "escalation")
+                    .before(format_escalation(
+                        record.ack_timeout,
+                        record.fallback_chat_id,
+                    ))
+                    .after(format_escalation(ack_timeout, fallback_chat_id))
+                    .record(&mut transaction)
+                    .await?;
+                transaction.commit().await?;
+                send_text(&bot, msg.chat.id, language.tr("escalation-updated")).await?;
+            }
+            Err(e) => {
+                send_text(
+                    &bot,
+                    msg.chat.id,
+                    language.tr_with("invalid-escalation", &[("error", e.to_string().into())]),
+                )
+                .await?;
+            }
+        },
+        RepoCommand::Codeowners(args) if args.trim().is_empty() => {
+            let record = query!(
+                r#"
+                SELECT codeowners
+                FROM main.repos
+                WHERE id = ?
+                "#,
+                repo_key
+            )
+            .fetch_one(&sqlite_pool)
+            .await?;
+
+            send_text(
+                &bot,
+                msg.chat.id,
+                record
+                    .codeowners
+                    .map_or(language.tr("codeowners-empty"), |codeowners| {
+                        language.tr_with("codeowners-rules", &[("rules", codeowners.into())])
+                    }),
+            )
+            .await?;
+        }
+        RepoCommand::Codeowners(args) => {
+            let codeowners = match args.trim() {
+                "clear" => Ok(None),
+                codeowners => parse_rules(codeowners).map(|_| Some(codeowners)),
+            };
+
+            match codeowners {
+                Ok(codeowners) => {
+                    let mut transaction = sqlite_pool.begin().await?;
+                    let record = query!(
+                        r#"
+                        SELECT codeowners
+                        FROM main.repos
+                        WHERE id = ?
+                        "#,
+                        repo_key
+                    )
+                    .fetch_one(&mut transaction)
+                    .await?;
+                    query!(
+                        r#"
+                        UPDATE main.repos
+                        SET codeowners = ?
+                        WHERE id = ?
+                        "#,
+                        codeowners,
+                        repo_key
+                    )
+                    .execute(&mut transaction)
+                    .await?;
+                    AuditEntry::by_sender(&msg, Some(&repo_key), "codeowners")
+                        .before(record.codeowners.as_deref().unwrap_or("none"))
+                        .after(codeowners.unwrap_or("none"))
+                        .record(&mut transaction)
+                        .await?;
+                    transaction.commit().await?;
+                    send_text(&bot, msg.chat.id, language.tr("codeowners-updated")).await?;
+                }
+                Err(e) => {
+                    send_text(
+                        &bot,
+                        msg.chat.id,
+                        language.tr_with("invalid-codeowners", &[("error", e.to_string().into())]),
+                    )
+                    .await?;
+                }
+            }
+        }
+        RepoCommand::Template(args) => match TemplateCommand::parse(&args) {
+            Ok(TemplateCommand::List) => {
+                let templates = repo_templates(&sqlite_pool, &repo_key).await?;
+
+                if templates.is_empty() {
+                    send_text(&bot, msg.chat.id, language.tr("templates-empty")).await?;
+                } else {
+                    let message = templates.into_iter().fold(
+                        MessageBuilder::new().text(language.tr("templates-header")),
+                        |message, (kind, template)| message.line().bold(kind).line().code(template),
+                    );
+                    send_message(&bot, msg.chat.id, &message).await?;
+                }
+            }
+            Ok(TemplateCommand::Preview(kind)) => {
+                let mut connection = sqlite_pool.acquire().await?;
+                let template = find_template(&mut connection, &repo_key, kind).await?;
+                let context = latest_context(language, &sqlite_pool, &repo_key, kind).await?;
+
+                match (template, context) {
+                    (None, _) => {
+                        send_text(
+                            &bot,
+                            msg.chat.id,
+                            language
+                                .tr_with("template-missing", &[("kind", kind.to_string().into())]),
+                        )
+                        .await?;
+                    }
+                    (Some(_), None) => {
+                        send_text(&bot, msg.chat.id, language.tr("template-no-job")).await?;
+                    }
+                    (Some(template), Some(context)) => {
+                        match Template::parse(&template)?.render(&context) {
+                            Some(preview) => send_message(&bot, msg.chat.id, &preview).await?,
+                            None => {
+                                send_text(&bot, msg.chat.id, language.tr("template-blank")).await?
+                            }
+                        };
+                    }
+                }
+            }
+            Ok(TemplateCommand::Set(kind, template)) => {
+                let mut transaction = sqlite_pool.begin().await?;
+                let previous =
+                    set_template(&mut transaction, &repo_key, kind, Some(&template)).await?;
+                AuditEntry::by_sender(&msg, Some(&repo_key), "template")
+                    .before(format!(
+                        "{kind}: {}",
+                        previous.as_deref().unwrap_or("default")
+                    ))
+                    .after(format!("{kind}: {template}"))
+                    .record(&mut transaction)
+                    .await?;
+                transaction.commit().await?;
+
+                let mut message = MessageBuilder::new()
+                    .text(language.tr_with("template-set", &[("kind", kind.to_string().into())]));
+
+                if let Some(context) =
+                    latest_context(language, &sqlite_pool, &repo_key, kind).await?
+                {
+                    message = match Template::parse(&template)?.render(&context) {
+                        Some(preview) => message
+                            .text(format!("{}\n\n", language.tr("template-set-preview")))
+                            .append(preview),
+                        None => message.text(format!("\n{}", language.tr("template-blank"))),
+                    };
+                }
+
+                send_message(&bot, msg.chat.id, &message).await?;
+            }
+            Ok(TemplateCommand::Reset(kind)) => {
+                let mut transaction = sqlite_pool.begin().await?;
+                let previous = set_template(&mut transaction, &repo_key, kind, None).await?;
+
+                if let Some(previous) = previous {
+                    AuditEntry::by_sender(&msg, Some(&repo_key), "template")
+                        .before(format!("{kind}: {previous}"))
+                        .after(format!("{kind}: default"))
+                        .record(&mut transaction)
+                        .await?;
+                }
+
+                transaction.commit().await?;
+                send_text(
+                    &bot,
+                    msg.chat.id,
+                    language.tr_with("template-reset", &[("kind", kind.to_string().into())]),
+                )
+                .await?;
+            }
+            Err(e) => {
+                send_text(
+                    &bot,
+                    msg.chat.id,
+                    language.tr_with("invalid-template", &[("error", e.to_string().into())]),
+                )
+                .await?;
+            }
+        },
+        RepoCommand::LinkMe(args) => link_me(&bot, &sqlite_pool, &msg, language, &args).await?,
+        RepoCommand::UnlinkMe(args) => unlink_me(&bot, &sqlite_pool, &msg, language, &args).await?,
+        RepoCommand::Mentions(args) => {
+            set_mentions(&bot, &sqlite_pool, &msg, language, &args).await?
+        }
+        RepoCommand::NotifyMe(args) => notify_me(&bot, &sqlite_pool, &msg, language, &args).await?,
+        RepoCommand::Language(args) => {
+            set_language(&bot, &sqlite_pool, &msg, language, &args).await?
+        }
+        RepoCommand::Timezone(args) => {
+            set_timezone(&bot, &sqlite_pool, &msg, language, &args).await?
+        }
+        RepoCommand::RemoveEmail(address) => {
+            let address = address.trim();
+            let mut transaction = sqlite_pool.begin().await?;
+            let result = query!(
+                r#"
+                DELETE FROM main.email_recipients
+                WHERE address = ?
+                AND repo_id = ?
+                "#,
+                address,
+                repo_key
+            )
+            .execute(&mut transaction)
+            .await?;
+
+            if result.rows_affected() > 0 {
+                AuditEntry::by_sender(&msg, Some(&repo_key), "remove_email")
+                    .before(address)
+                    .record(&mut transaction)
+                    .await?;
+            }
+
+            transaction.commit().await?;
+
+            if result.rows_affected() == 0 {
+                send_text(&bot, msg.chat.id, language.tr("email-missing")).await?;
+            } else {
+                send_text(
+                    &bot,
+                    msg.chat.id,
+                    language.tr_with("email-unsubscribed", &[("address", address.into())]),
+                )
+                .await?;
+            }
+        }
+    };
+    Ok(())
+}
+
+pub async fn normal_mode_handler(
+    bot: CiBot,
+    dialogue: MyDialogue,
+    sqlite_pool: Pool<Sqlite>,
+    msg: Message,
+    cmd: GeneralCommand,
+) -> HandlerResult {
+    let language = chat_language(&sqlite_pool, msg.chat.id.0).await?;
+
+    match cmd {
+        GeneralCommand::Help => {
+            send_text(
+                &bot,
+                msg.chat.id,
+                help_text(language, "general-cmd", GeneralCommand::bot_commands()),
+            )
+            .await?;
+        }
+        GeneralCommand::List => {
+            let repos = chat_repos(&sqlite_pool, msg.chat.id).await?;
+            send_repo_picker(&bot, language, &msg, &repos).await?;
+        }
+        GeneralCommand::Today => {
+            let date_time = Utc::now().naive_utc();
+            let timezone = chat_timezone(&sqlite_pool, msg.chat.id.0).await?;
+            let Some(beginning_of_today) = start_of_day(timezone, date_time) else {
+                return Err(Box::new(ServiceError::ChronoDatetime));
+            };
+            let jobs = query!(
+                r#"
+                SELECT jobs.id AS "id!",
+                    jobs.status AS "status!: DeployStatus",
+                    repos.name AS "repo_name!",
+                    jobs.branch,
+                    jobs.triggered_by,
+                    jobs.callback_url,
+                    jobs.started_at AS "started_at!",
+                    jobs.elapsed
+                FROM main.jobs
+                JOIN repos ON jobs.repo_id = repos.id
+                WHERE repos.message_id = ?
+                AND repos.deleted_at IS NULL
+                AND jobs.started_at >= ?
+                ORDER BY jobs.started_at
+                "#,
+                msg.chat.id.0,
+                beginning_of_today
+            )
+            .fetch_all(&sqlite_pool)
+            .await?
+            .into_iter()
+            .map(|record| JobListing {
+                id: record.id,
+                status: record.status,
+                repo_name: Some(record.repo_name),
+                branch: record.branch,
+                triggered_by: record.triggered_by,
+                url: record.callback_url,
+                started_at: record.started_at,
+                elapsed: record.elapsed,
+            })
+            .collect::<Vec<JobListing>>();
+
+            if jobs.is_empty() {
+                send_text(&bot, msg.chat.id, language.tr("today-empty")).await?;
+            } else {
+                let header = MessageBuilder::new()
+                    .text("📅 ")
+                    .bold(language.tr("today-header"));
+                let lines = format_jobs(language, header, &jobs, date_time);
+                send_lines(&bot, msg.chat.id, lines).await?;
+            }
+        }
+        GeneralCommand::Create(name) => {
+            let mut transaction = sqlite_pool.begin().await?;
+            // repo names are unique across every chat
+            let taken = query!(
+                r#"
+                SELECT EXISTS (
+                    SELECT 1
+                    FROM main.repos
+                    WHERE name = ?
+                ) AS "taken!: bool"
+                "#,
+                name
+            )
+            .fetch_one(&mut transaction)
+            .await?
+            .taken;
+
+            if taken {
+                send_text(
+                    &bot,
+                    msg.chat.id,
+                    language.tr_with("repo-name-taken", &[("name", name.as_str().into())]),
+                )
+                .await?;
+                return Ok(());
+            }
+
+            let uuid = Uuid::new_v4().simple().to_string();
+            query!(
+                r#"
+                INSERT INTO main.repos 
+                (id, name, message_id)
+                VALUES (?, ?, ?)
+                "#,
+                uuid,
+                name,
+                msg.chat.id.0
+            )
+            .execute(&mut transaction)
+            .await?;
+
+            // whoever creates a repo in a group owns it regardless of their membership
+            if let (false, Some(user)) = (msg.chat.is_private(), msg.from()) {
+                let user_id = user.id.0 as i64;
+                let user_name = display_name(user);
+                query!(
+                    r#"
+                    INSERT INTO main.repo_roles
+                    (repo_id, user_id, name, role)
+                    VALUES (?, ?, ?, ?)
+                    "#,
+                    uuid,
+                    user_id,
+                    user_name,
+                    Role::Owner
+                )
+                .execute(&mut transaction)
+                .await?;
+            }
+
+            AuditEntry::by_sender(&msg, Some(&uuid), "create")
+                .after(&name)
+                .record(&mut transaction)
+                .await?;
+            transaction.commit().await?;
+            send_text(
+                &bot,
+                msg.chat.id,
+                language.tr_with("repo-created", &[("name", name.as_str().into())]),
+            )
+            .await?;
+            send_text(
+                &bot,
+                msg.chat.id,
+                format!("{} {uuid}", language.tr("info-key")),
+            )
+            .await?;
+        }
+        GeneralCommand::SelectRepo(index) if index.trim().is_empty() => {
+            let repos = chat_repos(&sqlite_pool, msg.chat.id).await?;
+            send_repo_picker(&bot, language, &msg, &repos).await?;
+        }
+        GeneralCommand::SelectRepo(index) => {
+            let repos = chat_repos(&sqlite_pool, msg.chat.id).await?;
+            let index = index.trim().parse::<usize>().ok();
+
+            match index
+                .and_then(|index| index.checked_sub(1))
+                .and_then(|index| repos.get(index))
+            {
+                Some((id, name)) => {
+                    send_text(
+                        &bot,
+                        msg.chat.id,
+                        language.tr_with("repo-selecting", &[("name", name.as_str().into())]),
+                    )
+                    .await?;
+                    dialogue.update(BotState::Config(id.clone())).await?;
+                }
+                None => {
+                    send_text(&bot, msg.chat.id, language.tr("repo-missing")).await?;
+                }
+            };
+        }
+        GeneralCommand::History(args) => {
+            search(
+                &bot,
+                &sqlite_pool,
+                &msg,
+                language,
+                None,
+                &args,
+                parse_history,
+            )
+            .await?
+        }
+        GeneralCommand::Search(args) => {
+            search(
+                &bot,
+                &sqlite_pool,
+                &msg,
+                language,
+                None,
+                &args,
+                parse_search,
+            )
+            .await?
+        }
+        GeneralCommand::Digest(args) => match DigestCommand::parse(&args) {
+            Ok(DigestCommand::List) => {
+                let records = query!(
+                    r#"
+                    SELECT period AS "period: DigestPeriod", schedule, timezone, last_run_at
+                    FROM main.digests
+                    WHERE chat_id = ?
+                    ORDER BY period
+                    "#,
+                    msg.chat.id.0
+                )
+                .fetch_all(&sqlite_pool)
+                .await?;
+
+                if records.is_empty() {
+                    send_text(&bot, msg.chat.id, language.tr("digests-empty")).await?;
+                } else {
+                    let chat_timezone = chat_timezone(&sqlite_pool, msg.chat.id.0).await?;
+                    let mut lines = vec![];
+
+                    for record in records {
+                        let next =
+                            next_run(&record.schedule, &record.timezone, record.last_run_at)?
+                                .map_or(language.tr("digest-never"), |next| {
+                                    format_time(chat_timezone, next)
+                                });
+                        lines.push(language.tr_with(
+                            "digest-line",
+                            &[
+                                ("period", record.period.to_string().into()),
+                                ("schedule", record.schedule.into()),
+                                ("timezone", record.timezone.into()),
+                                ("next", next.into()),
+                            ],
+                        ));
+                    }
+
+                    send_text(&bot, msg.chat.id, lines.join("\n")).await?;
+                }
+            }
+            Ok(DigestCommand::Off(period)) => {
+                let result = query!(
+                    r#"
+                    DELETE FROM main.digests
+                    WHERE chat_id = ?
+                    AND (? IS NULL OR period = ?)
+                    "#,
+                    msg.chat.id.0,
+                    period,
+                    period
+                )
+                .execute(&sqlite_pool)
+                .await?;
+
+                if result.rows_affected() == 0 {
+                    send_text(&bot, msg.chat.id, language.tr("digest-none")).await?;
+                } else {
+                    AuditEntry::by_sender(&msg, None, "digest")
+                        .before(period.map_or("all".to_string(), |period| period.to_string()))
+                        .after("off")
+                        .record(&sqlite_pool)
+                        .await?;
+                    send_text(&bot, msg.chat.id, language.tr("digest-stopped")).await?;
+                }
+            }
+            Ok(DigestCommand::Schedule {
+                period,
+                schedule,
+                timezone,
+            }) => {
+                let now = Utc::now().naive_utc();
+                let chat_timezone = chat_timezone(&sqlite_pool, msg.chat.id.0).await?;
+                let timezone = timezone.unwrap_or(chat_timezone).name();
+                let next = next_run(&schedule, timezone, now)?;
+                query!(
+                    r#"
+                    INSERT INTO main.digests
+                    (chat_id, period, schedule, timezone, last_run_at)
+                    VALUES (?, ?, ?, ?, ?)
+                    ON CONFLICT (chat_id, period)
+                    DO UPDATE SET schedule = excluded.schedule,
+                        timezone = excluded.timezone,
+                        last_run_at = excluded.last_run_at
+                    "#,
+                    msg.chat.id.0,
+                    period,
+                    schedule,
+                    timezone,
+                    now
+                )
+                .execute(&sqlite_pool)
+                .await?;
+                AuditEntry::by_sender(&msg, None, "digest")
+                    .after(format!("{period} {schedule} {timezone}"))
+                    .record(&sqlite_pool)
+                    .await?;
+                send_text(
+                    &bot,
+                    msg.chat.id,
+                    language.tr_with(
+                        "digest-scheduled",
+                        &[
+                            ("period", period.to_string().into()),
+                            (
+                                "next",
+                                next.map_or(language.tr("digest-never"), |next| {
+                                    format_time(chat_timezone, next)
+                                })
+                                .into(),
+                            ),
+                        ],
+                    ),
+                )
+                .await?;
+            }
+            Err(e) => {
+                send_text(
+                    &bot,
+                    msg.chat.id,
+                    language.tr_with("invalid-digest", &[("error", e.to_string().into())]),
+                )
+                .await?;
+            }
+        },
+        GeneralCommand::Dora(args) => match command_window(&args) {
+            Ok(window) => {
+                let metrics =
+                    compute_dora(&sqlite_pool, Scope::Chat(msg.chat.id.0), window).await?;
+
+                send_text(
+                    &bot,
+                    msg.chat.id,
+                    format_dora(language, &language.tr("dora-all-repos"), &metrics),
+                )
+                .await?;
+            }
+            Err(e) => {
+                send_text(
+                    &bot,
+                    msg.chat.id,
+                    language.tr_with("invalid-window", &[("error", e.to_string().into())]),
+                )
+                .await?;
+            }
+        },
+        GeneralCommand::LinkMe(args) => link_me(&bot, &sqlite_pool, &msg, language, &args).await?,
+        GeneralCommand::UnlinkMe(args) => {
+            unlink_me(&bot, &sqlite_pool, &msg, language, &args).await?
+        }
+        GeneralCommand::Mentions(args) => {
+            set_mentions(&bot, &sqlite_pool, &msg, language, &args).await?
+        }
+        GeneralCommand::NotifyMe(args) => {
+            notify_me(&bot, &sqlite_pool, &msg, language, &args).await?
+        }
+        GeneralCommand::Language(args) => {
+            set_language(&bot, &sqlite_pool, &msg, language, &args).await?
+        }
+        GeneralCommand::Timezone(args) => {
+            set_timezone(&bot, &sqlite_pool, &msg, language, &args).await?
+        }
+        GeneralCommand::Reset => {
+            let records = query!(
+                r#"
+                SELECT id
+                FROM main.repos
+                WHERE message_id = ?
+                AND deleted_at IS NULL
+                "#,
+                msg.chat.id.0
+            )
+            .fetch_all(&sqlite_pool)
+            .await?;
+
+            // every repo of the chat is deleted so the sender has to own all of them
+            for record in records {
+                if sender_role(&bot, &sqlite_pool, &record.id, &msg).await? < Role::Owner {
+                    send_text(&bot, msg.chat.id, language.tr("reset-owner-required")).await?;
+                    return Ok(());
+                }
+            }
+
+            send_text(
+                &bot,
+                msg.chat.id,
+                language.tr_with(
+                    "reset-confirm",
+                    &[("retention", format_duration(language, retention()).into())],
+                ),
+            )
+            .reply_markup(Confirmation::Reset.keyboard(language))
+            .await?;
+        }
+        GeneralCommand::Undo => {
+            // restoring repos is as privileged as deleting them
+            for repo_id in latest_deleted(&sqlite_pool, msg.chat.id.0).await? {
+                if sender_role(&bot, &sqlite_pool, &repo_id, &msg).await? < Role::Owner {
+                    send_text(&bot, msg.chat.id, language.tr("undo-owner-required")).await?;
+                    return Ok(());
+                }
+            }
+
+            let Restore { restored, taken } = restore_latest(&sqlite_pool, msg.chat.id.0).await?;
+
+            if !taken.is_empty() {
+                send_text(
+                    &bot,
+                    msg.chat.id,
+                    language.tr_with("undo-taken", &[("names", taken.join(", ").into())]),
+                )
+                .await?;
+            }
+
+            if restored.is_empty() {
+                if taken.is_empty() {
+                    send_text(&bot, msg.chat.id, language.tr("undo-empty")).await?;
+                }
+            } else {
+                let names = restored
+                    .iter()
+                    .map(|(_, name)| name.as_str())
+                    .collect::<Vec<&str>>()
+                    .join(", ");
+                for (id, name) in &restored {
+                    AuditEntry::by_sender(&msg, Some(id), "undo")
+                        .before("deleted")
+                        .after(name)
+                        .record(&sqlite_pool)
+                        .await?;
+                }
+
+                send_text(
+                    &bot,
+                    msg.chat.id,
+                    language.tr_with("undo-restored", &[("names", names.into())]),
+                )
+                .await?;
+            }
+        }
+    };
+    Ok(())
+}
+
+async fn link_me(
+    bot: &CiBot,
+    pool: &Pool<Sqlite>,
+    msg: &Message,
+    language: Language,
+    args: &str,
+) -> HandlerResult {
+    let Some(user) = msg.from() else {
+        return Ok(());
+    };
+    let ci_username = match parse_ci_username(args) {
+        Ok(ci_username) => ci_username,
+        Err(e) => {
+            send_text(
+                bot,
+                msg.chat.id,
+                language.tr_with("invalid-ci-username", &[("error", e.to_string().into())]),
+            )
+            .await?;
+            return Ok(());
+        }
+    };
+
+    match link(
+        pool,
+        msg.chat.id.0,
+        &ci_username,
+        user.id.0 as i64,
+        &user.full_name(),
+    )
+    .await?
+    {
+        Link::Linked => {
+            AuditEntry::by_sender(msg, None, "link_me")
+                .after(&ci_username)
+                .record(pool)
+                .await?;
+            send_text(
+                bot,
+                msg.chat.id,
+                language.tr_with(
+                    if msg.chat.is_private() {
+                        "link-success-private"
+                    } else {
+                        "link-success"
+                    },
+                    &[
+                        ("ci_username", ci_username.as_str().into()),
+                        ("name", display_name(user).into()),
+                    ],
+                ),
+            )
+            .await?;
+        }
+        Link::Taken { name } => {
+            send_text(
+                bot,
+                msg.chat.id,
+                language.tr_with(
+                    if msg.chat.is_private() {
+                        "link-taken-private"
+                    } else {
+                        "link-taken"
+                    },
+                    &[
+                        ("ci_username", ci_username.as_str().into()),
+                        ("name", name.into()),
+                    ],
+                ),
+            )
+            .await?;
+        }
+        Link::Other { ci_username } => {
+            send_text(
+                bot,
+                msg.chat.id,
+                language.tr_with("link-other", &[("ci_username", ci_username.into())]),
+            )
+            .await?;
+        }
+    }
+
+    Ok(())
+}
+
+async fn unlink_me(
+    bot: &CiBot,
+    pool: &Pool<Sqlite>,
+    msg: &Message,
+    language: Language,
+    args: &str,
+) -> HandlerResult {
+    let Some(user) = msg.from() else {
+        return Ok(());
+    };
+    let ci_username = match parse_ci_username(args) {
+        Ok(ci_username) => ci_username,
+        Err(e) => {
+            send_text(
+                bot,
+                msg.chat.id,
+                language.tr_with("invalid-ci-username", &[("error", e.to_string().into())]),
+            )
+            .await?;
+            return Ok(());
+        }
+    };
+    let Some((user_id, name)) = linked_user(pool, msg.chat.id.0, &ci_username).await? else {
+        send_text(
+            bot,
+            msg.chat.id,
+            language.tr_with(
+                "unlink-missing",
+                &[("ci_username", ci_username.as_str().into())],
+            ),
+        )
+        .await?;
+        return Ok(());
+    };
+
+    // only the owner of the chat can take back a username someone else claimed
+    if user_id != user.id.0 as i64 && sender_chat_role(bot, pool, msg).await? < Role::Owner {
+        send_text(
+            bot,
+            msg.chat.id,
+            language.tr_with(
+                "unlink-owner-required",
+                &[
+                    ("ci_username", ci_username.as_str().into()),
+                    ("name", name.into()),
+                ],
+            ),
+        )
+        .await?;
+        return Ok(());
+    }
+
+    if unlink(pool, msg.chat.id.0, &ci_username).await? {
+        AuditEntry::by_sender(msg, None, "unlink_me")
+            .before(format!("{ci_username} ({name})"))
+            .record(pool)
+            .await?;
+        send_text(
+            bot,
+            msg.chat.id,
+            language.tr_with(
+                "unlink-success",
+                &[("ci_username", ci_username.as_str().into())],
+            ),
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+async fn set_mentions(
+    bot: &CiBot,
+    pool: &Pool<Sqlite>,
+    msg: &Message,
+    language: Language,
+    args: &str,
+) -> HandlerResult {
+    let Some(user) = msg.from() else {
+        return Ok(());
+    };
+    let mode = match MentionMode::try_from(args) {
+        Ok(mode) => mode,
+        Err(e) => {
+            send_text(
+                bot,
+                msg.chat.id,
+                language.tr_with("invalid-mentions", &[("error", e.to_string().into())]),
+            )
+            .await?;
+            return Ok(());
+        }
+    };
+    let user_id = user.id.0 as i64;
+    let result = query!(
+        r#"
+        UPDATE main.identities
+        SET mention = ?
+        WHERE user_id = ?
+        "#,
+        mode,
+        user_id
+    )
+    .execute(pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        send_text(bot, msg.chat.id, language.tr("identity-missing")).await?;
+    } else {
+        AuditEntry::by_sender(msg, None, "mentions")
+            .after(mode)
+            .record(pool)
+            .await?;
+        send_text(
+            bot,
+            msg.chat.id,
+            language.tr_with("mentions-set", &[("mode", mode.to_string().into())]),
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+async fn notify_me(
+    bot: &CiBot,
+    pool: &Pool<Sqlite>,
+    msg: &Message,
+    language: Language,
+    args: &str,
+) -> HandlerResult {
+    let Some(user) = msg.from() else {
+        return Ok(());
+    };
+    let preference = match NotifyPreference::try_from(args) {
+        Ok(preference) => preference,
+        Err(e) => {
+            send_text(
+                bot,
+                msg.chat.id,
+                language.tr_with("invalid-notify", &[("error", e.to_string().into())]),
+            )
+            .await?;
+            return Ok(());
+        }
+    };
+
+    // a bot cannot start a private chat, the user has to open one first
+    if preference != NotifyPreference::Off && !msg.chat.is_private() {
+        send_text(bot, msg.chat.id, language.tr("notify-private")).await?;
+        return Ok(());
+    }
+
+    let user_id = user.id.0 as i64;
+    let result = query!(
+        r#"
+        UPDATE main.identities
+        SET notify = ?
+        WHERE user_id = ?
+        "#,
+        preference,
+        user_id
+    )
+    .execute(pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        send_text(bot, msg.chat.id, language.tr("identity-missing")).await?;
+    } else {
+        AuditEntry::by_sender(msg, None, "notify_me")
+            .after(preference)
+            .record(pool)
+            .await?;
+        send_text(
+            bot,
+            msg.chat.id,
+            language.tr_with(
+                "notify-set",
+                &[("preference", preference.to_string().into())],
+            ),
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// show or change the language of the chat, which every member of the chat can do
+async fn set_language(
+    bot: &CiBot,
+    pool: &Pool<Sqlite>,
+    msg: &Message,
+    language: Language,
+    args: &str,
+) -> HandlerResult {
+    if args.trim().is_empty() {
+        send_text(bot, msg.chat.id, language.tr("language-current")).await?;
+        return Ok(());
+    }
+
+    let chosen = match Language::try_from(args) {
+        Ok(chosen) => chosen,
+        Err(e) => {
+            send_text(
+                bot,
+                msg.chat.id,
+                language.tr_with("invalid-language", &[("error", e.to_string().into())]),
+            )
+            .await?;
+            return Ok(());
+        }
+    };
+
+    set_chat_language(pool, msg.chat.id.0, chosen).await?;
+    AuditEntry::by_sender(msg, None, "language")
+        .before(language)
+        .after(chosen)
+        .record(pool)
+        .await?;
+    send_text(bot, msg.chat.id, chosen.tr("language-set")).await?;
+
+    Ok(())
+}
+
+/// show or change the timezone of the chat, which every member of the chat can do
+async fn set_timezone(
+    bot: &CiBot,
+    pool: &Pool<Sqlite>,
+    msg: &Message,
+    language: Language,
+    args: &str,
+) -> HandlerResult {
+    let timezone = chat_timezone(pool, msg.chat.id.0).await?;
+
+    if args.trim().is_empty() {
+        send_text(
+            bot,
+            msg.chat.id,
+            language.tr_with("timezone-current", &[("timezone", timezone.name().into())]),
+        )
+        .await?;
+        return Ok(());
+    }
+
+    let chosen = match parse_timezone(args.trim()) {
+        Ok(chosen) => chosen,
+        Err(e) => {
+            send_text(
+                bot,
+                msg.chat.id,
+                language.tr_with("invalid-timezone", &[("error", e.to_string().into())]),
+            )
+            .await?;
+            return Ok(());
+        }
+    };
+
+    set_chat_timezone(pool, msg.chat.id.0, chosen).await?;
+    AuditEntry::by_sender(msg, None, "timezone")
+        .before(timezone.name())
+        .after(chosen.name())
+        .record(pool)
+        .await?;
+    send_text(
+        bot,
+        msg.chat.id,
+        language.tr_with("timezone-set", &[("timezone", chosen.name().into())]),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// search the jobs of a repo, or of every repo of the chat, and send the first page of the
+/// results with buttons to page through the rest
+async fn search(
+    bot: &CiBot,
+    pool: &Pool<Sqlite>,
+    msg: &Message,
+    language: Language,
+    repo_id: Option<&str>,
+    args: &str,
+    parse: fn(&str) -> Result<JobFilter, ServiceError>,
+) -> HandlerResult {
+    let filter = match parse(args) {
+        Ok(filter) => filter,
+        Err(e) => {
+            send_text(
+                bot,
+                msg.chat.id,
+                language.tr_with("invalid-search", &[("error", e.to_string().into())]),
+            )
+            .await?;
+            return Ok(());
+        }
+    };
+
+    let search = save_search(pool, msg.chat.id.0, repo_id, args.trim()).await?;
+    let timezone = chat_timezone(pool, msg.chat.id.0).await?;
+    let (message, keyboard) = search_page(language, pool, &search, &filter, timezone, 0).await?;
+    send_message(bot, msg.chat.id, &message)
+        .reply_markup(keyboard)
+        .await?;
+
+    Ok(())
+}
+
+pub async fn callback_handler(
+    bot: CiBot,
+    dialogue: MyDialogue,
+    sqlite_pool: Pool<Sqlite>,
+    q: CallbackQuery,
+) -> HandlerResult {
+    if let (Some(confirmation), Some(message)) =
+        (q.data.as_deref().and_then(Confirmation::parse), &q.message)
+    {
+        return confirm_handler(&bot, &dialogue, &sqlite_pool, &q, message, confirmation).await;
+    }
+
+    let (Some(job_id), Some(message)) = (q.data.as_deref().and_then(parse_ack_data), &q.message)
+    else {
+        bot.answer_callback_query(q.id).await?;
+        return Ok(());
+    };
+
+    let language = chat_language(&sqlite_pool, message.chat.id.0).await?;
+    let text = match acknowledge(&sqlite_pool, job_id, message.chat.id, &q.from).await? {
+        Acknowledgement::Acknowledged { repo_id, repo_name } => {
+            AuditEntry::by_user(&q.from, message.chat.id, Some(&repo_id), "ack")
+                .after(format!("#{job_id}"))
+                .record(&sqlite_pool)
+                .await?;
+            let name = display_name(&q.from);
+            info!("{name} acknowledged failure of job {job_id}");
+            send_text(
+                &bot,
+                message.chat.id,
+                language.tr_with(
+                    "ack-on-it",
+                    &[
+                        ("name", name.into()),
+                        ("repo", repo_name.into()),
+                        ("job", job_id.to_string().into()),
+                    ],
+                ),
+            )
+            .await?;
+
+            language.tr("ack-thanks")
+        }
+        Acknowledgement::AlreadyAcknowledged { by } => {
+            language.tr_with("ack-already", &[("name", by.into())])
+        }
+        Acknowledgement::Resolved => language.tr("ack-resolved"),
+        Acknowledgement::NotFound => language.tr("ack-not-found"),
+    };
+
+    bot.answer_callback_query(q.id).text(text).await?;
+    Ok(())
+}
+
+/// the buttons of the repo picker and the job browser, which edit their message in place
+pub async fn browser_handler(
+    bot: CiBot,
+    dialogue: MyDialogue,
+    sqlite_pool: Pool<Sqlite>,
+    q: CallbackQuery,
+    action: BrowserAction,
+) -> HandlerResult {
+    let Some(message) = &q.message else {
+        bot.answer_callback_query(q.id).await?;
+        return Ok(());
+    };
+    let language = chat_language(&sqlite_pool, message.chat.id.0).await?;
+    let repos = chat_repos(&sqlite_pool, message.chat.id).await?;
+    let repo = |repo_id: &str| repos.iter().find(|(id, _)| id == repo_id).cloned();
+
+    match action {
+        BrowserAction::Repos(_) if repos.is_empty() => {
+            let text = MessageBuilder::new().text(language.tr("repos-empty"));
+            edit_in_place(&bot, message, text, None).await?;
+        }
+        BrowserAction::Repos(page) => {
+            let (picker, keyboard) = repo_picker(language, &repos, page);
+            edit_in_place(&bot, message, picker, Some(keyboard)).await?;
+        }
+        BrowserAction::Select(repo_id) => match repo(&repo_id) {
+            Some((id, name)) => {
+                dialogue.update(BotState::Config(id)).await?;
+                edit_in_place(
+                    &bot,
+                    message,
+                    MessageBuilder::new()
+                        .text(language.tr_with("repo-selected", &[("name", name.into())])),
+                    None,
+                )
+                .await?;
+            }
+            None => {
+                bot.answer_callback_query(q.id)
+                    .text(language.tr("repo-gone-short"))
+                    .await?;
+                return Ok(());
+            }
+        },
+        BrowserAction::Jobs { repo_id, page } => match repo(&repo_id) {
+            Some((id, name)) => {
+                let (jobs, keyboard) = job_page(language, &sqlite_pool, &id, &name, page).await?;
+                edit_in_place(&bot, message, jobs, Some(keyboard)).await?;
+            }
+            None => {
+                bot.answer_callback_query(q.id)
+                    .text(language.tr("repo-gone-short"))
+                    .await?;
+                return Ok(());
+            }
+        },
+        BrowserAction::Search { search_id, page } => {
+            match find_search(&sqlite_pool, search_id, message.chat.id.0).await? {
+                Some(search) => match search.filter() {
+                    Ok(filter) => {
+                        let timezone = chat_timezone(&sqlite_pool, message.chat.id.0).await?;
+                        let (jobs, keyboard) =
+                            search_page(language, &sqlite_pool, &search, &filter, timezone, page)
+                                .await?;
+                        edit_in_place(&bot, message, jobs, Some(keyboard)).await?;
+                    }
+                    Err(e) => {
+                        bot.answer_callback_query(q.id)
+                            .text(
+                                language
+                                    .tr_with("invalid-search", &[("error", e.to_string().into())]),
+                            )
+                            .await?;
+                        return Ok(());
+                    }
+                },
+                None => {
+                    bot.answer_callback_query(q.id)
+                        .text(language.tr("search-gone-short"))
+                        .await?;
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    bot.answer_callback_query(q.id).await?;
+    Ok(())
+}
+
+/// send lines in as few messages as telegram allows
+async fn send_lines(bot: &CiBot, chat_id: ChatId, lines: Vec<MessageBuilder>) -> HandlerResult {
+    for message in split_messages(lines, MESSAGE_LIMIT) {
+        send_message(bot, chat_id, &message).await?;
+    }
+
+    Ok(())
+}
+
+/// the help of a set of commands, each described by its `<namespace>-<command>` message
+fn help_text(language: Language, namespace: &str, commands: Vec<BotCommand>) -> String {
+    commands.into_iter().fold(
+        format!("{}\n", language.tr("help-header")),
+        |help, command| {
+            let name = command.command.trim_start_matches('/');
+            let description = language.tr(&format!("{namespace}-{name}"));
+
+            format!("{help}\n{} — {description}", command.command)
+        },
+    )
+}
+
+async fn send_repo_picker(
+    bot: &CiBot,
+    language: Language,
+    msg: &Message,
+    repos: &[(String, String)],
+) -> HandlerResult {
+    if repos.is_empty() {
+        send_text(bot, msg.chat.id, language.tr("repos-empty")).await?;
+        return Ok(());
+    }
+
+    let (picker, keyboard) = repo_picker(language, repos, 0);
+    send_message(bot, msg.chat.id, &picker)
+        .reply_markup(keyboard)
+        .await?;
+    Ok(())
+}
+
+/// replace the text and keyboard of a message. Pressing a button that leads to what is already
+/// shown, such as a page that has since become the last one, is not an error
+async fn edit_in_place(
+    bot: &CiBot,
+    message: &Message,
+    text: MessageBuilder,
+    keyboard: Option<InlineKeyboardMarkup>,
+) -> HandlerResult {
+    let request = edit_message_text(bot, message.chat.id, message.id, &text);
+    let result = match keyboard {
+        Some(keyboard) => request.reply_markup(keyboard).await,
+        None => request.await,
+    };
+
+    match result {
+        Err(RequestError::Api(ApiError::MessageNotModified)) => Ok(()),
+        result => result.map(|_| ()).map_err(Into::into),
+    }
+}
+
+async fn confirm_handler(
+    bot: &CiBot,
+    dialogue: &MyDialogue,
+    pool: &Pool<Sqlite>,
+    q: &CallbackQuery,
+    message: &Message,
+    confirmation: Confirmation,
+) -> HandlerResult {
+    let language = chat_language(pool, message.chat.id.0).await?;
+    let retention = format_duration(language, retention());
+    let text = match confirmation {
+        Confirmation::Cancel => language.tr("delete-cancelled"),
+        Confirmation::Delete(repo_id) => {
+            if member_role(bot, pool, &repo_id, &message.chat, &q.from).await? < Role::Owner {
+                bot.answer_callback_query(q.id.clone())
+                    .text(language.tr("delete-owner-required"))
+                    .await?;
+                return Ok(());
+            }
+
+            match delete_repo(pool, &repo_id, message.chat.id.0).await? {
+                Some(name) => {
+                    AuditEntry::by_user(&q.from, message.chat.id, Some(&repo_id), "delete")
+                        .before(&name)
+                        .after("deleted")
+                        .record(pool)
+                        .await?;
+                    if let Some(BotState::Config(key)) = dialogue.get().await? {
+                        if key == repo_id {
+                            dialogue.update(BotState::Normal).await?;
+                        }
+                    }
+
+                    info!("{} deleted repo {repo_id}", display_name(&q.from));
+                    language.tr_with(
+                        "delete-success",
+                        &[("repo", name.into()), ("retention", retention.into())],
+                    )
+                }
+                None => language.tr("delete-already"),
+            }
+        }
+        Confirmation::Reset => {
+            let records = query!(
+                r#"
+                SELECT id
+                FROM main.repos
+                WHERE message_id = ?
+                AND deleted_at IS NULL
+                "#,
+                message.chat.id.0
+            )
+            .fetch_all(pool)
+            .await?;
+
+            for record in records {
+                if member_role(bot, pool, &record.id, &message.chat, &q.from).await? < Role::Owner {
+                    bot.answer_callback_query(q.id.clone())
+                        .text(language.tr("reset-owner-required"))
+                        .await?;
+                    return Ok(());
+                }
+            }
+
+            let deleted = delete_chat_repos(pool, message.chat.id.0).await?;
+
+            for (repo_id, name) in &deleted {
+                AuditEntry::by_user(&q.from, message.chat.id, Some(repo_id), "reset")
+                    .before(name)
+                    .after("deleted")
+                    .record(pool)
+                    .await?;
+            }
+
+            dialogue.update(BotState::Start).await?;
+            info!("{} reset chat {}", display_name(&q.from), message.chat.id);
+            language.tr_with(
+                "reset-success",
+                &[
+                    ("count", deleted.len().into()),
+                    ("retention", retention.into()),
+                ],
+            )
+        }
+    };
+
+    edit_message_text(
+        bot,
+        message.chat.id,
+        message.id,
+        &MessageBuilder::new().text(text),
+    )
+    .await?;
+    bot.answer_callback_query(q.id.clone()).await?;
+    Ok(())
+}
+
+pub async fn invalid_command(bot: CiBot, sqlite_pool: Pool<Sqlite>, msg: Message) -> HandlerResult {
+    info!("invalid command: {}", msg.chat.id);
+    let language = chat_language(&sqlite_pool, msg.chat.id.0).await?;
+    send_text(&bot, msg.chat.id, language.tr("invalid-command")).await?;
     Ok(())
 }
@@ -3,63 +3,99 @@ use serde::{Deserialize, Serialize};
 use std::fmt::Display;
 use teloxide::macros::BotCommands;
 
+/// the dialogue of a chat. Only the selected repo is kept here, the repos of a chat are always
+/// read from `main.repos` by chat id
 #[derive(Clone, Default, Serialize, Deserialize)]
 pub enum BotState {
     #[default]
     Start,
+    /// superseded by `Config`. Kept, along with `NormalMode`, so that states stored in redis
+    /// by older versions still deserialize since bincode identifies variants by their index
     ConfigMode(Vec<String>, String),
     NormalMode(Vec<String>),
+    Normal,
+    /// a repo is selected for manipulation
+    Config(String),
 }
 
+impl BotState {
+    /// the state of this version equivalent to a legacy state, if it is one
+    pub fn upgrade(&self) -> Option<Self> {
+        match self {
+            Self::ConfigMode(_, key) => Some(Self::Config(key.clone())),
+            Self::NormalMode(_) => Some(Self::Normal),
+            _ => None,
+        }
+    }
+}
+
+/// commands of a chat without a selected repo. Each is described by its `general-cmd-<command>`
+/// message of the catalogs
 #[derive(BotCommands, Clone)]
-#[command(
-    rename_rule = "snake_case",
-    description = "These commands are supported:"
-)]
+#[command(rename_rule = "snake_case")]
 pub enum GeneralCommand {
-    #[command(description = "display this text.")]
     Help,
-    #[command(description = "display all configured repos.")]
     List,
-    #[command(description = "display all jobs that was created today.")]
     Today,
-    #[command(
-        description = "create new repo in the following format: /create <repo_name>\ni.e. /create Turbo Incubator Prototype"
-    )]
+    History(String),
+    Search(String),
     Create(String),
-    #[command(
-        description = "select repo for manipulation by index in the following format: /select_repo <index>\ni.e. /select_repo 1"
-    )]
-    SelectRepo(usize),
-    #[command(description = "[DEBUG] Successfully reset all state.")]
+    SelectRepo(String),
+    Digest(String),
+    Dora(String),
+    LinkMe(String),
+    UnlinkMe(String),
+    Mentions(String),
+    NotifyMe(String),
+    Language(String),
+    Timezone(String),
+    Undo,
     Reset,
 }
 
+/// commands of a chat with a selected repo. Each is described by its `repo-cmd-<command>`
+/// message of the catalogs
 #[derive(BotCommands, Clone)]
-#[command(
-    rename_rule = "snake_case",
-    description = "These commands are supported:"
-)]
+#[command(rename_rule = "snake_case")]
 pub enum RepoCommand {
-    #[command(description = "display this text.")]
     Help,
-    #[command(description = "display current repo info.")]
     GetInfo,
-    #[command(description = "display all jobs that was created today for current repo.")]
     Today,
-    #[command(description = "display all running jobs for current repo.")]
     Running,
-    #[command(description = "get latest jobs created for this repo.")]
     Latest,
-    #[command(description = "rename current repo.")]
+    Jobs,
+    History(String),
+    Search(String),
     Rename(String),
-    #[command(description = "delete selected repo.")]
+    AddNotifier(String),
+    Notifiers,
+    RemoveNotifier(i64),
+    AddEmail(String),
+    Emails,
+    RemoveEmail(String),
+    Dora(String),
+    Chart(String),
+    Slowdown(String),
+    Flaky(String),
+    Oncall(String),
+    Escalation(String),
+    Codeowners(String),
+    Template(String),
+    LinkMe(String),
+    UnlinkMe(String),
+    Mentions(String),
+    NotifyMe(String),
+    Language(String),
+    Timezone(String),
+    Grant(String),
+    Revoke(String),
+    Roles,
+    Audit(String),
     Delete,
-    #[command(description = "deselect current repo for manipulation.")]
     Cancel,
 }
 
-#[derive(Default, Deserialize, sqlx::Type, PartialEq, Eq, Debug)]
+#[derive(Default, Deserialize, Serialize, sqlx::Type, PartialEq, Eq, Debug, Clone, Copy)]
 #[serde(rename_all = "lowercase")]
 #[sqlx(type_name = "status", rename_all = "UPPERCASE")]
 pub enum DeployStatus {
@@ -0,0 +1,283 @@
+//! Inline keyboards to pick a repo and to page through the job history of a repo or the results
+//! of a search. Every button edits the message it belongs to instead of sending a new one.
+use super::{
+    bot::state::DeployStatus,
+    format::{format_jobs, JobListing},
+    i18n::Language,
+    message::MessageBuilder,
+    search::{count_search_jobs, search_jobs, JobFilter, JobSearch},
+};
+use crate::app::util::error::ServiceError;
+use chrono::Utc;
+use chrono_tz::Tz;
+use sqlx::{query, Pool, Sqlite};
+use teloxide::types::{InlineKeyboardButton, InlineKeyboardMarkup};
+
+/// number of repos on a page of the picker
+const REPO_PAGE_SIZE: usize = 8;
+/// number of jobs on a page of the browser
+const JOB_PAGE_SIZE: i64 = 10;
+const REPOS_PREFIX: &str = "repos:";
+const SELECT_PREFIX: &str = "select:";
+const JOBS_PREFIX: &str = "jobs:";
+const SEARCH_PREFIX: &str = "search:";
+
+/// what a button of the picker or the browser asks for
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum BrowserAction {
+    /// a page of the repo picker, starting at 0
+    Repos(usize),
+    Select(String),
+    /// a page of the job history of a repo, starting at 0
+    Jobs {
+        repo_id: String,
+        page: i64,
+    },
+    /// a page of the results of a kept search, starting at 0
+    Search {
+        search_id: i64,
+        page: i64,
+    },
+}
+
+impl BrowserAction {
+    fn data(&self) -> String {
+        match self {
+            Self::Repos(page) => format!("{REPOS_PREFIX}{page}"),
+            Self::Select(repo_id) => format!("{SELECT_PREFIX}{repo_id}"),
+            Self::Jobs { repo_id, page } => format!("{JOBS_PREFIX}{repo_id}:{page}"),
+            Self::Search { search_id, page } => format!("{SEARCH_PREFIX}{search_id}:{page}"),
+        }
+    }
+
+    /// the action of a button's callback data
+    pub fn parse(data: &str) -> Option<Self> {
+        if let Some(page) = data.strip_prefix(REPOS_PREFIX) {
+            return page.parse().ok().map(Self::Repos);
+        }
+
+        if let Some(repo_id) = data.strip_prefix(SELECT_PREFIX) {
+            return Some(Self::Select(repo_id.to_string()));
+        }
+
+        if let Some((search_id, page)) = data
+            .strip_prefix(SEARCH_PREFIX)
+            .and_then(|search| search.split_once(':'))
+        {
+            return Some(Self::Search {
+                search_id: search_id.parse().ok()?,
+                page: page.parse().ok()?,
+            });
+        }
+
+        let (repo_id, page) = data.strip_prefix(JOBS_PREFIX)?.rsplit_once(':')?;
+
+        Some(Self::Jobs {
+            repo_id: repo_id.to_string(),
+            page: page.parse().ok()?,
+        })
+    }
+}
+
+/// previous and next buttons of a page, leaving out the ones that lead nowhere
+fn navigation(
+    previous: Option<BrowserAction>,
+    next: Option<BrowserAction>,
+) -> Vec<InlineKeyboardButton> {
+    [("◀️", previous), ("▶️", next)]
+        .into_iter()
+        .filter_map(|(text, action)| {
+            action.map(|action| InlineKeyboardButton::callback(text, action.data()))
+        })
+        .collect()
+}
+
+/// number of pages needed for a number of entries, an empty list still has a page
+fn page_count(entries: i64, page_size: i64) -> i64 {
+    ((entries + page_size - 1) / page_size).max(1)
+}
+
+/// the message and keyboard of a page of the repo picker. Pages past the last one show the last
+pub fn repo_picker(
+    language: Language,
+    repos: &[(String, String)],
+    page: usize,
+) -> (MessageBuilder, InlineKeyboardMarkup) {
+    let pages = page_count(repos.len() as i64, REPO_PAGE_SIZE as i64) as usize;
+    let page = page.min(pages - 1);
+    let mut rows = repos
+        .iter()
+        .skip(page * REPO_PAGE_SIZE)
+        .take(REPO_PAGE_SIZE)
+        .map(|(id, name)| {
+            vec![InlineKeyboardButton::callback(
+                name.clone(),
+                BrowserAction::Select(id.clone()).data(),
+            )]
+        })
+        .collect::<Vec<Vec<InlineKeyboardButton>>>();
+    let buttons = navigation(
+        page.checked_sub(1).map(BrowserAction::Repos),
+        (page + 1 < pages).then_some(BrowserAction::Repos(page + 1)),
+    );
+
+    if !buttons.is_empty() {
+        rows.push(buttons);
+    }
+
+    let message = MessageBuilder::new().text(match pages {
+        1 => language.tr("picker-header"),
+        pages => language.tr_with(
+            "picker-header-page",
+            &[("page", (page + 1).into()), ("pages", pages.into())],
+        ),
+    });
+
+    (message, InlineKeyboardMarkup::new(rows))
+}
+
+/// the message and keyboard of a page of the job history of a repo, the latest job first
+pub async fn job_page(
+    language: Language,
+    pool: &Pool<Sqlite>,
+    repo_id: &str,
+    repo_name: &str,
+    page: i64,
+) -> Result<(MessageBuilder, InlineKeyboardMarkup), ServiceError> {
+    let record = query!(
+        r#"
+        SELECT COUNT(*) AS "total!: i64"
+        FROM main.jobs
+        JOIN main.repos ON jobs.repo_id = repos.id
+        WHERE jobs.repo_id = ?
+        AND repos.deleted_at IS NULL
+        "#,
+        repo_id
+    )
+    .fetch_one(pool)
+    .await?;
+    let pages = page_count(record.total, JOB_PAGE_SIZE);
+    let page = page.clamp(0, pages - 1);
+    let offset = page * JOB_PAGE_SIZE;
+    let jobs = query!(
+        r#"
+        SELECT jobs.id AS "id!",
+            jobs.status AS "status!: DeployStatus",
+            jobs.branch,
+            jobs.triggered_by,
+            jobs.callback_url,
+            jobs.started_at,
+            jobs.elapsed
+        FROM main.jobs
+        JOIN main.repos ON jobs.repo_id = repos.id
+        WHERE jobs.repo_id = ?
+        AND repos.deleted_at IS NULL
+        ORDER BY jobs.started_at DESC, jobs.id DESC
+        LIMIT ?
+        OFFSET ?
+        "#,
+        repo_id,
+        JOB_PAGE_SIZE,
+        offset
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|record| JobListing {
+        id: record.id,
+        status: record.status,
+        repo_name: None,
+        branch: record.branch,
+        triggered_by: record.triggered_by,
+        url: record.callback_url,
+        started_at: record.started_at,
+        elapsed: record.elapsed,
+    })
+    .collect::<Vec<JobListing>>();
+
+    let header = MessageBuilder::new()
+        .text("📚 ")
+        .bold(language.tr_with("browser-header", &[("repo", repo_name.into())]))
+        .text(format!(
+            " {}",
+            language.tr_with(
+                "browser-page",
+                &[("page", (page + 1).into()), ("pages", pages.into())]
+            )
+        ));
+    let mut message = MessageBuilder::join(
+        format_jobs(language, header, &jobs, Utc::now().naive_utc()),
+        "\n",
+    );
+
+    if jobs.is_empty() {
+        message = message.text(format!("\n{}", language.tr("browser-empty")));
+    }
+
+    let jobs = |page| BrowserAction::Jobs {
+        repo_id: repo_id.to_string(),
+        page,
+    };
+    let buttons = navigation(
+        (page > 0).then(|| jobs(page - 1)),
+        (page + 1 < pages).then(|| jobs(page + 1)),
+    );
+
+    Ok((message, InlineKeyboardMarkup::new([buttons])))
+}
+
+/// the message and keyboard of a page of the results of a search, the latest job first
+pub async fn search_page(
+    language: Language,
+    pool: &Pool<Sqlite>,
+    search: &JobSearch,
+    filter: &JobFilter,
+    timezone: Tz,
+    page: i64,
+) -> Result<(MessageBuilder, InlineKeyboardMarkup), ServiceError> {
+    let total = count_search_jobs(pool, search, filter, timezone).await?;
+    let pages = page_count(total, JOB_PAGE_SIZE);
+    let page = page.clamp(0, pages - 1);
+    let jobs = search_jobs(
+        pool,
+        search,
+        filter,
+        timezone,
+        JOB_PAGE_SIZE,
+        page * JOB_PAGE_SIZE,
+    )
+    .await?;
+
+    let header = MessageBuilder::new()
+        .text("🔎 ")
+        .bold(match search.query.trim() {
+            "" => language.tr("search-header-all"),
+            query => language.tr_with("search-header", &[("query", query.into())]),
+        })
+        .text(format!(
+            " {}",
+            language.tr_with(
+                "browser-page",
+                &[("page", (page + 1).into()), ("pages", pages.into())]
+            )
+        ));
+    let mut message = MessageBuilder::join(
+        format_jobs(language, header, &jobs, Utc::now().naive_utc()),
+        "\n",
+    );
+
+    if jobs.is_empty() {
+        message = message.text(format!("\n{}", language.tr("search-empty")));
+    }
+
+    let results = |page| BrowserAction::Search {
+        search_id: search.id,
+        page,
+    };
+    let buttons = navigation(
+        (page > 0).then(|| results(page - 1)),
+        (page + 1 < pages).then(|| results(page + 1)),
+    );
+
+    Ok((message, InlineKeyboardMarkup::new([buttons])))
+}
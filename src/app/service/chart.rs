@@ -0,0 +1,384 @@
+//! PNG charts of a repo's job history rendered in process with `plotters`. Text is drawn with
+//! a bundled DejaVu Sans so rendering does not depend on the fonts installed on the host.
+use super::{bot::state::DeployStatus, dora::command_window};
+use crate::app::util::error::ServiceError;
+use chrono::{Datelike, Duration, NaiveDateTime, TimeZone, Timelike, Utc};
+use chrono_tz::Tz;
+use image::{codecs::png::PngEncoder, ColorType, ImageEncoder};
+use plotters::{
+    coord::ranged1d::{IntoSegmentedCoord, SegmentValue},
+    prelude::*,
+    style::register_font,
+};
+use sqlx::{query, Pool, Sqlite};
+use std::{fmt::Display, sync::Once};
+
+const WIDTH: u32 = 1024;
+const HEIGHT: u32 = 576;
+const FONT: &str = "sans-serif";
+const WEEKDAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+const SUCCESS_COLOR: RGBColor = RGBColor(46, 160, 67);
+const FAILURE_COLOR: RGBColor = RGBColor(218, 54, 51);
+const CANCELLED_COLOR: RGBColor = RGBColor(139, 148, 158);
+const HEAT_COLOR: RGBColor = RGBColor(31, 111, 235);
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum ChartKind {
+    /// build duration of every finished job over time
+    Duration,
+    /// successful and failed jobs per day
+    PassRate,
+    /// number of started jobs per weekday and hour of the day
+    Heatmap,
+}
+
+impl Display for ChartKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Duration => "duration",
+                Self::PassRate => "passrate",
+                Self::Heatmap => "heatmap",
+            }
+        )
+    }
+}
+
+impl TryFrom<&str> for ChartKind {
+    type Error = ServiceError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value.to_lowercase().as_str() {
+            "duration" => Ok(Self::Duration),
+            "passrate" | "pass_rate" | "pass-rate" => Ok(Self::PassRate),
+            "heatmap" => Ok(Self::Heatmap),
+            _ => Err(ServiceError::TryFrom {
+                field: "kind",
+                from: value.to_string(),
+                into: "ChartKind",
+                expect: "duration, passrate, or heatmap",
+            }),
+        }
+    }
+}
+
+/// parse the argument of `/chart` which is in the form of `<kind> [range]` where range is a
+/// window like `14d` or `4w`
+pub fn parse_chart_command(args: &str) -> Result<(ChartKind, Duration), ServiceError> {
+    let (kind, range) = args.trim().split_once(' ').unwrap_or((args.trim(), ""));
+
+    Ok((ChartKind::try_from(kind)?, command_window(range)?))
+}
+
+pub struct ChartJob {
+    started_at: NaiveDateTime,
+    status: DeployStatus,
+    elapsed: Option<i64>,
+}
+
+pub async fn load_chart_jobs(
+    pool: &Pool<Sqlite>,
+    repo_id: &str,
+    since: NaiveDateTime,
+) -> Result<Vec<ChartJob>, ServiceError> {
+    Ok(query!(
+        r#"
+        SELECT started_at, status AS "status!: DeployStatus", elapsed
+        FROM main.jobs
+        WHERE repo_id = ?
+        AND started_at >= ?
+        ORDER BY started_at
+        "#,
+        repo_id,
+        since
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|record| ChartJob {
+        started_at: record.started_at,
+        status: record.status,
+        elapsed: record.elapsed,
+    })
+    .collect())
+}
+
+fn register_fonts() -> Result<(), ServiceError> {
+    static REGISTER: Once = Once::new();
+    let mut result = Ok(());
+
+    REGISTER.call_once(|| {
+        result = register_font(
+            FONT,
+            FontStyle::Normal,
+            include_bytes!("../../../assets/fonts/DejaVuSans.ttf"),
+        )
+        .map_err(|_| ServiceError::Chart("bundled font could not be loaded".to_string()));
+    });
+
+    result
+}
+
+fn status_color(status: DeployStatus) -> RGBColor {
+    match status {
+        DeployStatus::Success => SUCCESS_COLOR,
+        DeployStatus::Failure => FAILURE_COLOR,
+        _ => CANCELLED_COLOR,
+    }
+}
+
+/// days elapsed between two points in time as a fraction
+fn days_between(since: NaiveDateTime, at: NaiveDateTime) -> f64 {
+    (at - since).num_seconds() as f64 / 86_400.0
+}
+
+fn format_day(since: NaiveDateTime, days: f64) -> String {
+    (since + Duration::seconds((days * 86_400.0) as i64))
+        .format("%b %d")
+        .to_string()
+}
+
+fn draw_duration(
+    root: &DrawingArea<BitMapBackend, plotters::coord::Shift>,
+    repo_name: &str,
+    jobs: &[ChartJob],
+    since: NaiveDateTime,
+    until: NaiveDateTime,
+) -> Result<(), ServiceError> {
+    let points = jobs
+        .iter()
+        .filter_map(|job| {
+            job.elapsed.map(|elapsed| {
+                (
+                    days_between(since, job.started_at),
+                    elapsed as f64 / 60.0,
+                    job.status,
+                )
+            })
+        })
+        .collect::<Vec<(f64, f64, DeployStatus)>>();
+    let max_minutes = points
+        .iter()
+        .map(|(_, minutes, _)| *minutes)
+        .fold(1.0, f64::max);
+    let mut chart = ChartBuilder::on(root)
+        .caption(format!("{repo_name} build duration"), (FONT, 28))
+        .margin(20)
+        .x_label_area_size(40)
+        .y_label_area_size(60)
+        .build_cartesian_2d(0.0..days_between(since, until), 0.0..max_minutes * 1.1)?;
+
+    chart
+        .configure_mesh()
+        .x_label_formatter(&|days| format_day(since, *days))
+        .y_desc("minutes")
+        .label_style((FONT, 14))
+        .draw()?;
+    chart.draw_series(LineSeries::new(
+        points.iter().map(|(days, minutes, _)| (*days, *minutes)),
+        CANCELLED_COLOR.stroke_width(1),
+    ))?;
+    chart.draw_series(points.iter().map(|(days, minutes, status)| {
+        Circle::new((*days, *minutes), 4, status_color(*status).filled())
+    }))?;
+
+    Ok(())
+}
+
+fn draw_pass_rate(
+    root: &DrawingArea<BitMapBackend, plotters::coord::Shift>,
+    repo_name: &str,
+    jobs: &[ChartJob],
+    since: NaiveDateTime,
+    until: NaiveDateTime,
+) -> Result<(), ServiceError> {
+    let days = ((until - since).num_days() + 1) as usize;
+    let mut counts = vec![(0, 0); days];
+
+    for job in jobs {
+        let day = (job.started_at - since).num_days() as usize;
+
+        match (job.status, counts.get_mut(day)) {
+            (DeployStatus::Success, Some((success, _))) => *success += 1,
+            (DeployStatus::Failure, Some((_, failure))) => *failure += 1,
+            _ => {}
+        }
+    }
+
+    let (success, failure) = counts.iter().fold((0, 0), |(s, f), (success, failure)| {
+        (s + success, f + failure)
+    });
+    let caption = match success + failure {
+        0 => format!("{repo_name} pass rate"),
+        finished => format!(
+            "{repo_name} pass rate {:.1}%",
+            success as f64 * 100.0 / finished as f64
+        ),
+    };
+    let max_jobs = counts
+        .iter()
+        .map(|(success, failure)| success + failure)
+        .max()
+        .unwrap_or_default()
+        .max(1);
+    let mut chart = ChartBuilder::on(root)
+        .caption(caption, (FONT, 28))
+        .margin(20)
+        .x_label_area_size(40)
+        .y_label_area_size(60)
+        .build_cartesian_2d(0.0..days as f64, 0.0..max_jobs as f64 * 1.1)?;
+
+    chart
+        .configure_mesh()
+        .disable_x_mesh()
+        .x_label_formatter(&|days| format_day(since, *days))
+        .y_desc("jobs")
+        .label_style((FONT, 14))
+        .draw()?;
+    chart
+        .draw_series(counts.iter().enumerate().map(|(day, (success, _))| {
+            let day = day as f64;
+
+            Rectangle::new(
+                [(day + 0.1, 0.0), (day + 0.9, *success as f64)],
+                SUCCESS_COLOR.filled(),
+            )
+        }))?
+        .label("success")
+        .legend(|(x, y)| Rectangle::new([(x, y - 5), (x + 10, y + 5)], SUCCESS_COLOR.filled()));
+    chart
+        .draw_series(counts.iter().enumerate().map(|(day, (success, failure))| {
+            let day = day as f64;
+
+            Rectangle::new(
+                [
+                    (day + 0.1, *success as f64),
+                    (day + 0.9, (success + failure) as f64),
+                ],
+                FAILURE_COLOR.filled(),
+            )
+        }))?
+        .label("failure")
+        .legend(|(x, y)| Rectangle::new([(x, y - 5), (x + 10, y + 5)], FAILURE_COLOR.filled()));
+    chart
+        .configure_series_labels()
+        .position(SeriesLabelPosition::UpperLeft)
+        .label_font((FONT, 14))
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
+        .draw()?;
+
+    Ok(())
+}
+
+fn draw_heatmap(
+    root: &DrawingArea<BitMapBackend, plotters::coord::Shift>,
+    repo_name: &str,
+    jobs: &[ChartJob],
+    timezone: Tz,
+) -> Result<(), ServiceError> {
+    let mut counts = [[0_u32; 24]; 7];
+
+    for job in jobs {
+        counts[job.started_at.weekday().num_days_from_monday() as usize]
+            [job.started_at.hour() as usize] += 1;
+    }
+
+    let max_jobs = counts.iter().flatten().copied().max().unwrap_or_default();
+    let mut chart = ChartBuilder::on(root)
+        .caption(
+            format!("{repo_name} jobs per weekday ({timezone})"),
+            (FONT, 28),
+        )
+        .margin(20)
+        .x_label_area_size(40)
+        .y_label_area_size(60)
+        .build_cartesian_2d((0_u32..23).into_segmented(), (0_u32..6).into_segmented())?;
+
+    chart
+        .configure_mesh()
+        .disable_mesh()
+        .x_labels(24)
+        .y_labels(7)
+        .x_label_formatter(&|hour| match hour {
+            SegmentValue::CenterOf(hour) => format!("{hour:02}"),
+            _ => String::new(),
+        })
+        .y_label_formatter(&|weekday| match weekday {
+            // monday is drawn on top
+            SegmentValue::CenterOf(weekday) => 6_usize
+                .checked_sub(*weekday as usize)
+                .and_then(|weekday| WEEKDAYS.get(weekday))
+                .map_or(String::new(), |weekday| weekday.to_string()),
+            _ => String::new(),
+        })
+        .x_desc("hour of the day")
+        .label_style((FONT, 14))
+        .draw()?;
+    chart.draw_series(counts.iter().enumerate().flat_map(|(weekday, hours)| {
+        let row = 6 - weekday as u32;
+
+        hours.iter().enumerate().map(move |(hour, count)| {
+            let hour = hour as u32;
+            let intensity = match max_jobs {
+                0 => 0.0,
+                max_jobs => *count as f64 / max_jobs as f64,
+            };
+
+            Rectangle::new(
+                [
+                    (SegmentValue::Exact(hour), SegmentValue::Exact(row)),
+                    (SegmentValue::Exact(hour + 1), SegmentValue::Exact(row + 1)),
+                ],
+                HEAT_COLOR.mix(0.05 + intensity * 0.95).filled(),
+            )
+        })
+    }))?;
+
+    Ok(())
+}
+
+/// render a chart of the given jobs into a PNG image with days and hours in the given timezone.
+/// Rendering is CPU bound so this is meant to be run with `spawn_blocking`
+pub fn render_chart(
+    kind: ChartKind,
+    repo_name: &str,
+    jobs: &[ChartJob],
+    since: NaiveDateTime,
+    timezone: Tz,
+) -> Result<Vec<u8>, ServiceError> {
+    register_fonts()?;
+
+    let local = |time: &NaiveDateTime| timezone.from_utc_datetime(time).naive_local();
+    let since = local(&since);
+    let until = local(&Utc::now().naive_utc());
+    let jobs = jobs
+        .iter()
+        .map(|job| ChartJob {
+            started_at: local(&job.started_at),
+            ..*job
+        })
+        .collect::<Vec<ChartJob>>();
+    let jobs = jobs.as_slice();
+    let mut buffer = vec![0_u8; (WIDTH * HEIGHT * 3) as usize];
+
+    {
+        let root = BitMapBackend::with_buffer(&mut buffer, (WIDTH, HEIGHT)).into_drawing_area();
+        root.fill(&WHITE)?;
+
+        match kind {
+            ChartKind::Duration => draw_duration(&root, repo_name, jobs, since, until)?,
+            ChartKind::PassRate => draw_pass_rate(&root, repo_name, jobs, since, until)?,
+            ChartKind::Heatmap => draw_heatmap(&root, repo_name, jobs, timezone)?,
+        }
+
+        root.present()?;
+    }
+
+    let mut png = vec![];
+    PngEncoder::new(&mut png).write_image(&buffer, WIDTH, HEIGHT, ColorType::Rgb8)?;
+
+    Ok(png)
+}
@@ -0,0 +1,136 @@
+//! Path ownership rules in the format of CODEOWNERS. Every line is a pattern followed by the
+//! telegram handles of its owners and, like CODEOWNERS, the last rule matching a path wins. A
+//! failed job that reports its changed files mentions the owners of those files.
+use crate::app::util::error::ServiceError;
+use globset::{GlobBuilder, GlobMatcher};
+use sqlx::{query, SqliteConnection};
+
+pub struct OwnerRule {
+    pub owners: Vec<String>,
+    matchers: Vec<GlobMatcher>,
+}
+
+impl OwnerRule {
+    pub fn is_match(&self, path: &str) -> bool {
+        let path = path.trim_start_matches('/');
+
+        self.matchers.iter().any(|matcher| matcher.is_match(path))
+    }
+}
+
+/// globs equivalent to a CODEOWNERS pattern. A pattern without a slash but a trailing one
+/// matches at any depth, any other pattern is relative to the root of the repo. A pattern
+/// also matches everything under the directories it names
+fn to_globs(pattern: &str) -> Vec<String> {
+    let directory = pattern.ends_with('/');
+    let trimmed = pattern.trim_end_matches('/');
+    let anchored = trimmed.starts_with('/') || trimmed.contains('/');
+    let trimmed = trimmed.trim_start_matches('/');
+    let glob = match anchored {
+        true => trimmed.to_string(),
+        false => format!("**/{trimmed}"),
+    };
+
+    match directory {
+        true => vec![format!("{glob}/**")],
+        false => vec![format!("{glob}/**"), glob],
+    }
+}
+
+fn parse_owner(owner: &str) -> Result<String, ServiceError> {
+    let username = owner.trim_start_matches('@');
+
+    if !owner.starts_with('@')
+        || username.is_empty()
+        || !username
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_')
+    {
+        return Err(ServiceError::ValidateFailure {
+            field: "owner",
+            reason: format!("{owner} is not a telegram username"),
+        });
+    }
+
+    Ok(format!("@{username}"))
+}
+
+/// parse ownership rules, blank lines and `#` comments are skipped. A rule without owners
+/// leaves its paths unowned
+pub fn parse_rules(text: &str) -> Result<Vec<OwnerRule>, ServiceError> {
+    let mut rules = vec![];
+
+    for (index, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        let mut tokens = line.split_whitespace();
+        let Some(pattern) = tokens.next() else {
+            continue;
+        };
+        let owners = tokens
+            .map(parse_owner)
+            .collect::<Result<Vec<String>, ServiceError>>()?;
+        let matchers = to_globs(pattern)
+            .iter()
+            .map(|glob| {
+                GlobBuilder::new(glob)
+                    .literal_separator(true)
+                    .build()
+                    .map(|glob| glob.compile_matcher())
+                    .map_err(|e| ServiceError::ValidateFailure {
+                        field: "pattern",
+                        reason: format!("line {}: {e}", index + 1),
+                    })
+            })
+            .collect::<Result<Vec<GlobMatcher>, ServiceError>>()?;
+
+        rules.push(OwnerRule { owners, matchers });
+    }
+
+    Ok(rules)
+}
+
+/// owners of the given paths in the order they are first found, without duplicates
+pub fn owners_of(rules: &[OwnerRule], paths: &[String]) -> Vec<String> {
+    let mut owners: Vec<String> = vec![];
+
+    for path in paths {
+        let Some(rule) = rules.iter().rev().find(|rule| rule.is_match(path)) else {
+            continue;
+        };
+
+        for owner in &rule.owners {
+            if !owners.contains(owner) {
+                owners.push(owner.clone());
+            }
+        }
+    }
+
+    owners
+}
+
+/// owners of the changed files of a job according to the rules of its repo
+pub async fn find_owners(
+    connection: &mut SqliteConnection,
+    repo_id: &str,
+    paths: &[String],
+) -> Result<Vec<String>, ServiceError> {
+    if paths.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let record = query!(
+        r#"
+        SELECT codeowners
+        FROM main.repos
+        WHERE id = ?
+        "#,
+        repo_id
+    )
+    .fetch_one(&mut *connection)
+    .await?;
+    let Some(codeowners) = record.codeowners else {
+        return Ok(vec![]);
+    };
+
+    Ok(owners_of(&parse_rules(&codeowners)?, paths))
+}
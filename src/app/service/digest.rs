@@ -0,0 +1,343 @@
+use super::{
+    i18n::{chat_language, Language},
+    job::format_duration,
+    message::send_text,
+    report::{longest_red_streak, slowest_jobs, summarize_chat, top_committers},
+    timezone::parse_timezone,
+};
+use crate::app::{
+    config::bot::CiBot,
+    util::{error::ServiceError, sentry::capture_warning},
+};
+use chrono::{Duration, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use cron::Schedule;
+use serde::{Deserialize, Serialize};
+use sqlx::{query, Pool, Sqlite};
+use std::{fmt::Display, str::FromStr};
+use teloxide::types::ChatId;
+use tracing::{info, warn};
+
+/// how often schedules are checked for a due digest
+const TICK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+/// number of entries listed in the ranked sections of a digest
+const RANKING_SIZE: i64 = 3;
+
+#[derive(Deserialize, Serialize, sqlx::Type, PartialEq, Eq, Debug, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "period", rename_all = "UPPERCASE")]
+pub enum DigestPeriod {
+    Daily,
+    Weekly,
+}
+
+impl DigestPeriod {
+    /// the span of history covered by a digest of this period
+    pub fn window(&self) -> Duration {
+        match self {
+            Self::Daily => Duration::days(1),
+            Self::Weekly => Duration::weeks(1),
+        }
+    }
+}
+
+impl Display for DigestPeriod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Daily => "daily",
+                Self::Weekly => "weekly",
+            }
+        )
+    }
+}
+
+impl TryFrom<&str> for DigestPeriod {
+    type Error = ServiceError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value.to_lowercase().as_str() {
+            "daily" => Ok(Self::Daily),
+            "weekly" => Ok(Self::Weekly),
+            _ => Err(ServiceError::TryFrom {
+                field: "period",
+                from: value.to_string(),
+                into: "DigestPeriod",
+                expect: "daily or weekly",
+            }),
+        }
+    }
+}
+
+/// a parsed `/digest` argument
+pub enum DigestCommand {
+    List,
+    Off(Option<DigestPeriod>),
+    Schedule {
+        period: DigestPeriod,
+        schedule: String,
+        /// the timezone of the schedule, the chat's own when none is given
+        timezone: Option<Tz>,
+    },
+}
+
+fn parse_time(value: &str) -> Result<(u32, u32), ServiceError> {
+    let invalid = || ServiceError::ValidateFailure {
+        field: "time",
+        reason: format!("{value} is not a time of the day in HH:MM format"),
+    };
+    let (hour, minute) = value.split_once(':').ok_or_else(invalid)?;
+    let hour = hour.parse::<u32>().map_err(|_| invalid())?;
+    let minute = minute.parse::<u32>().map_err(|_| invalid())?;
+
+    if hour < 24 && minute < 60 {
+        Ok((hour, minute))
+    } else {
+        Err(invalid())
+    }
+}
+
+fn parse_weekday(value: &str) -> Result<&'static str, ServiceError> {
+    match value.to_lowercase().get(..3) {
+        Some("mon") => Ok("Mon"),
+        Some("tue") => Ok("Tue"),
+        Some("wed") => Ok("Wed"),
+        Some("thu") => Ok("Thu"),
+        Some("fri") => Ok("Fri"),
+        Some("sat") => Ok("Sat"),
+        Some("sun") => Ok("Sun"),
+        _ => Err(ServiceError::ValidateFailure {
+            field: "weekday",
+            reason: format!("{value} is not a day of the week"),
+        }),
+    }
+}
+
+impl DigestCommand {
+    /// parse the argument of `/digest` which is in the form of `daily <HH:MM> [timezone]`,
+    /// `weekly <weekday> <HH:MM> [timezone]` or `off [daily|weekly]`. An empty argument lists
+    /// the schedules of the chat
+    pub fn parse(args: &str) -> Result<Self, ServiceError> {
+        let args = args.split_whitespace().collect::<Vec<&str>>();
+
+        match args.as_slice() {
+            [] => Ok(Self::List),
+            ["off"] => Ok(Self::Off(None)),
+            ["off", period] => Ok(Self::Off(Some(DigestPeriod::try_from(*period)?))),
+            [period, rest @ ..] => {
+                let period = DigestPeriod::try_from(*period)?;
+                let (weekday, rest) = match (period, rest) {
+                    (DigestPeriod::Weekly, [weekday, rest @ ..]) => (parse_weekday(weekday)?, rest),
+                    (DigestPeriod::Weekly, []) => {
+                        return Err(ServiceError::ValidateFailure {
+                            field: "weekday",
+                            reason: "weekly digest expect a day of the week".to_string(),
+                        })
+                    }
+                    (DigestPeriod::Daily, rest) => ("*", rest),
+                };
+                let (time, timezone) = match rest {
+                    [time] => (*time, None),
+                    [time, timezone] => (*time, Some(parse_timezone(timezone)?)),
+                    _ => {
+                        return Err(ServiceError::ValidateFailure {
+                            field: "time",
+                            reason: "expect a time of the day in HH:MM format".to_string(),
+                        })
+                    }
+                };
+                let (hour, minute) = parse_time(time)?;
+
+                Ok(Self::Schedule {
+                    period,
+                    schedule: format!("0 {minute} {hour} * * {weekday}"),
+                    timezone,
+                })
+            }
+        }
+    }
+}
+
+/// the next time a schedule fires strictly after `after` in the schedule's timezone
+pub fn next_run(
+    schedule: &str,
+    timezone: &str,
+    after: NaiveDateTime,
+) -> Result<Option<NaiveDateTime>, ServiceError> {
+    let schedule = Schedule::from_str(schedule)?;
+    let timezone = parse_timezone(timezone)?;
+
+    Ok(schedule
+        .after(&timezone.from_utc_datetime(&after))
+        .next()
+        .map(|next| next.naive_utc()))
+}
+
+/// render a digest of every repo of a chat since the given time
+pub async fn format_digest(
+    pool: &Pool<Sqlite>,
+    language: Language,
+    chat_id: i64,
+    period: DigestPeriod,
+    since: NaiveDateTime,
+) -> Result<String, ServiceError> {
+    let summary = summarize_chat(pool, chat_id, since).await?;
+    let title = match period {
+        DigestPeriod::Daily => language.tr("digest-daily-title"),
+        DigestPeriod::Weekly => language.tr("digest-weekly-title"),
+    };
+
+    if summary.total == 0 {
+        return Ok(format!("{title}\n{}", language.tr("digest-no-job")));
+    }
+
+    let mut text = format!(
+        "{title}\n{}",
+        language.tr_with(
+            "digest-jobs",
+            &[
+                ("total", summary.total.into()),
+                ("success", summary.success.into()),
+                ("failure", summary.failure.into()),
+                ("cancelled", summary.cancelled.into()),
+                ("running", summary.running.into()),
+            ],
+        )
+    );
+
+    if let Some(pass_rate) = summary.pass_rate() {
+        text = format!(
+            "{text}\n{}",
+            language.tr_with(
+                "digest-pass-rate",
+                &[("rate", format!("{pass_rate:.1}").into())]
+            )
+        );
+    }
+
+    let slowest = slowest_jobs(pool, chat_id, since, RANKING_SIZE).await?;
+
+    if !slowest.is_empty() {
+        text = format!("{text}\n\n{}", language.tr("digest-slowest"));
+
+        for (index, job) in slowest.iter().enumerate() {
+            text = format!(
+                "{text}\n{}. {} #{} {}",
+                index + 1,
+                job.repo_name,
+                job.id,
+                format_duration(language, Duration::seconds(job.elapsed))
+            );
+        }
+    }
+
+    if let Some(streak) = longest_red_streak(pool, chat_id, since).await? {
+        text = format!(
+            "{text}\n\n{}",
+            language.tr_with(
+                "digest-red-streak",
+                &[
+                    ("repo", streak.repo_name.into()),
+                    ("length", streak.length.into()),
+                ],
+            )
+        );
+    }
+
+    let committers = top_committers(pool, chat_id, since, RANKING_SIZE).await?;
+
+    if !committers.is_empty() {
+        text = format!("{text}\n\n{}", language.tr("digest-committers"));
+
+        for (index, (name, count)) in committers.iter().enumerate() {
+            let committer = language.tr_with(
+                "digest-committer",
+                &[("name", name.as_str().into()), ("count", (*count).into())],
+            );
+            text = format!("{text}\n{}. {committer}", index + 1);
+        }
+    }
+
+    Ok(text)
+}
+
+async fn run_due_digests(pool: &Pool<Sqlite>, bot: &CiBot) -> Result<(), ServiceError> {
+    let records = query!(
+        r#"
+        SELECT chat_id,
+            period AS "period: DigestPeriod",
+            schedule,
+            timezone,
+            last_run_at
+        FROM main.digests
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+    let now = Utc::now().naive_utc();
+
+    for record in records {
+        match next_run(&record.schedule, &record.timezone, record.last_run_at) {
+            Ok(Some(next)) if next <= now => {}
+            Ok(_) => continue,
+            Err(e) => {
+                warn!("invalid digest schedule of chat {}: {e}", record.chat_id);
+                continue;
+            }
+        }
+
+        // the schedule is moved forward first so that a chat that cannot be reached is not
+        // retried on every tick
+        query!(
+            r#"
+            UPDATE main.digests
+            SET last_run_at = ?
+            WHERE chat_id = ?
+            AND period = ?
+            "#,
+            now,
+            record.chat_id,
+            record.period
+        )
+        .execute(pool)
+        .await?;
+
+        let language = chat_language(pool, record.chat_id).await?;
+        let text = format_digest(
+            pool,
+            language,
+            record.chat_id,
+            record.period,
+            now - record.period.window(),
+        )
+        .await?;
+
+        match send_text(bot, ChatId(record.chat_id), text).await {
+            Ok(_) => info!("sent {} digest to chat {}", record.period, record.chat_id),
+            Err(e) => {
+                warn!(
+                    "failed to send {} digest to chat {}: {e}",
+                    record.period, record.chat_id
+                );
+                capture_warning("digest scheduler failed to deliver a digest");
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// post digests to chats according to their schedule. This task never returns and is dropped
+/// along with the runtime
+pub async fn run_digest_scheduler(pool: Pool<Sqlite>, bot: CiBot) {
+    loop {
+        if let Err(e) = run_due_digests(&pool, &bot).await {
+            warn!("failed to run digest schedules: {e}");
+            capture_warning("digest scheduler failed to run schedules");
+        }
+
+        tokio::time::sleep(TICK_INTERVAL).await;
+    }
+}
@@ -0,0 +1,303 @@
+//! DORA metrics derived from the job history of `main.jobs`.
+//!
+//! Every job reported to the bot is treated as a deployment pipeline run, so the four metrics
+//! are inferred from how `DeployStatus` changes over time:
+//!
+//! - a *deployment* is a job that finished with `SUCCESS`. Deployment frequency is the number
+//!   of deployments divided by the number of days in the window.
+//! - *lead time for changes* is the time from `committed_at` (when CI supplies it) until the
+//!   job that shipped the commit finished successfully. The median is reported.
+//! - *change failure rate* is the share of finished jobs that ended with `FAILURE`. Cancelled
+//!   and running jobs are neither a success nor a failure and are left out.
+//! - a *failure* is a `FAILURE` that follows a `SUCCESS` of the same repo (or the beginning of
+//!   its history). Further failures before the next success belong to the same failure. The
+//!   first `SUCCESS` after a failure is its *recovery* and *time to restore* is the time between
+//!   the failing job and the recovering job finishing. The mean is reported.
+//!
+//! A job counts toward a window when it finished inside the window. Transitions are tracked
+//! from one window length before the window starts so that a failure raised shortly before the
+//! window is still recovered inside of it.
+use super::{bot::state::DeployStatus, i18n::Language};
+use crate::app::{middleware::auth::service::SessionContainer, util::error::ServiceError};
+use axum::{extract::Query, response::IntoResponse, Extension, Json};
+use chrono::{Duration, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{query, Pool, Sqlite};
+use std::collections::HashMap;
+
+const DEFAULT_WINDOW: &str = "30d";
+/// the longest window of a command, about ten years
+pub const MAX_WINDOW_DAYS: i64 = 3650;
+
+/// whose jobs a metric is computed from
+pub enum Scope<'a> {
+    Repo(&'a str),
+    Chat(i64),
+}
+
+#[derive(Serialize, Debug)]
+pub struct DoraMetrics {
+    pub window_days: i64,
+    pub deployments: i64,
+    pub deployment_frequency: f64,
+    pub lead_time_seconds: Option<i64>,
+    pub change_failure_rate: Option<f64>,
+    pub failures: i64,
+    pub recoveries: i64,
+    pub mean_time_to_restore_seconds: Option<i64>,
+}
+
+/// parse a window in the form of `<n>d` or `<n>w` i.e. `30d`, up to `MAX_WINDOW_DAYS` long
+pub fn parse_window(value: &str) -> Result<Duration, ServiceError> {
+    let value = value.trim();
+    let invalid = || ServiceError::ValidateFailure {
+        field: "window",
+        reason: format!("{value} is not a window in the form of <n>d or <n>w i.e. 30d"),
+    };
+    let (amount, unit) = value.split_at(value.len().saturating_sub(1));
+    let amount = amount.parse::<i64>().map_err(|_| invalid())?;
+    let days = match unit {
+        _ if amount <= 0 => return Err(invalid()),
+        "d" => Some(amount),
+        "w" => amount.checked_mul(7),
+        _ => return Err(invalid()),
+    };
+
+    match days {
+        Some(days) if days <= MAX_WINDOW_DAYS => Ok(Duration::days(days)),
+        _ => Err(ServiceError::ValidateFailure {
+            field: "window",
+            reason: format!("{value} is longer than {MAX_WINDOW_DAYS} days"),
+        }),
+    }
+}
+
+/// the time a window before `now`
+pub fn start_of_window(
+    now: NaiveDateTime,
+    window: Duration,
+) -> Result<NaiveDateTime, ServiceError> {
+    now.checked_sub_signed(window)
+        .ok_or(ServiceError::ChronoDatetime)
+}
+
+struct FinishedJob {
+    repo_id: String,
+    status: DeployStatus,
+    finished_at: NaiveDateTime,
+    committed_at: Option<NaiveDateTime>,
+}
+
+pub fn median(mut values: Vec<i64>) -> Option<i64> {
+    values.sort_unstable();
+
+    match values.len() {
+        0 => None,
+        length if length % 2 == 0 => Some((values[length / 2 - 1] + values[length / 2]) / 2),
+        length => Some(values[length / 2]),
+    }
+}
+
+fn mean(values: &[i64]) -> Option<i64> {
+    match values.len() {
+        0 => None,
+        length => Some(values.iter().sum::<i64>() / length as i64),
+    }
+}
+
+pub async fn compute_dora(
+    pool: &Pool<Sqlite>,
+    scope: Scope<'_>,
+    window: Duration,
+) -> Result<DoraMetrics, ServiceError> {
+    let now = Utc::now().naive_utc();
+    let window_start = start_of_window(now, window)?;
+    let (repo_id, chat_id) = match scope {
+        Scope::Repo(repo_id) => (Some(repo_id), None),
+        Scope::Chat(chat_id) => (None, Some(chat_id)),
+    };
+    let lookback = start_of_window(window_start, window)?;
+    let mut jobs = query!(
+        r#"
+        SELECT jobs.repo_id,
+            jobs.status AS "status!: DeployStatus",
+            jobs.started_at,
+            jobs.elapsed AS "elapsed!",
+            jobs.committed_at
+        FROM main.jobs
+        JOIN repos ON jobs.repo_id = repos.id
+        WHERE (repos.id = ? OR repos.message_id = ?)
+        AND repos.deleted_at IS NULL
+        AND jobs.started_at >= ?
+        AND jobs.status IN (?, ?)
+        AND jobs.elapsed IS NOT NULL
+        "#,
+        repo_id,
+        chat_id,
+        lookback,
+        DeployStatus::Success,
+        DeployStatus::Failure
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|record| FinishedJob {
+        repo_id: record.repo_id,
+        status: record.status,
+        finished_at: record.started_at + Duration::seconds(record.elapsed),
+        committed_at: record.committed_at,
+    })
+    .collect::<Vec<FinishedJob>>();
+    jobs.sort_by_key(|job| job.finished_at);
+
+    let mut deployments = 0;
+    let mut failed = 0;
+    let mut lead_times = vec![];
+    let mut restores = vec![];
+    let mut failures = 0;
+    // finish time of the failure each repo is currently in, if any
+    let mut open_failures: HashMap<&str, NaiveDateTime> = HashMap::new();
+
+    for job in &jobs {
+        let in_window = job.finished_at >= window_start;
+
+        match job.status {
+            DeployStatus::Success => {
+                if let Some(failed_at) = open_failures.remove(job.repo_id.as_str()) {
+                    if in_window {
+                        restores.push((job.finished_at - failed_at).num_seconds());
+                    }
+                }
+
+                if in_window {
+                    deployments += 1;
+
+                    if let Some(committed_at) = job.committed_at {
+                        lead_times.push((job.finished_at - committed_at).num_seconds().max(0));
+                    }
+                }
+            }
+            DeployStatus::Failure => {
+                if in_window {
+                    failed += 1;
+                }
+
+                if !open_failures.contains_key(job.repo_id.as_str()) {
+                    open_failures.insert(&job.repo_id, job.finished_at);
+
+                    if in_window {
+                        failures += 1;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let window_days = window.num_days().max(1);
+
+    Ok(DoraMetrics {
+        window_days,
+        deployments,
+        deployment_frequency: deployments as f64 / window_days as f64,
+        lead_time_seconds: median(lead_times),
+        change_failure_rate: match deployments + failed {
+            0 => None,
+            finished => Some(failed as f64 / finished as f64),
+        },
+        failures,
+        recoveries: restores.len() as i64,
+        mean_time_to_restore_seconds: mean(&restores),
+    })
+}
+
+fn format_seconds(language: Language, seconds: Option<i64>) -> String {
+    seconds.map_or_else(
+        || language.tr("dora-none"),
+        |seconds| super::job::format_duration(language, Duration::seconds(seconds)),
+    )
+}
+
+pub fn format_dora(language: Language, title: &str, metrics: &DoraMetrics) -> String {
+    [
+        language.tr_with(
+            "dora-header",
+            &[
+                ("title", title.into()),
+                ("days", metrics.window_days.into()),
+            ],
+        ),
+        language.tr_with(
+            "dora-frequency",
+            &[
+                (
+                    "frequency",
+                    format!("{:.2}", metrics.deployment_frequency).into(),
+                ),
+                ("deployments", metrics.deployments.into()),
+            ],
+        ),
+        language.tr_with(
+            "dora-lead-time",
+            &[(
+                "lead_time",
+                format_seconds(language, metrics.lead_time_seconds).into(),
+            )],
+        ),
+        language.tr_with(
+            "dora-failure-rate",
+            &[(
+                "rate",
+                metrics
+                    .change_failure_rate
+                    .map_or_else(
+                        || language.tr("dora-none"),
+                        |rate| format!("{:.1}%", rate * 100.0),
+                    )
+                    .into(),
+            )],
+        ),
+        language.tr_with(
+            "dora-restore",
+            &[
+                (
+                    "restore",
+                    format_seconds(language, metrics.mean_time_to_restore_seconds).into(),
+                ),
+                ("failures", metrics.failures.into()),
+                ("recoveries", metrics.recoveries.into()),
+            ],
+        ),
+    ]
+    .join("\n")
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DoraQuery {
+    window: Option<String>,
+}
+
+pub async fn dora_handler(
+    Extension(SessionContainer(session)): Extension<SessionContainer>,
+    Extension(pool): Extension<Pool<Sqlite>>,
+    Query(DoraQuery { window }): Query<DoraQuery>,
+) -> impl IntoResponse {
+    if let Some(session) = session {
+        let window = parse_window(window.as_deref().unwrap_or(DEFAULT_WINDOW))?;
+
+        Ok(Json(
+            compute_dora(&pool, Scope::Repo(&session.sid), window).await?,
+        ))
+    } else {
+        Err(ServiceError::BadCredential)
+    }
+}
+
+/// window of a `/dora` command argument, an empty argument means the default window
+pub fn command_window(args: &str) -> Result<Duration, ServiceError> {
+    match args.trim() {
+        "" => parse_window(DEFAULT_WINDOW),
+        window => parse_window(window),
+    }
+}
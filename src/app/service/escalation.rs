@@ -0,0 +1,416 @@
+//! Failure acknowledgement and escalation. Every failed job stays open until someone presses
+//! "I'm on it" on its notification or a later job of the repo succeeds. An open failure that
+//! nobody acknowledged within the repo's `ack_timeout` is escalated to the on-call users of the
+//! repo and, one timeout later, to the repo's fallback chat.
+use super::{
+    bot::state::DeployStatus,
+    i18n::{chat_language, Language},
+    job::format_duration,
+    message::{send_message, MessageBuilder},
+    notifier::JobEvent,
+};
+use crate::app::{
+    config::bot::CiBot,
+    util::{error::ServiceError, sentry::capture_warning},
+};
+use chrono::{Duration, NaiveDateTime, Utc};
+use sqlx::{query, Pool, Sqlite, SqliteConnection};
+use teloxide::{
+    payloads::SendMessageSetters,
+    types::{ChatId, InlineKeyboardButton, InlineKeyboardMarkup, User},
+};
+use tracing::{info, warn};
+
+/// how often open failures are checked for a due escalation
+const TICK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+/// prefix of the callback data of an acknowledgement button
+const ACK_PREFIX: &str = "ack:";
+const NOT_ESCALATED: i64 = 0;
+const ONCALL_NOTIFIED: i64 = 1;
+const FALLBACK_NOTIFIED: i64 = 2;
+
+/// open a failure of a job so that it can be acknowledged and escalated
+pub async fn record_failure(
+    connection: &mut SqliteConnection,
+    repo_id: &str,
+    job_id: i32,
+) -> Result<(), ServiceError> {
+    query!(
+        r#"
+        INSERT OR IGNORE INTO main.failure_acks
+        (job_id, repo_id)
+        VALUES (?, ?)
+        "#,
+        job_id,
+        repo_id
+    )
+    .execute(&mut *connection)
+    .await?;
+
+    Ok(())
+}
+
+/// close every open failure of a repo once one of its jobs succeeds again
+pub async fn resolve_failures(
+    connection: &mut SqliteConnection,
+    repo_id: &str,
+) -> Result<(), ServiceError> {
+    let now = Utc::now().naive_utc();
+
+    query!(
+        r#"
+        UPDATE main.failure_acks
+        SET resolved_at = ?
+        WHERE repo_id = ?
+        AND resolved_at IS NULL
+        "#,
+        now,
+        repo_id
+    )
+    .execute(&mut *connection)
+    .await?;
+
+    Ok(())
+}
+
+pub struct OpenFailure {
+    pub job_id: i64,
+    pub failed_at: NaiveDateTime,
+}
+
+/// failures of a repo nobody is on yet, the oldest first
+pub async fn unacknowledged_failures(
+    connection: &mut SqliteConnection,
+    repo_id: &str,
+) -> Result<Vec<OpenFailure>, ServiceError> {
+    Ok(query!(
+        r#"
+        SELECT job_id AS "job_id!", failed_at
+        FROM main.failure_acks
+        WHERE repo_id = ?
+        AND acked_at IS NULL
+        AND resolved_at IS NULL
+        ORDER BY failed_at
+        "#,
+        repo_id
+    )
+    .fetch_all(&mut *connection)
+    .await?
+    .into_iter()
+    .map(|record| OpenFailure {
+        job_id: record.job_id,
+        failed_at: record.failed_at,
+    })
+    .collect())
+}
+
+fn ack_button(language: Language, job_id: i64) -> InlineKeyboardButton {
+    InlineKeyboardButton::callback(
+        language.tr_with("ack-button", &[("job", job_id.into())]),
+        format!("{ACK_PREFIX}{job_id}"),
+    )
+}
+
+/// one acknowledgement button per failed job of a notification, if there is any
+pub fn ack_keyboard(events: &[JobEvent]) -> Option<InlineKeyboardMarkup> {
+    let buttons = events
+        .iter()
+        .filter(|event| event.status == DeployStatus::Failure)
+        .map(|event| vec![ack_button(event.language, event.job_id.into())])
+        .collect::<Vec<Vec<InlineKeyboardButton>>>();
+
+    (!buttons.is_empty()).then(|| InlineKeyboardMarkup::new(buttons))
+}
+
+/// the job id of an acknowledgement button's callback data
+pub fn parse_ack_data(data: &str) -> Option<i64> {
+    data.strip_prefix(ACK_PREFIX)?.parse().ok()
+}
+
+/// how a user is named in chat, their username when they have one
+pub fn display_name(user: &User) -> String {
+    user.username
+        .as_ref()
+        .map_or(user.full_name(), |username| format!("@{username}"))
+}
+
+pub enum Acknowledgement {
+    Acknowledged { repo_id: String, repo_name: String },
+    AlreadyAcknowledged { by: String },
+    Resolved,
+    NotFound,
+}
+
+/// acknowledge a failure on behalf of a user. A failure can only be acknowledged from the chat
+/// of its repo, from the repo's fallback chat, or from the private chat of the user who
+/// triggered the job
+pub async fn acknowledge(
+    pool: &Pool<Sqlite>,
+    job_id: i64,
+    chat_id: ChatId,
+    user: &User,
+) -> Result<Acknowledgement, ServiceError> {
+    let Some(record) = query!(
+        r#"
+        SELECT repos.id,
+            repos.name,
+            failure_acks.acked_by_name,
+            failure_acks.acked_at,
+            failure_acks.resolved_at
+        FROM main.failure_acks
+        JOIN repos ON failure_acks.repo_id = repos.id
+        JOIN jobs ON failure_acks.job_id = jobs.id
+        WHERE failure_acks.job_id = ?
+        AND (
+            repos.message_id = ?
+            OR repos.fallback_chat_id = ?
+            OR EXISTS (
+                SELECT 1
+                FROM main.identities
+                WHERE identities.chat_id = repos.message_id
+                AND identities.ci_username = jobs.triggered_by
+                AND identities.user_id = ?
+            )
+        )
+        "#,
+        job_id,
+        chat_id.0,
+        chat_id.0,
+        chat_id.0
+    )
+    .fetch_optional(pool)
+    .await?
+    else {
+        return Ok(Acknowledgement::NotFound);
+    };
+
+    if record.acked_at.is_some() {
+        return Ok(Acknowledgement::AlreadyAcknowledged {
+            by: record.acked_by_name.unwrap_or_default(),
+        });
+    }
+
+    if record.resolved_at.is_some() {
+        return Ok(Acknowledgement::Resolved);
+    }
+
+    let now = Utc::now().naive_utc();
+    let user_id = user.id.0 as i64;
+    let name = display_name(user);
+
+    query!(
+        r#"
+        UPDATE main.failure_acks
+        SET acked_by = ?,
+            acked_by_name = ?,
+            acked_at = ?
+        WHERE job_id = ?
+        AND acked_at IS NULL
+        "#,
+        user_id,
+        name,
+        now,
+        job_id
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(Acknowledgement::Acknowledged {
+        repo_id: record.id,
+        repo_name: record.name,
+    })
+}
+
+/// parse the argument of `/oncall` which is in the form of `add <@username>` or
+/// `remove <@username>`
+pub fn parse_oncall(args: &str) -> Result<(bool, String), ServiceError> {
+    let args = args.split_whitespace().collect::<Vec<&str>>();
+    let (add, username) = match args.as_slice() {
+        ["add", username] => (true, username),
+        ["remove", username] => (false, username),
+        _ => {
+            return Err(ServiceError::ValidateFailure {
+                field: "oncall",
+                reason: "expect add <@username> or remove <@username>".to_string(),
+            })
+        }
+    };
+    let username = username.trim_start_matches('@');
+
+    if username.is_empty()
+        || !username
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_')
+    {
+        return Err(ServiceError::ValidateFailure {
+            field: "username",
+            reason: format!("{username} is not a telegram username"),
+        });
+    }
+
+    Ok((add, username.to_string()))
+}
+
+/// parse the argument of `/escalation` which is in the form of `<minutes> [fallback_chat_id]`
+/// or `off`
+pub fn parse_escalation(args: &str) -> Result<Option<(i64, Option<i64>)>, ServiceError> {
+    let args = args.split_whitespace().collect::<Vec<&str>>();
+    let invalid = || ServiceError::ValidateFailure {
+        field: "escalation",
+        reason: "expect <minutes> [fallback_chat_id] or off".to_string(),
+    };
+
+    match args.as_slice() {
+        ["off"] => Ok(None),
+        [minutes] => Ok(Some((minutes.parse().map_err(|_| invalid())?, None))),
+        [minutes, chat_id] => Ok(Some((
+            minutes.parse().map_err(|_| invalid())?,
+            Some(chat_id.parse().map_err(|_| invalid())?),
+        ))),
+        _ => Err(invalid()),
+    }
+    .and_then(|escalation| match escalation {
+        Some((minutes, _)) if minutes <= 0 => Err(invalid()),
+        escalation => Ok(escalation),
+    })
+}
+
+async fn load_oncall(pool: &Pool<Sqlite>, repo_id: &str) -> Result<Vec<String>, ServiceError> {
+    Ok(query!(
+        r#"
+        SELECT username
+        FROM main.oncall_users
+        WHERE repo_id = ?
+        ORDER BY username
+        "#,
+        repo_id
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|record| record.username)
+    .collect())
+}
+
+async fn escalate_due(pool: &Pool<Sqlite>, bot: &CiBot) -> Result<(), ServiceError> {
+    let records = query!(
+        r#"
+        SELECT failure_acks.job_id AS "job_id!",
+            failure_acks.repo_id,
+            failure_acks.failed_at,
+            failure_acks.escalation_level,
+            failure_acks.escalated_at,
+            repos.name,
+            repos.message_id,
+            repos.ack_timeout AS "ack_timeout!",
+            repos.fallback_chat_id
+        FROM main.failure_acks
+        JOIN repos ON failure_acks.repo_id = repos.id
+        WHERE failure_acks.acked_at IS NULL
+        AND failure_acks.resolved_at IS NULL
+        AND failure_acks.escalation_level < ?
+        AND repos.ack_timeout IS NOT NULL
+        AND repos.deleted_at IS NULL
+        "#,
+        FALLBACK_NOTIFIED
+    )
+    .fetch_all(pool)
+    .await?;
+    let now = Utc::now().naive_utc();
+
+    for record in records {
+        let since = record.escalated_at.unwrap_or(record.failed_at);
+
+        if now < since + Duration::minutes(record.ack_timeout) {
+            continue;
+        }
+
+        let oncall = match record.escalation_level {
+            NOT_ESCALATED => load_oncall(pool, &record.repo_id).await?,
+            _ => vec![],
+        };
+        let (level, chat_id, id) = match (oncall.is_empty(), record.fallback_chat_id) {
+            (false, _) => (ONCALL_NOTIFIED, Some(record.message_id), "escalated-oncall"),
+            (true, fallback_chat_id) => (FALLBACK_NOTIFIED, fallback_chat_id, "escalated-fallback"),
+        };
+
+        // the escalation is recorded first so that a chat that cannot be reached is not
+        // notified again on every tick
+        query!(
+            r#"
+            UPDATE main.failure_acks
+            SET escalation_level = ?,
+                escalated_at = ?
+            WHERE job_id = ?
+            "#,
+            level,
+            now,
+            record.job_id
+        )
+        .execute(pool)
+        .await?;
+
+        let Some(chat_id) = chat_id else {
+            continue;
+        };
+
+        let language = chat_language(pool, chat_id).await?;
+        let mut text = MessageBuilder::new().text(language.tr_with(
+            id,
+            &[
+                ("repo", record.name.as_str().into()),
+                ("job", record.job_id.into()),
+                (
+                    "duration",
+                    format_duration(language, now - record.failed_at).into(),
+                ),
+            ],
+        ));
+
+        if !oncall.is_empty() {
+            text = text.text(format!(
+                "\n{}",
+                oncall
+                    .iter()
+                    .map(|username| format!("@{username}"))
+                    .collect::<Vec<String>>()
+                    .join(" ")
+            ));
+        }
+
+        match send_message(bot, ChatId(chat_id), &text)
+            .reply_markup(InlineKeyboardMarkup::new([[ack_button(
+                language,
+                record.job_id,
+            )]]))
+            .await
+        {
+            Ok(_) => info!(
+                "escalated failure of job {} to chat {chat_id}",
+                record.job_id
+            ),
+            Err(e) => {
+                warn!(
+                    "failed to escalate failure of job {} to chat {chat_id}: {e}",
+                    record.job_id
+                );
+                capture_warning("escalation failed to deliver a message");
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// escalate unacknowledged failures in the background. This task never returns and is dropped
+/// along with the runtime
+pub async fn run_escalation(pool: Pool<Sqlite>, bot: CiBot) {
+    loop {
+        if let Err(e) = escalate_due(&pool, &bot).await {
+            warn!("failed to escalate failures: {e}");
+            capture_warning("escalation failed to check open failures");
+        }
+
+        tokio::time::sleep(TICK_INTERVAL).await;
+    }
+}
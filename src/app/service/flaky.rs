@@ -0,0 +1,226 @@
+//! Flake detection. A job failure is a flake when another job of the same repo built the same
+//! commit successfully, since nothing but chance differed between the two. A test is flaky when
+//! it failed in one job and passed in another job of the same commit.
+use super::{bot::state::DeployStatus, i18n::Language};
+use crate::app::util::error::ServiceError;
+use chrono::{Duration, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{query, SqliteConnection};
+use std::{collections::HashSet, fmt::Display};
+
+/// a test is considered known to be flaky when it flaked within this many days
+const KNOWN_FLAKE_DAYS: i64 = 30;
+/// number of entries listed in the flaky test leaderboard
+const LEADERBOARD_SIZE: i64 = 10;
+
+#[derive(Deserialize, Serialize, sqlx::Type, PartialEq, Eq, Debug, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "outcome", rename_all = "UPPERCASE")]
+pub enum TestOutcome {
+    Passed,
+    Failed,
+    Skipped,
+}
+
+impl Display for TestOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Passed => "PASSED",
+                Self::Failed => "FAILED",
+                Self::Skipped => "SKIPPED",
+            }
+        )
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct TestResult {
+    pub name: String,
+    pub outcome: TestOutcome,
+}
+
+pub async fn record_test_results(
+    connection: &mut SqliteConnection,
+    repo_id: &str,
+    job_id: i32,
+    results: &[TestResult],
+) -> Result<(), ServiceError> {
+    for result in results {
+        query!(
+            r#"
+            INSERT OR REPLACE INTO main.test_results
+            (repo_id, job_id, name, outcome)
+            VALUES (?, ?, ?, ?)
+            "#,
+            repo_id,
+            job_id,
+            result.name,
+            result.outcome
+        )
+        .execute(&mut *connection)
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// flaky tests of a repo since the given time along with the number of jobs they flaked in,
+/// the flakiest first
+pub async fn flaky_tests(
+    connection: &mut SqliteConnection,
+    repo_id: &str,
+    since: NaiveDateTime,
+    limit: i64,
+) -> Result<Vec<(String, i64)>, ServiceError> {
+    Ok(query!(
+        r#"
+        SELECT failed.name AS "name!", COUNT(DISTINCT failed.job_id) AS "flakes!: i64"
+        FROM main.test_results AS failed
+        JOIN jobs AS failed_job
+            ON failed_job.repo_id = failed.repo_id
+            AND failed_job.id = failed.job_id
+        JOIN jobs AS retry_job
+            ON retry_job.repo_id = failed_job.repo_id
+            AND retry_job.commit_sha = failed_job.commit_sha
+            AND retry_job.id != failed_job.id
+        JOIN test_results AS passed
+            ON passed.repo_id = retry_job.repo_id
+            AND passed.job_id = retry_job.id
+            AND passed.name = failed.name
+        WHERE failed.repo_id = ?
+        AND failed.outcome = ?
+        AND passed.outcome = ?
+        AND failed_job.started_at >= ?
+        GROUP BY failed.name
+        ORDER BY 2 DESC, failed.name
+        LIMIT ?
+        "#,
+        repo_id,
+        TestOutcome::Failed,
+        TestOutcome::Passed,
+        since,
+        limit
+    )
+    .fetch_all(&mut *connection)
+    .await?
+    .into_iter()
+    .map(|record| (record.name, record.flakes))
+    .collect())
+}
+
+/// whether a failed job is a flake. It is when the same commit was built successfully by
+/// another job, or when every failing test of the job is already known to be flaky
+pub async fn is_flaky_failure(
+    connection: &mut SqliteConnection,
+    repo_id: &str,
+    job_id: i32,
+    commit_sha: Option<&str>,
+    results: &[TestResult],
+) -> Result<bool, ServiceError> {
+    if let Some(commit_sha) = commit_sha {
+        let record = query!(
+            r#"
+            SELECT COUNT(*) AS "passed!: i64"
+            FROM main.jobs
+            WHERE repo_id = ?
+            AND commit_sha = ?
+            AND id != ?
+            AND status = ?
+            "#,
+            repo_id,
+            commit_sha,
+            job_id,
+            DeployStatus::Success
+        )
+        .fetch_one(&mut *connection)
+        .await?;
+
+        if record.passed > 0 {
+            return Ok(true);
+        }
+    }
+
+    let failing = results
+        .iter()
+        .filter(|result| result.outcome == TestOutcome::Failed)
+        .map(|result| result.name.as_str())
+        .collect::<Vec<&str>>();
+
+    if failing.is_empty() {
+        return Ok(false);
+    }
+
+    let since = Utc::now().naive_utc() - Duration::days(KNOWN_FLAKE_DAYS);
+    let known = flaky_tests(connection, repo_id, since, i64::MAX)
+        .await?
+        .into_iter()
+        .map(|(name, _)| name)
+        .collect::<HashSet<String>>();
+
+    Ok(failing.iter().all(|name| known.contains(*name)))
+}
+
+/// render the flake leaderboard of a repo since the given time
+pub async fn format_flaky(
+    connection: &mut SqliteConnection,
+    language: Language,
+    repo_id: &str,
+    since: NaiveDateTime,
+) -> Result<String, ServiceError> {
+    let record = query!(
+        r#"
+        SELECT COUNT(*) AS "failures!: i64",
+            COALESCE(SUM(EXISTS (
+                SELECT 1
+                FROM main.jobs AS retry_job
+                WHERE retry_job.repo_id = jobs.repo_id
+                AND retry_job.commit_sha = jobs.commit_sha
+                AND retry_job.id != jobs.id
+                AND retry_job.status = ?
+            )), 0) AS "flaky!: i64"
+        FROM main.jobs
+        WHERE repo_id = ?
+        AND status = ?
+        AND started_at >= ?
+        "#,
+        DeployStatus::Success,
+        repo_id,
+        DeployStatus::Failure,
+        since
+    )
+    .fetch_one(&mut *connection)
+    .await?;
+    let mut text = match record.failures {
+        0 => language.tr("flaky-none"),
+        failures => language.tr_with(
+            "flaky-rate",
+            &[
+                ("flaky", record.flaky.into()),
+                ("failures", failures.into()),
+                (
+                    "percent",
+                    format!("{:.1}", record.flaky as f64 * 100.0 / failures as f64).into(),
+                ),
+            ],
+        ),
+    };
+    let tests = flaky_tests(connection, repo_id, since, LEADERBOARD_SIZE).await?;
+
+    if !tests.is_empty() {
+        text = format!("{text}\n\n{}", language.tr("flaky-tests"));
+
+        for (index, (name, flakes)) in tests.iter().enumerate() {
+            let test = language.tr_with(
+                "flaky-test",
+                &[("name", name.as_str().into()), ("flakes", (*flakes).into())],
+            );
+            text = format!("{text}\n{}. {test}", index + 1);
+        }
+    }
+
+    Ok(text)
+}
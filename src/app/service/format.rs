@@ -0,0 +1,220 @@
+//! Job listings as chat messages. Every job is a line of its status, id, repo, branch, start time
+//! relative to now, duration, triggerer and link.
+use super::{
+    bot::state::DeployStatus, i18n::Language, job::format_duration, message::MessageBuilder,
+};
+use chrono::{Duration, NaiveDateTime};
+
+/// a job as listed by `/today`, `/latest`, `/history` and the job browser
+#[derive(sqlx::FromRow)]
+pub struct JobListing {
+    pub id: i64,
+    pub status: DeployStatus,
+    /// the repo of the job, left out when the listing is about a single repo
+    pub repo_name: Option<String>,
+    pub branch: Option<String>,
+    pub triggered_by: Option<String>,
+    pub url: Option<String>,
+    pub started_at: NaiveDateTime,
+    /// seconds the job took, a running job has none
+    pub elapsed: Option<i64>,
+}
+
+pub fn status_icon(status: DeployStatus) -> &'static str {
+    match status {
+        DeployStatus::Running => "🚧",
+        DeployStatus::Success => "✅",
+        DeployStatus::Failure => "🚨",
+        DeployStatus::Cancelled => "⛔️",
+    }
+}
+
+/// how long ago a time was i.e. `5 minute(s) ago`, a time in the future is `just now`
+pub fn format_relative(language: Language, now: NaiveDateTime, time: NaiveDateTime) -> String {
+    match now - time {
+        elapsed if elapsed < Duration::seconds(1) => language.tr("time-just-now"),
+        elapsed => language.tr_with(
+            "time-ago",
+            &[("duration", format_duration(language, elapsed).into())],
+        ),
+    }
+}
+
+/// a job as a line of a listing. Ids are padded to `id_width` digits so that the lines of a
+/// listing line up
+pub fn format_job(
+    language: Language,
+    job: &JobListing,
+    now: NaiveDateTime,
+    id_width: usize,
+) -> MessageBuilder {
+    let mut line = MessageBuilder::new()
+        .text(format!("{} ", status_icon(job.status)))
+        .code(format!("#{:<id_width$}", job.id));
+
+    if let Some(repo_name) = &job.repo_name {
+        line = line.text(" ").bold(repo_name);
+    }
+
+    if let Some(branch) = &job.branch {
+        line = line.text(format!(" ({branch})"));
+    }
+
+    line = line.text(format!(
+        " · {}",
+        format_relative(language, now, job.started_at)
+    ));
+
+    let duration = match job.elapsed {
+        Some(elapsed) => Some(language.tr_with(
+            "job-took",
+            &[(
+                "duration",
+                format_duration(language, Duration::seconds(elapsed)).into(),
+            )],
+        )),
+        None if job.status == DeployStatus::Running => Some(language.tr_with(
+            "job-running-for",
+            &[(
+                "duration",
+                format_duration(language, now - job.started_at).into(),
+            )],
+        )),
+        None => None,
+    };
+
+    if let Some(duration) = duration {
+        line = line.text(format!(" · {duration}"));
+    }
+
+    if let Some(triggered_by) = &job.triggered_by {
+        line = line.text(format!(
+            " · {}",
+            language.tr_with(
+                "job-triggered-by",
+                &[("name", triggered_by.as_str().into())]
+            )
+        ));
+    }
+
+    if let Some(url) = &job.url {
+        line = line.text(" · ").link(url, language.tr("job-link-text"));
+    }
+
+    line
+}
+
+/// lines of a listing of jobs under a header
+pub fn format_jobs(
+    language: Language,
+    header: MessageBuilder,
+    jobs: &[JobListing],
+    now: NaiveDateTime,
+) -> Vec<MessageBuilder> {
+    let id_width = jobs
+        .iter()
+        .map(|job| job.id.to_string().len())
+        .max()
+        .unwrap_or_default();
+
+    std::iter::once(header)
+        .chain(
+            jobs.iter()
+                .map(|job| format_job(language, job, now, id_width)),
+        )
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn now() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2023, 1, 2)
+            .and_then(|date| date.and_hms_opt(12, 0, 0))
+            .unwrap()
+    }
+
+    fn job(id: i64, status: DeployStatus) -> JobListing {
+        JobListing {
+            id,
+            status,
+            repo_name: None,
+            branch: None,
+            triggered_by: None,
+            url: None,
+            started_at: now() - Duration::minutes(5),
+            elapsed: None,
+        }
+    }
+
+    #[test]
+    fn format_jobs_puts_the_header_first() {
+        let header = MessageBuilder::new().bold("jobs");
+        let lines = format_jobs(Language::En, header.clone(), &[], now());
+
+        assert_eq!(lines, [header]);
+    }
+
+    #[test]
+    fn format_jobs_lines_up_ids() {
+        let jobs = [
+            job(7, DeployStatus::Cancelled),
+            job(1024, DeployStatus::Cancelled),
+        ];
+        let lines = format_jobs(Language::En, MessageBuilder::new(), &jobs, now());
+
+        assert_eq!(lines[1].plain(), "⛔️ #7    · 5 minutes ago");
+        assert_eq!(lines[2].plain(), "⛔️ #1024 · 5 minutes ago");
+    }
+
+    #[test]
+    fn format_jobs_shows_every_detail_of_a_job() {
+        let jobs = [JobListing {
+            repo_name: Some("web".to_string()),
+            branch: Some("main".to_string()),
+            triggered_by: Some("octocat".to_string()),
+            url: Some("https://ci.example.com/1".to_string()),
+            elapsed: Some(90),
+            ..job(1, DeployStatus::Success)
+        }];
+        let lines = format_jobs(Language::En, MessageBuilder::new(), &jobs, now());
+
+        assert_eq!(
+            lines[1].plain(),
+            "✅ #1 web (main) · 5 minutes ago · took 1 minute · by octocat · link"
+        );
+        assert_eq!(
+            lines[1].html(),
+            "✅ <code>#1</code> <b>web</b> (main) · 5 minutes ago · took 1 minute · by octocat · \
+             <a href=\"https://ci.example.com/1\">link</a>"
+        );
+    }
+
+    #[test]
+    fn format_jobs_shows_how_long_a_job_has_been_running() {
+        let jobs = [job(1, DeployStatus::Running)];
+        let lines = format_jobs(Language::En, MessageBuilder::new(), &jobs, now());
+
+        assert_eq!(
+            lines[1].plain(),
+            "🚧 #1 · 5 minutes ago · running for 5 minutes"
+        );
+    }
+
+    #[test]
+    fn format_jobs_escapes_what_a_ci_sends() {
+        let jobs = [JobListing {
+            repo_name: Some("<b>web</b>".to_string()),
+            branch: Some("a&b".to_string()),
+            ..job(1, DeployStatus::Failure)
+        }];
+        let lines = format_jobs(Language::En, MessageBuilder::new(), &jobs, now());
+
+        assert_eq!(
+            lines[1].html(),
+            "🚨 <code>#1</code> <b>&lt;b&gt;web&lt;/b&gt;</b> (a&amp;b) · 5 minutes ago"
+        );
+    }
+}
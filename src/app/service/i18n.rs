@@ -0,0 +1,161 @@
+//! Messages of the bot in the language of each chat. Every message lives in a Fluent catalog
+//! under `assets/locales`, a message missing from a catalog falls back to English.
+use crate::app::util::error::ServiceError;
+use fluent_bundle::{concurrent::FluentBundle, FluentArgs, FluentResource, FluentValue};
+use serde::{Deserialize, Serialize};
+use sqlx::{query, Executor, Sqlite};
+use std::fmt::Display;
+use tracing::warn;
+use unic_langid::{langid, LanguageIdentifier};
+
+lazy_static::lazy_static! {
+    static ref ENGLISH: FluentBundle<FluentResource> = bundle(Language::En, include_str!("../../../assets/locales/en.ftl"));
+    static ref THAI: FluentBundle<FluentResource> = bundle(Language::Th, include_str!("../../../assets/locales/th.ftl"));
+}
+
+#[derive(Default, Deserialize, Serialize, sqlx::Type, PartialEq, Eq, Debug, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "language", rename_all = "UPPERCASE")]
+pub enum Language {
+    #[default]
+    En,
+    Th,
+}
+
+fn bundle(language: Language, source: &str) -> FluentBundle<FluentResource> {
+    let resource = FluentResource::try_new(source.to_string()).unwrap_or_else(|(_, errors)| {
+        panic!("expect the {language} catalog to be valid: {errors:?}")
+    });
+    let mut bundle = FluentBundle::new_concurrent(vec![language.identifier()]);
+
+    // telegram shows the unicode isolation marks around arguments as is
+    bundle.set_use_isolating(false);
+    bundle
+        .add_resource(resource)
+        .unwrap_or_else(|errors| panic!("expect the {language} catalog to be unique: {errors:?}"));
+    bundle
+}
+
+impl Language {
+    fn identifier(&self) -> LanguageIdentifier {
+        match self {
+            Self::En => langid!("en"),
+            Self::Th => langid!("th"),
+        }
+    }
+
+    fn bundle(&self) -> &'static FluentBundle<FluentResource> {
+        match self {
+            Self::En => &ENGLISH,
+            Self::Th => &THAI,
+        }
+    }
+
+    /// a message of the catalog
+    pub fn tr(&self, id: &str) -> String {
+        self.tr_with(id, &[])
+    }
+
+    /// a message of the catalog filled in with arguments. A message that no catalog has is
+    /// shown as its id
+    pub fn tr_with(&self, id: &str, args: &[(&str, FluentValue)]) -> String {
+        let mut fluent_args = FluentArgs::new();
+
+        for (name, value) in args {
+            fluent_args.set(*name, value.clone());
+        }
+
+        let message = [*self, Self::En].into_iter().find_map(|language| {
+            let bundle = language.bundle();
+            let pattern = bundle.get_message(id)?.value()?;
+            let mut errors = vec![];
+            let message = bundle.format_pattern(pattern, Some(&fluent_args), &mut errors);
+
+            if !errors.is_empty() {
+                warn!("failed to format message {id} in {language}: {errors:?}");
+            }
+
+            Some(message.into_owned())
+        });
+
+        message.unwrap_or_else(|| {
+            warn!("message {id} is missing from every catalog");
+            id.to_string()
+        })
+    }
+}
+
+impl Display for Language {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::En => "en",
+                Self::Th => "th",
+            }
+        )
+    }
+}
+
+impl TryFrom<&str> for Language {
+    type Error = ServiceError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value.trim().to_lowercase().as_str() {
+            "en" | "english" => Ok(Self::En),
+            "th" | "thai" | "ไทย" => Ok(Self::Th),
+            _ => Err(ServiceError::TryFrom {
+                field: "language",
+                from: value.to_string(),
+                into: "Language",
+                expect: "en or th",
+            }),
+        }
+    }
+}
+
+/// the language of a chat, English unless the chat set another one
+pub async fn chat_language<'c, E>(executor: E, chat_id: i64) -> Result<Language, ServiceError>
+where
+    E: Executor<'c, Database = Sqlite>,
+{
+    Ok(query!(
+        r#"
+        SELECT language AS "language: Language"
+        FROM main.chat_settings
+        WHERE chat_id = ?
+        "#,
+        chat_id
+    )
+    .fetch_optional(executor)
+    .await?
+    .map(|record| record.language)
+    .unwrap_or_default())
+}
+
+/// set the language of a chat
+pub async fn set_chat_language<'c, E>(
+    executor: E,
+    chat_id: i64,
+    language: Language,
+) -> Result<(), ServiceError>
+where
+    E: Executor<'c, Database = Sqlite>,
+{
+    query!(
+        r#"
+        INSERT INTO main.chat_settings
+        (chat_id, language)
+        VALUES (?, ?)
+        ON CONFLICT (chat_id)
+        DO UPDATE SET language = excluded.language
+        "#,
+        chat_id,
+        language
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
@@ -0,0 +1,422 @@
+//! Links between the identity of a CI actor (the `by_name` of a job) and a Telegram user so that
+//! a failure can reach the person who triggered it. A link only covers the repos of the chat it
+//! was made in, since anyone can make their own chat and claim any username there. The one
+//! exception is a link made in a private chat with the bot, which sends the user private
+//! messages about jobs of any repo but never mentions them. It can only be made for a username
+//! nobody else linked, and a link made in the chat of a repo takes precedence over it.
+use super::bot::state::DeployStatus;
+use crate::app::util::error::ServiceError;
+use serde::{Deserialize, Serialize};
+use sqlx::{query, Pool, Sqlite, SqliteConnection};
+use std::fmt::Display;
+
+#[derive(Deserialize, Serialize, sqlx::Type, PartialEq, Eq, Debug, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "mention", rename_all = "UPPERCASE")]
+pub enum MentionMode {
+    /// mention the user in the failure notification of the repo's chat
+    Mention,
+    Off,
+    /// send the failure to the user in a private chat instead of mentioning them
+    Dm,
+}
+
+impl Display for MentionMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Mention => "on",
+                Self::Off => "off",
+                Self::Dm => "dm",
+            }
+        )
+    }
+}
+
+impl TryFrom<&str> for MentionMode {
+    type Error = ServiceError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value.trim().to_lowercase().as_str() {
+            "on" | "mention" => Ok(Self::Mention),
+            "off" => Ok(Self::Off),
+            "dm" => Ok(Self::Dm),
+            _ => Err(ServiceError::TryFrom {
+                field: "mention",
+                from: value.to_string(),
+                into: "MentionMode",
+                expect: "on, off, or dm",
+            }),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, sqlx::Type, PartialEq, Eq, Debug, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "notify", rename_all = "UPPERCASE")]
+pub enum NotifyPreference {
+    Failures,
+    All,
+    Off,
+}
+
+impl Display for NotifyPreference {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Failures => "failures",
+                Self::All => "all",
+                Self::Off => "off",
+            }
+        )
+    }
+}
+
+impl TryFrom<&str> for NotifyPreference {
+    type Error = ServiceError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value.trim().to_lowercase().as_str() {
+            "failure" | "failures" => Ok(Self::Failures),
+            "all" => Ok(Self::All),
+            "off" => Ok(Self::Off),
+            _ => Err(ServiceError::TryFrom {
+                field: "notify",
+                from: value.to_string(),
+                into: "NotifyPreference",
+                expect: "failures, all, or off",
+            }),
+        }
+    }
+}
+
+/// a telegram user to mention in a notification
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Mention {
+    pub user_id: i64,
+    pub name: String,
+}
+
+pub struct Identity {
+    pub mention: Mention,
+    pub mode: MentionMode,
+    pub notify: NotifyPreference,
+}
+
+impl Identity {
+    /// whether a job of the given status is sent to the user in a private chat. Running jobs
+    /// are never sent, only completions are
+    pub fn wants_dm(&self, status: DeployStatus) -> bool {
+        match status {
+            DeployStatus::Running => false,
+            DeployStatus::Failure => {
+                self.mode == MentionMode::Dm || self.notify != NotifyPreference::Off
+            }
+            _ => self.notify == NotifyPreference::All,
+        }
+    }
+}
+
+/// the telegram user linked to a CI username in a chat, if any
+pub async fn find_identity(
+    connection: &mut SqliteConnection,
+    chat_id: i64,
+    ci_username: &str,
+) -> Result<Option<Identity>, ServiceError> {
+    Ok(query!(
+        r#"
+        SELECT user_id,
+            name,
+            mention AS "mention: MentionMode",
+            notify AS "notify: NotifyPreference"
+        FROM main.identities
+        WHERE chat_id = ?
+        AND ci_username = ?
+        "#,
+        chat_id,
+        ci_username
+    )
+    .fetch_optional(&mut *connection)
+    .await?
+    .map(|record| Identity {
+        mention: Mention {
+            user_id: record.user_id,
+            name: record.name,
+        },
+        mode: record.mention,
+        notify: record.notify,
+    }))
+}
+
+/// the telegram user to send private messages about a job of a CI username in a chat to. A link
+/// made in the chat itself is preferred over a link made in a private chat with the bot, whose
+/// id is the same as the id of its user
+pub async fn find_dm_identity(
+    connection: &mut SqliteConnection,
+    chat_id: i64,
+    ci_username: &str,
+) -> Result<Option<Identity>, ServiceError> {
+    if let Some(identity) = find_identity(connection, chat_id, ci_username).await? {
+        return Ok(Some(identity));
+    }
+
+    Ok(query!(
+        r#"
+        SELECT user_id AS "user_id!",
+            name AS "name!",
+            mention AS "mention!: MentionMode",
+            notify AS "notify!: NotifyPreference"
+        FROM main.identities
+        WHERE chat_id = user_id
+        AND ci_username = ?
+        ORDER BY linked_at
+        LIMIT 1
+        "#,
+        ci_username
+    )
+    .fetch_optional(&mut *connection)
+    .await?
+    .map(|record| Identity {
+        mention: Mention {
+            user_id: record.user_id,
+            name: record.name,
+        },
+        mode: record.mention,
+        notify: record.notify,
+    }))
+}
+
+/// validate a CI username given to `/link_me` or `/unlink_me`
+pub fn parse_ci_username(value: &str) -> Result<String, ServiceError> {
+    match value.trim().trim_start_matches('@') {
+        "" => Err(ServiceError::ValidateFailure {
+            field: "ci_username",
+            reason: "expect the username you use on CI i.e. /link_me octocat".to_string(),
+        }),
+        username if username.contains(char::is_whitespace) => Err(ServiceError::ValidateFailure {
+            field: "ci_username",
+            reason: format!("{username} is not a single username"),
+        }),
+        username => Ok(username.to_string()),
+    }
+}
+
+pub enum Link {
+    Linked,
+    /// the CI username already belongs to another telegram user in the chat, or in any chat for
+    /// a link made in a private chat
+    Taken {
+        name: String,
+    },
+    /// the user already claimed another CI username in the chat
+    Other {
+        ci_username: String,
+    },
+}
+
+/// the telegram user a CI username is linked to in a chat, by id and name
+pub async fn linked_user(
+    pool: &Pool<Sqlite>,
+    chat_id: i64,
+    ci_username: &str,
+) -> Result<Option<(i64, String)>, ServiceError> {
+    Ok(query!(
+        r#"
+        SELECT user_id, name
+        FROM main.identities
+        WHERE chat_id = ?
+        AND ci_username = ?
+        "#,
+        chat_id,
+        ci_username
+    )
+    .fetch_optional(pool)
+    .await?
+    .map(|record| (record.user_id, record.name)))
+}
+
+/// link a CI username to a telegram user in a chat. A user claims a single username per chat,
+/// and a username that is linked to someone else has to be unlinked first so that nobody can
+/// take over another person's notifications. Since a link made in a private chat reaches every
+/// repo, it is refused when the username is linked to someone else in any chat
+pub async fn link(
+    pool: &Pool<Sqlite>,
+    chat_id: i64,
+    ci_username: &str,
+    user_id: i64,
+    name: &str,
+) -> Result<Link, ServiceError> {
+    let existing = query!(
+        r#"
+        SELECT ci_username, user_id, name
+        FROM main.identities
+        WHERE chat_id = ?
+        AND (ci_username = ? OR user_id = ?)
+        "#,
+        chat_id,
+        ci_username,
+        user_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    if chat_id == user_id {
+        let claimed = query!(
+            r#"
+            SELECT name AS "name!"
+            FROM main.identities
+            WHERE ci_username = ?
+            AND user_id <> ?
+            ORDER BY linked_at
+            LIMIT 1
+            "#,
+            ci_username,
+            user_id
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        if let Some(claimed) = claimed {
+            return Ok(Link::Taken { name: claimed.name });
+        }
+    }
+
+    for existing in existing {
+        if existing.user_id != user_id {
+            return Ok(Link::Taken {
+                name: existing.name,
+            });
+        }
+
+        if !existing.ci_username.eq_ignore_ascii_case(ci_username) {
+            return Ok(Link::Other {
+                ci_username: existing.ci_username,
+            });
+        }
+    }
+
+    // a new link follows the preferences the user already chose for their other links
+    query!(
+        r#"
+        INSERT INTO main.identities
+        (chat_id, ci_username, user_id, name, mention, notify)
+        VALUES (?, ?, ?, ?,
+            COALESCE((SELECT mention FROM main.identities WHERE user_id = ? LIMIT 1), 'MENTION'),
+            COALESCE((SELECT notify FROM main.identities WHERE user_id = ? LIMIT 1), 'OFF')
+        )
+        ON CONFLICT (chat_id, ci_username) DO UPDATE SET name = excluded.name
+        "#,
+        chat_id,
+        ci_username,
+        user_id,
+        name,
+        user_id,
+        user_id
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(Link::Linked)
+}
+
+/// remove the link of a CI username in a chat, returning whether there was one
+pub async fn unlink(
+    pool: &Pool<Sqlite>,
+    chat_id: i64,
+    ci_username: &str,
+) -> Result<bool, ServiceError> {
+    let result = query!(
+        r#"
+        DELETE FROM main.identities
+        WHERE chat_id = ?
+        AND ci_username = ?
+        "#,
+        chat_id,
+        ci_username
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    const REPO_CHAT: i64 = -100;
+    const OTHER_CHAT: i64 = -200;
+    const ALICE: i64 = 1;
+    const BOB: i64 = 2;
+
+    async fn pool() -> Pool<Sqlite> {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        sqlx::migrate!().run(&pool).await.unwrap();
+
+        pool
+    }
+
+    async fn dm_user(pool: &Pool<Sqlite>, chat_id: i64, ci_username: &str) -> Option<i64> {
+        let mut connection = pool.acquire().await.unwrap();
+
+        find_dm_identity(&mut connection, chat_id, ci_username)
+            .await
+            .unwrap()
+            .map(|identity| identity.mention.user_id)
+    }
+
+    #[tokio::test]
+    async fn a_private_link_reaches_repos_of_other_chats() {
+        let pool = pool().await;
+
+        assert!(matches!(
+            link(&pool, ALICE, "octocat", ALICE, "alice").await.unwrap(),
+            Link::Linked
+        ));
+        assert_eq!(dm_user(&pool, REPO_CHAT, "OctoCat").await, Some(ALICE));
+    }
+
+    #[tokio::test]
+    async fn a_link_in_another_group_does_not_reach_the_repo() {
+        let pool = pool().await;
+
+        link(&pool, OTHER_CHAT, "octocat", ALICE, "alice")
+            .await
+            .unwrap();
+        assert_eq!(dm_user(&pool, REPO_CHAT, "octocat").await, None);
+        assert_eq!(dm_user(&pool, OTHER_CHAT, "octocat").await, Some(ALICE));
+    }
+
+    #[tokio::test]
+    async fn a_link_in_the_repo_chat_takes_precedence() {
+        let pool = pool().await;
+
+        link(&pool, ALICE, "octocat", ALICE, "alice").await.unwrap();
+        link(&pool, REPO_CHAT, "octocat", BOB, "bob").await.unwrap();
+        assert_eq!(dm_user(&pool, REPO_CHAT, "octocat").await, Some(BOB));
+        assert_eq!(dm_user(&pool, OTHER_CHAT, "octocat").await, Some(ALICE));
+    }
+
+    #[tokio::test]
+    async fn a_private_link_of_a_username_linked_elsewhere_is_refused() {
+        let pool = pool().await;
+
+        link(&pool, OTHER_CHAT, "octocat", BOB, "bob")
+            .await
+            .unwrap();
+        assert!(matches!(
+            link(&pool, ALICE, "octocat", ALICE, "alice").await.unwrap(),
+            Link::Taken { name } if name == "bob"
+        ));
+        assert_eq!(dm_user(&pool, REPO_CHAT, "octocat").await, None);
+    }
+}
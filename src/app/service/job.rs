@@ -1,15 +1,26 @@
-use super::bot::state::DeployStatus;
+use super::{
+    bot::state::DeployStatus,
+    codeowners::find_owners,
+    escalation::{record_failure, resolve_failures},
+    flaky::{is_flaky_failure, record_test_results, TestResult},
+    i18n::{chat_language, Language},
+    identity::{find_dm_identity, find_identity, MentionMode},
+    message::MessageBuilder,
+    notifier::JobEvent,
+    outbox::{enqueue, enqueue_sinks},
+    regression::{detect_slowdown, rolling_baseline},
+    template::{find_template, TemplateKind},
+};
 use crate::app::{
     middleware::auth::service::SessionContainer,
     util::{empty_string_deserializer::empty_string_as_none, error::ServiceError},
 };
 use axum::{response::IntoResponse, Extension, Json};
-use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+use chrono::{DateTime, Duration, Utc};
 use http::StatusCode;
 use serde::Deserialize;
 use sqlx::{query, Pool, Sqlite};
-use teloxide::{requests::Requester, types::ChatId, utils::markdown::link, Bot};
-use tracing::info;
+use teloxide::types::ChatId;
 
 #[derive(Deserialize)]
 #[serde(deny_unknown_fields)]
@@ -23,6 +34,14 @@ pub struct JobCreationBody {
     by: Option<String>,
     #[serde(deserialize_with = "empty_string_as_none")]
     by_name: Option<String>,
+    /// commit time of the change being built, used to measure lead time
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    committed_at: Option<DateTime<Utc>>,
+    /// branch being built, jobs of a branch are compared against their own history
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    branch: Option<String>,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    commit_sha: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -34,29 +53,66 @@ pub struct JobStatusBody {
     description: Option<String>,
     #[serde(deserialize_with = "empty_string_as_none")]
     by: Option<String>,
+    /// outcome of every test run by the job, used to tell flaky tests apart
+    #[serde(default)]
+    tests: Vec<TestResult>,
+    /// paths changed by the commit being built, a failure mentions the owners of these paths
+    #[serde(default)]
+    changed_files: Vec<String>,
 }
 
-fn format_create_message(
+pub fn format_create_message(
+    language: Language,
     repo_name: String,
     url: Option<String>,
     description: Option<String>,
     by: Option<String>,
     by_name: Option<String>,
-) -> String {
-    let mut text = description.map_or(format!("🚧 {repo_name}'s job is running..."), |dsc| dsc);
+    eta: Option<i64>,
+) -> MessageBuilder {
+    let mut message = MessageBuilder::new().text(description.unwrap_or_else(|| {
+        language.tr_with(
+            headline_id(DeployStatus::Running, false),
+            &[("repo", repo_name.as_str().into())],
+        )
+    }));
+
+    if let Some(eta) = eta {
+        message = message.text(format!(
+            "\n{}",
+            language.tr_with("job-eta", &[("eta", format_eta(language, eta).into())])
+        ));
+    }
 
     if let (Some(by), Some(by_name)) = (by, by_name) {
-        text = format!("{text}\nby: {}", link(&by, &by_name));
+        message = message
+            .text(format!("\n{} ", language.tr("job-by")))
+            .link(by, by_name);
     }
 
     if let Some(url) = url {
-        text = format!("{text}\nlink: {}", link(&url, &repo_name));
+        message = message
+            .text(format!("\n{} ", language.tr("job-link")))
+            .link(url, repo_name);
     }
 
-    text
+    message
 }
 
-fn format_update_message(
+/// the catalog message of the first line of a notification about a job of the given status
+pub fn headline_id(status: DeployStatus, flaky: bool) -> &'static str {
+    match status {
+        DeployStatus::Running => "job-running",
+        DeployStatus::Success => "job-success",
+        DeployStatus::Failure if flaky => "job-flaky-failure",
+        DeployStatus::Failure => "job-failure",
+        DeployStatus::Cancelled => "job-cancelled",
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn format_update_message(
+    language: Language,
     repo_name: String,
     status: DeployStatus,
     elapsed: String,
@@ -64,52 +120,57 @@ fn format_update_message(
     description: Option<String>,
     by: Option<String>,
     by_name: Option<String>,
-) -> Result<String, ServiceError> {
-    let mut text = description.map_or(
-        match status {
-            DeployStatus::Success => {
-                format!("✅ {repo_name}'s job has completed")
-            }
-            DeployStatus::Failure => {
-                format!("🚨 {repo_name}'s job encountered failure")
-            }
-            DeployStatus::Cancelled => {
-                format!("⛔️ {repo_name}'s job was cancelled")
-            }
-            _ => {
-                return Err(ServiceError::ParseMessage(format!(
-                    "Invalid job status: {status}"
-                )))
-            }
-        },
-        |dsc| dsc,
-    );
-    text = format!("{text}\nelapsed: {elapsed}");
+    flaky: bool,
+) -> Result<MessageBuilder, ServiceError> {
+    if status == DeployStatus::Running {
+        return Err(ServiceError::ParseMessage(format!(
+            "Invalid job status: {status}"
+        )));
+    }
+
+    let text = description.unwrap_or_else(|| {
+        language.tr_with(
+            headline_id(status, flaky),
+            &[("repo", repo_name.as_str().into())],
+        )
+    });
+    let mut message = MessageBuilder::new().text(format!(
+        "{text}\n{}",
+        language.tr_with("job-elapsed", &[("elapsed", elapsed.into())])
+    ));
 
     if let (Some(by), Some(by_name)) = (by, by_name) {
-        text = format!("{text}\nby: {}", link(&by, &by_name));
+        message = message
+            .text(format!("\n{} ", language.tr("job-by")))
+            .link(by, by_name);
     }
 
     if let Some(url) = url {
-        text = format!("{text}\nlink: {}", link(&url, &repo_name));
+        message = message
+            .text(format!("\n{} ", language.tr("job-link")))
+            .link(url, repo_name);
     }
 
-    Ok(text)
+    Ok(message)
 }
 
 pub async fn create_job_handler(
     Extension(SessionContainer(session)): Extension<SessionContainer>,
     Extension(pool): Extension<Pool<Sqlite>>,
-    Extension(bot): Extension<Bot>,
     Json(JobCreationBody {
         job_id,
         url,
         description,
         by,
         by_name,
+        committed_at,
+        branch,
+        commit_sha,
     }): Json<JobCreationBody>,
 ) -> impl IntoResponse {
     if let Some(session) = session {
+        let committed_at = committed_at.map(|committed_at| committed_at.naive_utc());
+        let mut transaction = pool.begin().await?;
         let record = query!(
             r#"
             SELECT message_id, name
@@ -118,29 +179,67 @@ pub async fn create_job_handler(
             "#,
             session.sid
         )
-        .fetch_one(&pool)
+        .fetch_one(&mut transaction)
         .await?;
         query!(
             r#"
             INSERT INTO main.jobs
-            (id, status, triggered_by, description, callback_url, repo_id)
-            VALUES (?, ?, ?, ?, ?, ?)
+            (id, status, triggered_by, description, callback_url, repo_id, committed_at, branch,
+                commit_sha)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#,
             job_id,
             DeployStatus::Running,
             by_name,
             description,
             url,
-            session.sid
+            session.sid,
+            committed_at,
+            branch,
+            commit_sha
         )
-        .execute(&pool)
+        .execute(&mut transaction)
         .await?;
 
-        bot.send_message(
+        let eta =
+            rolling_baseline(&mut transaction, &session.sid, branch.as_deref(), job_id).await?;
+        let template = find_template(&mut transaction, &session.sid, TemplateKind::Created).await?;
+        let language = chat_language(&mut transaction, record.message_id).await?;
+        let event = JobEvent {
+            job_id,
+            repo_name: record.name,
+            status: DeployStatus::Running,
+            elapsed: None,
+            url,
+            description,
+            by,
+            by_name,
+            slowdown: None,
+            eta,
+            flaky: false,
+            mention: None,
+            owners: vec![],
+            branch,
+            commit_sha,
+            template,
+            language,
+        };
+
+        enqueue(
+            &mut transaction,
+            ChatId(record.message_id),
+            &session.sid,
+            &event,
+        )
+        .await?;
+        enqueue_sinks(
+            &mut transaction,
             ChatId(record.message_id),
-            format_create_message(record.name, url, description, by, by_name),
+            &session.sid,
+            &event,
         )
         .await?;
+        transaction.commit().await?;
 
         Ok(StatusCode::OK)
     } else {
@@ -148,31 +247,80 @@ pub async fn create_job_handler(
     }
 }
 
-fn format_duration(elapsed: Duration) -> String {
-    if elapsed.num_seconds() < 60 {
-        return format!("{} second(s)", elapsed.num_seconds());
-    }
-    if elapsed.num_minutes() < 60 {
-        return format!("{} minute(s)", elapsed.num_minutes());
+pub fn format_duration(language: Language, elapsed: Duration) -> String {
+    let (id, count) = match elapsed {
+        elapsed if elapsed.num_seconds() < 60 => ("duration-seconds", elapsed.num_seconds()),
+        elapsed if elapsed.num_minutes() < 60 => ("duration-minutes", elapsed.num_minutes()),
+        elapsed if elapsed.num_hours() < 24 => ("duration-hours", elapsed.num_hours()),
+        elapsed => ("duration-days", elapsed.num_days()),
+    };
+
+    language.tr_with(id, &[("count", count.into())])
+}
+
+/// a rough estimate of the remaining time of a job in minutes i.e. `~7 min`
+pub fn format_eta(language: Language, seconds: i64) -> String {
+    match (seconds + 59) / 60 {
+        minutes if minutes < 60 => {
+            language.tr_with("eta-minutes", &[("minutes", minutes.max(1).into())])
+        }
+        minutes => language.tr_with(
+            "eta-hours",
+            &[
+                ("hours", (minutes / 60).into()),
+                ("minutes", (minutes % 60).into()),
+            ],
+        ),
     }
-    if elapsed.num_hours() < 24 {
-        return format!("{} hour(s)", elapsed.num_hours());
+}
+
+/// progress of a running job against its estimated duration
+pub fn format_progress(language: Language, elapsed: Duration, estimate: Option<i64>) -> String {
+    let running = format_duration(language, elapsed);
+
+    match estimate {
+        Some(estimate) if estimate > 0 && elapsed.num_seconds() < estimate => language.tr_with(
+            "progress-estimate",
+            &[
+                ("running", running.into()),
+                ("percent", (elapsed.num_seconds() * 100 / estimate).into()),
+                (
+                    "eta",
+                    format_eta(language, estimate - elapsed.num_seconds()).into(),
+                ),
+            ],
+        ),
+        Some(estimate) if estimate > 0 => language.tr_with(
+            "progress-overdue",
+            &[
+                ("running", running.into()),
+                ("eta", format_eta(language, estimate).into()),
+            ],
+        ),
+        _ => language.tr_with("progress-unknown", &[("running", running.into())]),
     }
-    format!("{} day(s)", elapsed.num_days())
 }
 
 pub async fn update_job_handler(
     Extension(SessionContainer(session)): Extension<SessionContainer>,
     Extension(pool): Extension<Pool<Sqlite>>,
-    Extension(bot): Extension<Bot>,
     Json(JobStatusBody {
         job_id,
         status,
         description,
         by,
+        tests,
+        changed_files,
     }): Json<JobStatusBody>,
 ) -> impl IntoResponse {
     if let Some(session) = session {
+        // a job only moves from running to a final status
+        if status == DeployStatus::Running {
+            return Err(ServiceError::ParseMessage(format!(
+                "Invalid job status: {status}"
+            )));
+        }
+
         let mut transaction = pool.begin().await?;
         let record = query!(
             r#"
@@ -180,13 +328,17 @@ pub async fn update_job_handler(
                 repos.name, 
                 jobs.callback_url, 
                 jobs.triggered_by, 
-                jobs.started_at
+                jobs.started_at,
+                jobs.branch,
+                jobs.commit_sha
             FROM main.jobs
             JOIN repos ON jobs.repo_id = repos.id
             WHERE repos.id = ?
+            AND jobs.id = ?
             AND jobs.status = ?
             "#,
             session.sid,
+            job_id,
             DeployStatus::Running
         )
         .fetch_one(&mut transaction)
@@ -210,17 +362,106 @@ pub async fn update_job_handler(
         .execute(&mut transaction)
         .await?;
 
-        bot.send_message(
+        let slowdown = match status {
+            DeployStatus::Success | DeployStatus::Failure => {
+                detect_slowdown(
+                    &mut transaction,
+                    &session.sid,
+                    record.branch.as_deref(),
+                    job_id,
+                    elapsed_seconds,
+                )
+                .await?
+            }
+            _ => None,
+        };
+
+        record_test_results(&mut transaction, &session.sid, job_id, &tests).await?;
+
+        match status {
+            DeployStatus::Failure => record_failure(&mut transaction, &session.sid, job_id).await?,
+            DeployStatus::Success => resolve_failures(&mut transaction, &session.sid).await?,
+            _ => {}
+        }
+
+        let flaky = status == DeployStatus::Failure
+            && is_flaky_failure(
+                &mut transaction,
+                &session.sid,
+                job_id,
+                record.commit_sha.as_deref(),
+                &tests,
+            )
+            .await?;
+        let owners = match status {
+            DeployStatus::Failure => {
+                find_owners(&mut transaction, &session.sid, &changed_files).await?
+            }
+            _ => vec![],
+        };
+        let template = find_template(&mut transaction, &session.sid, status.into()).await?;
+        let language = chat_language(&mut transaction, record.message_id).await?;
+        let (identity, dm_identity) = match &record.triggered_by {
+            Some(triggered_by) => (
+                find_identity(&mut transaction, record.message_id, triggered_by).await?,
+                find_dm_identity(&mut transaction, record.message_id, triggered_by).await?,
+            ),
+            None => (None, None),
+        };
+        let mut event = JobEvent {
+            job_id,
+            repo_name: record.name,
+            status,
+            elapsed: Some(elapsed_seconds),
+            url: record.callback_url,
+            description,
+            by,
+            by_name: record.triggered_by,
+            slowdown,
+            eta: None,
+            flaky,
+            mention: None,
+            owners,
+            branch: record.branch,
+            commit_sha: record.commit_sha,
+            template,
+            language,
+        };
+
+        // the user is told in a private chat, whose id is the same as the user's
+        if let Some(identity) = dm_identity.filter(|identity| identity.wants_dm(status)) {
+            let language = chat_language(&mut transaction, identity.mention.user_id).await?;
+            enqueue(
+                &mut transaction,
+                ChatId(identity.mention.user_id),
+                &session.sid,
+                &JobEvent {
+                    language,
+                    ..event.clone()
+                },
+            )
+            .await?;
+        }
+
+        // only a user linked in the chat of the repo is mentioned there
+        if let Some(identity) = identity {
+            if status == DeployStatus::Failure && identity.mode == MentionMode::Mention {
+                event.mention = Some(identity.mention);
+            }
+        }
+
+        enqueue(
+            &mut transaction,
+            ChatId(record.message_id),
+            &session.sid,
+            &event,
+        )
+        .await?;
+        enqueue_sinks(
+            &mut transaction,
             ChatId(record.message_id),
-            format_update_message(
-                record.name,
-                status,
-                format_duration(elapsed),
-                record.callback_url,
-                description,
-                by,
-                record.triggered_by,
-            )?,
+            &session.sid,
+            &event,
         )
         .await?;
         transaction.commit().await?;
@@ -230,3 +471,33 @@ pub async fn update_job_handler(
         Err(ServiceError::BadCredential)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn duration(seconds: i64) -> String {
+        format_duration(Language::En, Duration::seconds(seconds))
+    }
+
+    #[test]
+    fn format_duration_picks_the_largest_whole_unit() {
+        assert_eq!(duration(0), "0 seconds");
+        assert_eq!(duration(1), "1 second");
+        assert_eq!(duration(59), "59 seconds");
+        assert_eq!(duration(60), "1 minute");
+        assert_eq!(duration(3599), "59 minutes");
+        assert_eq!(duration(3600), "1 hour");
+        assert_eq!(duration(86399), "23 hours");
+        assert_eq!(duration(86400), "1 day");
+        assert_eq!(duration(10 * 86400 + 3600), "10 days");
+    }
+
+    #[test]
+    fn format_duration_is_translated() {
+        assert_ne!(
+            format_duration(Language::Th, Duration::minutes(5)),
+            duration(300)
+        );
+    }
+}
@@ -0,0 +1,422 @@
+//! Telegram messages built from typed segments. Every message the bot formats is sent in HTML
+//! parse mode and every segment escapes its own text, so nothing a user or a CI sends can break
+//! the markup of a message.
+use crate::app::config::bot::CiBot;
+use teloxide::{
+    payloads::{EditMessageTextSetters, SendMessageSetters},
+    requests::Requester,
+    types::{ChatId, MessageId, ParseMode},
+    utils::html::escape,
+};
+
+/// the only parse mode the bot sends messages in
+pub const PARSE_MODE: ParseMode = ParseMode::Html;
+/// telegram rejects any message longer than this many UTF-16 code units, not counting markup
+pub const MESSAGE_LIMIT: usize = 4096;
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum Segment {
+    Text(String),
+    Bold(String),
+    Code(String),
+    /// hidden until tapped
+    Spoiler(String),
+    /// a link, shown as its text alone unless the url is http(s) since telegram rejects the
+    /// whole message over a single invalid link
+    Link {
+        url: String,
+        text: String,
+    },
+    /// a mention by user id, which also works for users without a username
+    Mention {
+        user_id: i64,
+        name: String,
+    },
+}
+
+/// escape a value of an HTML attribute, which unlike text also has to escape quotes
+fn escape_attribute(text: &str) -> String {
+    escape(text).replace('"', "&quot;")
+}
+
+fn is_web_url(url: &str) -> bool {
+    url.starts_with("https://") || url.starts_with("http://")
+}
+
+impl Segment {
+    fn html(&self) -> String {
+        match self {
+            Self::Text(text) => escape(text),
+            Self::Bold(text) => format!("<b>{}</b>", escape(text)),
+            Self::Code(text) => format!("<code>{}</code>", escape(text)),
+            Self::Spoiler(text) => format!("<tg-spoiler>{}</tg-spoiler>", escape(text)),
+            Self::Link { url, text } if is_web_url(url) => {
+                format!("<a href=\"{}\">{}</a>", escape_attribute(url), escape(text))
+            }
+            Self::Link { text, .. } => escape(text),
+            Self::Mention { user_id, name } => {
+                format!("<a href=\"tg://user?id={user_id}\">{}</a>", escape(name))
+            }
+        }
+    }
+
+    /// the text telegram shows for this segment
+    fn plain(&self) -> &str {
+        match self {
+            Self::Text(text)
+            | Self::Bold(text)
+            | Self::Code(text)
+            | Self::Spoiler(text)
+            | Self::Link { text, .. } => text,
+            Self::Mention { name, .. } => name,
+        }
+    }
+}
+
+#[derive(Default, PartialEq, Eq, Debug, Clone)]
+pub struct MessageBuilder {
+    segments: Vec<Segment>,
+}
+
+impl MessageBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(mut self, segment: Segment) -> Self {
+        self.segments.push(segment);
+        self
+    }
+
+    pub fn text(self, text: impl ToString) -> Self {
+        self.push(Segment::Text(text.to_string()))
+    }
+
+    pub fn bold(self, text: impl ToString) -> Self {
+        self.push(Segment::Bold(text.to_string()))
+    }
+
+    pub fn code(self, text: impl ToString) -> Self {
+        self.push(Segment::Code(text.to_string()))
+    }
+
+    pub fn spoiler(self, text: impl ToString) -> Self {
+        self.push(Segment::Spoiler(text.to_string()))
+    }
+
+    pub fn link(self, url: impl ToString, text: impl ToString) -> Self {
+        self.push(Segment::Link {
+            url: url.to_string(),
+            text: text.to_string(),
+        })
+    }
+
+    pub fn mention(self, user_id: i64, name: impl ToString) -> Self {
+        self.push(Segment::Mention {
+            user_id,
+            name: name.to_string(),
+        })
+    }
+
+    pub fn line(self) -> Self {
+        self.text('\n')
+    }
+
+    /// append the segments of another message
+    pub fn append(mut self, other: MessageBuilder) -> Self {
+        self.segments.extend(other.segments);
+        self
+    }
+
+    /// a message of the given messages one after another, with a separator between each
+    pub fn join(messages: Vec<MessageBuilder>, separator: &str) -> Self {
+        messages
+            .into_iter()
+            .enumerate()
+            .fold(Self::new(), |joined, (index, message)| match index {
+                0 => joined.append(message),
+                _ => joined.text(separator).append(message),
+            })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    pub fn html(&self) -> String {
+        self.segments.iter().map(Segment::html).collect()
+    }
+
+    /// the text telegram shows for this message, which is what its length limit applies to
+    pub fn plain(&self) -> String {
+        self.segments.iter().map(Segment::plain).collect()
+    }
+
+    /// number of UTF-16 code units telegram counts against `MESSAGE_LIMIT`, so that an emoji
+    /// outside of the basic plane counts twice
+    pub fn len(&self) -> usize {
+        self.segments
+            .iter()
+            .map(|segment| segment.plain().encode_utf16().count())
+            .sum()
+    }
+}
+
+/// cut text into pieces of at most `limit` UTF-16 code units without splitting a character
+fn cut(text: &str, limit: usize) -> Vec<String> {
+    let mut pieces = vec![];
+    let mut piece = String::new();
+    let mut length = 0;
+
+    for c in text.chars() {
+        if !piece.is_empty() && length + c.len_utf16() > limit {
+            pieces.push(std::mem::take(&mut piece));
+            length = 0;
+        }
+
+        piece.push(c);
+        length += c.len_utf16();
+    }
+
+    if !piece.is_empty() {
+        pieces.push(piece);
+    }
+
+    pieces
+}
+
+/// join lines into as few messages as fit within `limit` UTF-16 code units each. A line longer
+/// than the limit on its own loses its formatting and is cut into pieces
+pub fn split_messages(lines: Vec<MessageBuilder>, limit: usize) -> Vec<MessageBuilder> {
+    let mut messages = vec![];
+    let mut message = MessageBuilder::new();
+    let mut length = 0;
+    let pieces = lines.into_iter().flat_map(|line| match line.len() > limit {
+        true => cut(&line.plain(), limit)
+            .into_iter()
+            .map(|piece| MessageBuilder::new().text(piece))
+            .collect(),
+        false => vec![line],
+    });
+
+    for piece in pieces {
+        let piece_length = piece.len();
+
+        if length > 0 && length + 1 + piece_length > limit {
+            messages.push(std::mem::take(&mut message));
+            length = 0;
+        }
+
+        if length > 0 {
+            message = message.line();
+            length += 1;
+        }
+
+        message = message.append(piece);
+        length += piece_length;
+    }
+
+    if !message.is_empty() {
+        messages.push(message);
+    }
+
+    messages
+}
+
+/// a request to send a built message, which can be given more options before it is awaited
+pub fn send_message(
+    bot: &CiBot,
+    chat_id: ChatId,
+    message: &MessageBuilder,
+) -> <CiBot as Requester>::SendMessage {
+    bot.send_message(chat_id, message.html())
+        .parse_mode(PARSE_MODE)
+}
+
+/// a request to send plain text, which is escaped like the text of a built message
+pub fn send_text(
+    bot: &CiBot,
+    chat_id: ChatId,
+    text: impl ToString,
+) -> <CiBot as Requester>::SendMessage {
+    send_message(bot, chat_id, &MessageBuilder::new().text(text))
+}
+
+/// a request to replace the text of a message with a built one
+pub fn edit_message_text(
+    bot: &CiBot,
+    chat_id: ChatId,
+    message_id: MessageId,
+    message: &MessageBuilder,
+) -> <CiBot as Requester>::EditMessageText {
+    bot.edit_message_text(chat_id, message_id, message.html())
+        .parse_mode(PARSE_MODE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(lines: &[&str]) -> Vec<MessageBuilder> {
+        lines
+            .iter()
+            .map(|line| MessageBuilder::new().text(line))
+            .collect()
+    }
+
+    fn plain(messages: &[MessageBuilder]) -> Vec<String> {
+        messages.iter().map(MessageBuilder::plain).collect()
+    }
+
+    const HOSTILE: &str = "<b>&\"</a><script>alert(1)</script>";
+    const ESCAPED: &str = "&lt;b&gt;&amp;\"&lt;/a&gt;&lt;script&gt;alert(1)&lt;/script&gt;";
+
+    #[test]
+    fn text_escapes_markup() {
+        assert_eq!(MessageBuilder::new().text(HOSTILE).html(), ESCAPED);
+        assert_eq!(
+            MessageBuilder::new().bold(HOSTILE).html(),
+            format!("<b>{ESCAPED}</b>")
+        );
+        assert_eq!(
+            MessageBuilder::new().code(HOSTILE).html(),
+            format!("<code>{ESCAPED}</code>")
+        );
+        assert_eq!(
+            MessageBuilder::new().spoiler(HOSTILE).html(),
+            format!("<tg-spoiler>{ESCAPED}</tg-spoiler>")
+        );
+        assert_eq!(
+            MessageBuilder::new().mention(42, HOSTILE).html(),
+            format!("<a href=\"tg://user?id=42\">{ESCAPED}</a>")
+        );
+    }
+
+    #[test]
+    fn link_escapes_its_text_and_url() {
+        let message =
+            MessageBuilder::new().link("https://ci.example.com/?a=1&b=\"><script>", HOSTILE);
+
+        assert_eq!(
+            message.html(),
+            format!(
+                "<a href=\"https://ci.example.com/?a=1&amp;b=&quot;&gt;&lt;script&gt;\">{ESCAPED}</a>"
+            )
+        );
+    }
+
+    #[test]
+    fn link_drops_urls_that_are_not_web_urls() {
+        for url in [
+            "javascript:alert(1)",
+            "JAVASCRIPT:alert(1)",
+            " https://ci.example.com",
+            "tg://user?id=42",
+            "data:text/html,<script>",
+        ] {
+            assert_eq!(MessageBuilder::new().link(url, "job").html(), "job");
+        }
+
+        assert_eq!(
+            MessageBuilder::new()
+                .link("javascript:alert(1)", HOSTILE)
+                .html(),
+            ESCAPED
+        );
+    }
+
+    #[test]
+    fn len_counts_text_before_escaping() {
+        // `&` is shown as one character even though it is sent as `&amp;`
+        assert_eq!(MessageBuilder::new().text("<&>").len(), 3);
+        assert_eq!(MessageBuilder::new().link("https://a.b", "<>").len(), 2);
+    }
+
+    #[test]
+    fn split_messages_never_cuts_an_entity() {
+        let messages = split_messages(lines(&["a&b<c>d\"e"]), 2);
+
+        assert_eq!(
+            messages
+                .iter()
+                .map(MessageBuilder::html)
+                .collect::<Vec<String>>(),
+            ["a&amp;", "b&lt;", "c&gt;", "d\"", "e"]
+        );
+    }
+
+    #[test]
+    fn split_messages_counts_an_entity_as_one_character() {
+        // five escaped characters fit a limit of five even though their markup is longer
+        let messages = split_messages(lines(&["<<", "&&"]), 5);
+
+        assert_eq!(plain(&messages), ["<<\n&&"]);
+        assert_eq!(messages[0].html(), "&lt;&lt;\n&amp;&amp;");
+    }
+
+    #[test]
+    fn len_counts_utf16_code_units() {
+        assert_eq!(MessageBuilder::new().text("abc").len(), 3);
+        assert_eq!(MessageBuilder::new().text("สวัสดี").len(), 6);
+        assert_eq!(MessageBuilder::new().text("🚨").bold(" ok").len(), 5);
+    }
+
+    #[test]
+    fn split_messages_without_lines() {
+        assert!(split_messages(vec![], 10).is_empty());
+    }
+
+    #[test]
+    fn split_messages_fills_a_message_up_to_the_limit() {
+        // 4 + 1 + 5 is exactly the limit, the newline between lines counts
+        let messages = split_messages(lines(&["abcd", "efghi", "j"]), 10);
+
+        assert_eq!(plain(&messages), ["abcd\nefghi", "j"]);
+    }
+
+    #[test]
+    fn split_messages_starts_a_new_message_past_the_limit() {
+        let messages = split_messages(lines(&["abcde", "fghij"]), 10);
+
+        assert_eq!(plain(&messages), ["abcde", "fghij"]);
+    }
+
+    #[test]
+    fn split_messages_cuts_a_line_longer_than_the_limit() {
+        let messages = split_messages(lines(&["ab", "cdefghijklm", "n"]), 5);
+
+        assert_eq!(plain(&messages), ["ab", "cdefg", "hijkl", "m\nn"]);
+        assert!(messages.iter().all(|message| message.len() <= 5));
+    }
+
+    #[test]
+    fn split_messages_keeps_the_formatting_of_lines_that_fit() {
+        let line = MessageBuilder::new().bold("a").code("b");
+        let messages = split_messages(vec![line.clone(), line], 5);
+
+        assert_eq!(messages.len(), 1);
+        assert_eq!(
+            messages[0].html(),
+            "<b>a</b><code>b</code>\n<b>a</b><code>b</code>"
+        );
+    }
+
+    #[test]
+    fn split_messages_never_cuts_an_emoji_in_half() {
+        let messages = split_messages(lines(&["🚨🚨🚨"]), 3);
+
+        assert_eq!(plain(&messages), ["🚨", "🚨", "🚨"]);
+    }
+
+    #[test]
+    fn split_messages_fits_an_emoji_heavy_page() {
+        let line = "🚨".repeat(1000);
+        let messages = split_messages(lines(&[&line, &line, &line]), MESSAGE_LIMIT);
+
+        // every line is 2000 code units so only two fit in a message
+        assert_eq!(messages.len(), 2);
+        assert!(messages
+            .iter()
+            .all(|message| message.len() <= MESSAGE_LIMIT));
+    }
+}
@@ -1,4 +1,25 @@
+pub mod audit;
 pub mod bot;
+pub mod browser;
+pub mod chart;
+pub mod codeowners;
+pub mod digest;
+pub mod dora;
+pub mod escalation;
+pub mod flaky;
+pub mod format;
+pub mod i18n;
+pub mod identity;
 pub mod job;
+pub mod message;
+pub mod notifier;
+pub mod outbox;
+pub mod regression;
+pub mod report;
+pub mod role;
 pub mod root;
+pub mod search;
+pub mod template;
+pub mod timezone;
+pub mod trash;
 // pub mod status;
@@ -0,0 +1,108 @@
+use super::{http_url, JobEvent, Notifier};
+use crate::app::util::error::ServiceError;
+use futures::future::{BoxFuture, FutureExt as _};
+use reqwest::Client;
+use serde_json::json;
+
+/// posts to a Discord channel webhook, see https://discord.com/developers/docs/resources/webhook
+pub struct DiscordNotifier {
+    client: Client,
+    url: String,
+}
+
+impl DiscordNotifier {
+    pub fn new(client: Client, url: String) -> Self {
+        Self { client, url }
+    }
+}
+
+fn format_message(event: &JobEvent) -> String {
+    let language = event.language;
+    let mut text = event.headline();
+
+    if let Some(elapsed) = event.elapsed() {
+        text = format!(
+            "{text}\n{}",
+            language.tr_with("job-elapsed", &[("elapsed", elapsed.into())])
+        );
+    }
+
+    if let Some(by_name) = &event.by_name {
+        text = match event.by.as_deref().and_then(http_url) {
+            Some(by) => format!("{text}\n{} [{by_name}](<{by}>)", language.tr("job-by")),
+            None => format!("{text}\n{} {by_name}", language.tr("job-by")),
+        };
+    }
+
+    if let Some(url) = event.url.as_deref().and_then(http_url) {
+        text = format!(
+            "{text}\n{} [{}](<{url}>)",
+            language.tr("job-link"),
+            event.repo_name
+        );
+    }
+
+    text
+}
+
+impl Notifier for DiscordNotifier {
+    fn notify<'a>(&'a self, event: &'a JobEvent) -> BoxFuture<'a, Result<(), ServiceError>> {
+        async move {
+            self.client
+                .post(&self.url)
+                .json(&json!({
+                    "content": format_message(event),
+                    // never let user supplied description ping a whole server
+                    "allowed_mentions": { "parse": [] },
+                }))
+                .send()
+                .await?
+                .error_for_status()?;
+
+            Ok(())
+        }
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::service::notifier::stand_in::{failed_job, serve};
+    use reqwest::StatusCode;
+
+    #[tokio::test]
+    async fn notify_posts_content_without_mentions() {
+        let stand_in = serve(StatusCode::NO_CONTENT);
+
+        DiscordNotifier::new(Client::new(), format!("{}/api/webhooks/1/x", stand_in.url))
+            .notify(&failed_job())
+            .await
+            .unwrap();
+
+        let request = stand_in.request();
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, "/api/webhooks/1/x");
+        assert_eq!(request.headers["content-type"], "application/json");
+        assert_eq!(
+            request.body["content"],
+            "🚨 api's job encountered failure\nelapsed: 1 minute\n\
+            by: [octocat](<https://github.com/octocat>)\n\
+            link: [api](<https://ci.example.com/jobs/7>)"
+        );
+        assert_eq!(request.body["allowed_mentions"]["parse"], json!([]));
+    }
+
+    #[tokio::test]
+    async fn notify_fails_on_an_error_status() {
+        let stand_in = serve(StatusCode::TOO_MANY_REQUESTS);
+        let result = DiscordNotifier::new(Client::new(), stand_in.url.clone())
+            .notify(&failed_job())
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(ServiceError::Reqwest(e)) if e.status() == Some(StatusCode::TOO_MANY_REQUESTS)
+        ));
+    }
+}
@@ -0,0 +1,555 @@
+use super::{http_url, JobEvent, Notifier};
+use crate::{
+    app::{
+        config::mailer::Mailer,
+        service::{
+            bot::state::DeployStatus,
+            i18n::{chat_language, Language},
+            report::{failed_jobs, summarize_repo, FailedJob, JobSummary},
+        },
+        util::{error::ServiceError, sentry::capture_warning},
+    },
+    EMAIL_DIGEST_HOUR,
+};
+use chrono::{Duration, NaiveDateTime, Utc};
+use futures::future::{BoxFuture, FutureExt as _};
+use lettre::{
+    message::{Mailbox, MultiPart},
+    AsyncTransport, Message,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::{query, Pool, Sqlite};
+use std::fmt::Display;
+use tracing::{info, warn};
+
+#[derive(Deserialize, Serialize, sqlx::Type, PartialEq, Eq, Debug, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "subscription", rename_all = "UPPERCASE")]
+pub enum EmailSubscription {
+    Failure,
+    Digest,
+    All,
+}
+
+impl Display for EmailSubscription {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Failure => "failure",
+                Self::Digest => "digest",
+                Self::All => "all",
+            }
+        )
+    }
+}
+
+impl TryFrom<&str> for EmailSubscription {
+    type Error = ServiceError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value.to_lowercase().as_str() {
+            "failure" | "failures" => Ok(Self::Failure),
+            "digest" => Ok(Self::Digest),
+            "all" => Ok(Self::All),
+            _ => Err(ServiceError::TryFrom {
+                field: "subscription",
+                from: value.to_string(),
+                into: "EmailSubscription",
+                expect: "failure, digest, or all",
+            }),
+        }
+    }
+}
+
+/// sends failure notifications to every recipient subscribed to a repo. Other statuses are
+/// ignored since a mailbox is not the place for every job that is started or completed
+pub struct EmailNotifier {
+    mailer: Mailer,
+    recipients: Vec<Mailbox>,
+}
+
+impl EmailNotifier {
+    pub fn new(mailer: Mailer, recipients: Vec<Mailbox>) -> Self {
+        Self { mailer, recipients }
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// an email to every recipient. The recipients are blind copied so that they do not see each
+/// other's address, and the email is addressed to its sender
+fn build_message(
+    from: &Mailbox,
+    recipients: &[Mailbox],
+    subject: String,
+    text: String,
+    html: String,
+) -> Result<Message, ServiceError> {
+    let mut builder = Message::builder()
+        .from(from.clone())
+        .to(from.clone())
+        .subject(subject);
+
+    for recipient in recipients {
+        builder = builder.bcc(recipient.clone());
+    }
+
+    Ok(builder.multipart(MultiPart::alternative_plain_html(text, html))?)
+}
+
+/// an html link to a url given by CI, or only its text when the url is not http(s)
+fn link(url: Option<&str>, text: &str) -> String {
+    match url.and_then(http_url) {
+        Some(url) => format!("<a href=\"{}\">{}</a>", escape(url.as_str()), escape(text)),
+        None => escape(text),
+    }
+}
+
+fn format_failure_text(event: &JobEvent) -> String {
+    let language = event.language;
+    let mut text = event.headline();
+
+    if let Some(elapsed) = event.elapsed() {
+        text = format!(
+            "{text}\n{}",
+            language.tr_with("job-elapsed", &[("elapsed", elapsed.into())])
+        );
+    }
+
+    if let (Some(by), Some(by_name)) = (&event.by, &event.by_name) {
+        text = format!("{text}\n{} {by_name} ({by})", language.tr("job-by"));
+    }
+
+    if let Some(url) = &event.url {
+        text = format!("{text}\n{} {url}", language.tr("job-link"));
+    }
+
+    text
+}
+
+fn format_failure_html(event: &JobEvent) -> String {
+    let language = event.language;
+    let mut rows = String::new();
+
+    if let Some(elapsed) = event.elapsed() {
+        rows = format!(
+            "{rows}<tr><th align=\"left\">{}</th><td>{}</td></tr>",
+            escape(&language.tr("email-elapsed")),
+            escape(&elapsed)
+        );
+    }
+
+    if let Some(by_name) = &event.by_name {
+        rows = format!(
+            "{rows}<tr><th align=\"left\">{}</th><td>{}</td></tr>",
+            escape(&language.tr("email-by")),
+            link(event.by.as_deref(), by_name)
+        );
+    }
+
+    if let Some(url) = &event.url {
+        let link = match http_url(url) {
+            Some(_) => link(Some(url), &event.repo_name),
+            None => escape(url),
+        };
+        rows = format!(
+            "{rows}<tr><th align=\"left\">{}</th><td>{link}</td></tr>",
+            escape(&language.tr("email-link"))
+        );
+    }
+
+    format!(
+        "<html><body><h3>{}</h3><table>{rows}</table></body></html>",
+        escape(&event.headline())
+    )
+}
+
+/// the rows of the table of a digest email, a label and its count
+fn digest_rows(language: Language, summary: &JobSummary) -> [(String, i64); 5] {
+    [
+        (language.tr("email-jobs"), summary.total),
+        (language.tr("email-success"), summary.success),
+        (language.tr("email-failure"), summary.failure),
+        (language.tr("email-cancelled"), summary.cancelled),
+        (language.tr("email-running"), summary.running),
+    ]
+}
+
+fn format_digest_text(
+    language: Language,
+    repo_name: &str,
+    summary: &JobSummary,
+    failures: &[FailedJob],
+) -> String {
+    let mut text = language.tr_with("email-digest-title", &[("repo", repo_name.into())]);
+
+    for (label, count) in digest_rows(language, summary) {
+        text = format!("{text}\n{label}: {count}");
+    }
+
+    if let Some(pass_rate) = summary.pass_rate() {
+        text = format!(
+            "{text}\n{}: {pass_rate:.1}%",
+            language.tr("email-pass-rate")
+        );
+    }
+
+    if !failures.is_empty() {
+        text = format!("{text}\n\n{}:", language.tr("email-failed-jobs"));
+    }
+
+    for failure in failures {
+        text = format!(
+            "{text}\n- #{} {}",
+            failure.id,
+            failure.description.as_deref().unwrap_or_default()
+        );
+
+        if let Some(by_name) = &failure.triggered_by {
+            text = format!(
+                "{text} {}",
+                language.tr_with("email-failed-by", &[("name", by_name.as_str().into())])
+            );
+        }
+
+        if let Some(url) = &failure.callback_url {
+            text = format!("{text} ({url})");
+        }
+    }
+
+    text
+}
+
+fn format_digest_html(
+    language: Language,
+    repo_name: &str,
+    summary: &JobSummary,
+    failures: &[FailedJob],
+) -> String {
+    let mut html = format!(
+        "<html><body><h3>{}</h3><table>",
+        escape(&language.tr_with("email-digest-title", &[("repo", repo_name.into())]))
+    );
+
+    for (label, count) in digest_rows(language, summary) {
+        html = format!(
+            "{html}<tr><th align=\"left\">{}</th><td>{count}</td></tr>",
+            escape(&label)
+        );
+    }
+
+    if let Some(pass_rate) = summary.pass_rate() {
+        html = format!(
+            "{html}<tr><th align=\"left\">{}</th><td>{pass_rate:.1}%</td></tr>",
+            escape(&language.tr("email-pass-rate"))
+        );
+    }
+
+    html = format!("{html}</table>");
+
+    if !failures.is_empty() {
+        html = format!(
+            "{html}<h4>{}</h4><ul>",
+            escape(&language.tr("email-failed-jobs"))
+        );
+
+        for failure in failures {
+            let description = escape(failure.description.as_deref().unwrap_or_default());
+            let description = format!(
+                "{} {description}",
+                link(failure.callback_url.as_deref(), &format!("#{}", failure.id))
+            );
+
+            html = match &failure.triggered_by {
+                Some(by_name) => format!(
+                    "{html}<li>{description} {}</li>",
+                    escape(
+                        &language.tr_with("email-failed-by", &[("name", by_name.as_str().into())])
+                    )
+                ),
+                None => format!("{html}<li>{description}</li>"),
+            };
+        }
+
+        html = format!("{html}</ul>");
+    }
+
+    format!("{html}</body></html>")
+}
+
+impl Notifier for EmailNotifier {
+    fn notify<'a>(&'a self, event: &'a JobEvent) -> BoxFuture<'a, Result<(), ServiceError>> {
+        async move {
+            if event.status != DeployStatus::Failure || self.recipients.is_empty() {
+                return Ok(());
+            }
+
+            let message = build_message(
+                &self.mailer.from,
+                &self.recipients,
+                event.language.tr_with(
+                    "email-failure-subject",
+                    &[
+                        ("repo", event.repo_name.as_str().into()),
+                        ("job", event.job_id.to_string().into()),
+                    ],
+                ),
+                format_failure_text(event),
+                format_failure_html(event),
+            )?;
+
+            self.mailer.transport.send(message).await?;
+
+            Ok(())
+        }
+        .boxed()
+    }
+}
+
+pub async fn load_recipients(
+    pool: &Pool<Sqlite>,
+    repo_id: &str,
+    subscription: EmailSubscription,
+) -> Result<Vec<Mailbox>, ServiceError> {
+    let records = query!(
+        r#"
+        SELECT address
+        FROM main.email_recipients
+        WHERE repo_id = ?
+        AND subscription IN (?, ?)
+        "#,
+        repo_id,
+        subscription,
+        EmailSubscription::All
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(records
+        .into_iter()
+        .filter_map(|record| match record.address.parse() {
+            Ok(mailbox) => Some(mailbox),
+            Err(e) => {
+                warn!("skipping invalid email address {}: {e}", record.address);
+                None
+            }
+        })
+        .collect())
+}
+
+async fn send_repo_digest(
+    pool: &Pool<Sqlite>,
+    mailer: &Mailer,
+    repo_id: &str,
+    repo_name: &str,
+    chat_id: i64,
+    since: NaiveDateTime,
+) -> Result<(), ServiceError> {
+    let recipients = load_recipients(pool, repo_id, EmailSubscription::Digest).await?;
+
+    if recipients.is_empty() {
+        return Ok(());
+    }
+
+    let summary = summarize_repo(pool, repo_id, since).await?;
+    let failures = failed_jobs(pool, repo_id, since).await?;
+    let language = chat_language(pool, chat_id).await?;
+    let message = build_message(
+        &mailer.from,
+        &recipients,
+        language.tr_with("email-digest-subject", &[("repo", repo_name.into())]),
+        format_digest_text(language, repo_name, &summary, &failures),
+        format_digest_html(language, repo_name, &summary, &failures),
+    )?;
+
+    mailer.transport.send(message).await?;
+    info!(
+        "sent daily digest of {repo_name} to {} recipient(s)",
+        recipients.len()
+    );
+
+    Ok(())
+}
+
+/// email the digest of every subscribed repo. A repo that fails to be sent is only reported so
+/// that the repos after it still get their digest
+async fn send_daily_digest(
+    pool: &Pool<Sqlite>,
+    mailer: &Mailer,
+    since: NaiveDateTime,
+) -> Result<(), ServiceError> {
+    let repos = query!(
+        r#"
+        SELECT DISTINCT repos.id, repos.name, repos.message_id AS chat_id
+        FROM main.repos
+        JOIN email_recipients ON email_recipients.repo_id = repos.id
+        WHERE email_recipients.subscription IN (?, ?)
+        AND repos.deleted_at IS NULL
+        "#,
+        EmailSubscription::Digest,
+        EmailSubscription::All
+    )
+    .fetch_all(pool)
+    .await?;
+
+    for repo in repos {
+        if let Err(e) =
+            send_repo_digest(pool, mailer, &repo.id, &repo.name, repo.chat_id, since).await
+        {
+            warn!("failed to send daily digest of {}: {e}", repo.name);
+            capture_warning("email notifier failed to deliver daily digest");
+        }
+    }
+
+    Ok(())
+}
+
+/// time left until the next `EMAIL_DIGEST_HOUR` o'clock (UTC)
+fn until_next_digest() -> std::time::Duration {
+    let now = Utc::now().naive_utc();
+    let today = now
+        .date()
+        .and_hms_opt(*EMAIL_DIGEST_HOUR, 0, 0)
+        .unwrap_or(now);
+    let next = if today > now {
+        today
+    } else {
+        today + Duration::days(1)
+    };
+
+    (next - now).to_std().unwrap_or_default()
+}
+
+/// email a summary of the last 24 hours to digest subscribers once a day. This task never
+/// returns and is meant to be spawned alongside the other services
+pub async fn run_daily_digest(pool: Pool<Sqlite>, mailer: Mailer) {
+    loop {
+        tokio::time::sleep(until_next_digest()).await;
+
+        let since = Utc::now().naive_utc() - Duration::days(1);
+
+        if let Err(e) = send_daily_digest(&pool, &mailer, since).await {
+            warn!("failed to send daily digest: {e}");
+            capture_warning("email notifier failed to deliver daily digest");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::service::notifier::stand_in::failed_job;
+
+    fn summary() -> JobSummary {
+        JobSummary {
+            total: 4,
+            success: 2,
+            failure: 1,
+            cancelled: 1,
+            running: 0,
+        }
+    }
+
+    fn failures() -> Vec<FailedJob> {
+        vec![
+            FailedJob {
+                id: 7,
+                description: Some("tests <failed>".to_string()),
+                triggered_by: Some("octocat".to_string()),
+                callback_url: Some("https://ci.example.com/jobs/7".to_string()),
+            },
+            FailedJob {
+                id: 8,
+                description: None,
+                triggered_by: None,
+                callback_url: Some("javascript:alert(1)".to_string()),
+            },
+        ]
+    }
+
+    #[test]
+    fn build_message_blind_copies_recipients() {
+        let from = "CI Bot <ci@example.com>".parse::<Mailbox>().unwrap();
+        let recipients = ["alice@example.com", "bob@example.com"]
+            .map(|address| address.parse::<Mailbox>().unwrap());
+        let message = build_message(
+            &from,
+            &recipients,
+            "subject".to_string(),
+            "text".to_string(),
+            "html".to_string(),
+        )
+        .unwrap();
+        let formatted = String::from_utf8(message.formatted()).unwrap();
+
+        assert_eq!(message.envelope().to().len(), 3);
+        assert!(formatted.contains("To: \"CI Bot\" <ci@example.com>"));
+        assert!(!formatted.contains("alice@example.com"));
+        assert!(!formatted.contains("bob@example.com"));
+    }
+
+    #[test]
+    fn format_failure_text_lists_the_job() {
+        assert_eq!(
+            format_failure_text(&failed_job()),
+            "🚨 api's job encountered failure\nelapsed: 1 minute\n\
+            by: octocat (https://github.com/octocat)\n\
+            link: https://ci.example.com/jobs/7"
+        );
+    }
+
+    #[test]
+    fn format_failure_html_links_http_urls() {
+        let html = format_failure_html(&failed_job());
+
+        assert!(html.contains("<td><a href=\"https://github.com/octocat\">octocat</a></td>"));
+        assert!(html.contains("<td><a href=\"https://ci.example.com/jobs/7\">api</a></td>"));
+    }
+
+    #[test]
+    fn format_failure_html_never_links_other_urls() {
+        let event = JobEvent {
+            by: Some("javascript:alert(1)".to_string()),
+            by_name: Some("<b>octocat</b>".to_string()),
+            url: Some("data:text/html,<script>".to_string()),
+            ..failed_job()
+        };
+        let html = format_failure_html(&event);
+
+        assert!(!html.contains("href"));
+        assert!(html.contains("<td>&lt;b&gt;octocat&lt;/b&gt;</td>"));
+        assert!(html.contains("<td>data:text/html,&lt;script&gt;</td>"));
+    }
+
+    #[test]
+    fn format_digest_text_summarizes_the_repo() {
+        assert_eq!(
+            format_digest_text(Language::En, "api", &summary(), &failures()),
+            "api daily summary\njobs: 4\nsuccess: 2\nfailure: 1\ncancelled: 1\nrunning: 0\n\
+            pass rate: 66.7%\n\n\
+            failed jobs:\n\
+            - #7 tests <failed> by octocat (https://ci.example.com/jobs/7)\n\
+            - #8  (javascript:alert(1))"
+        );
+    }
+
+    #[test]
+    fn format_digest_html_escapes_and_only_links_http_urls() {
+        let html = format_digest_html(Language::En, "api", &summary(), &failures());
+
+        assert!(html.contains("<h3>api daily summary</h3>"));
+        assert!(html.contains("<tr><th align=\"left\">pass rate</th><td>66.7%</td></tr>"));
+        assert!(html.contains(
+            "<li><a href=\"https://ci.example.com/jobs/7\">#7</a> tests &lt;failed&gt; by octocat</li>"
+        ));
+        assert!(html.contains("<li>#8 </li>"));
+        assert!(!html.contains("javascript"));
+    }
+}
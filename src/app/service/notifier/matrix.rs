@@ -0,0 +1,222 @@
+use super::{http_url, JobEvent, Notifier};
+use crate::app::util::error::ServiceError;
+use futures::future::{BoxFuture, FutureExt as _};
+use reqwest::{Client, Url};
+use serde_json::json;
+
+/// sends `m.room.message` events through the Matrix client-server API, see
+/// https://spec.matrix.org/v1.4/client-server-api/#put_matrixclientv3roomsroomidsendeventtypetxnid
+pub struct MatrixNotifier {
+    client: Client,
+    homeserver: String,
+    room: String,
+    token: String,
+    /// the id of the outbox row being delivered
+    delivery_id: i64,
+}
+
+impl MatrixNotifier {
+    pub fn new(
+        client: Client,
+        homeserver: String,
+        room: String,
+        token: String,
+        delivery_id: i64,
+    ) -> Self {
+        Self {
+            client,
+            homeserver,
+            room,
+            token,
+            delivery_id,
+        }
+    }
+
+    fn endpoint(&self) -> Result<Url, ServiceError> {
+        let mut url = Url::parse(&self.homeserver).map_err(|e| ServiceError::ValidateFailure {
+            field: "homeserver",
+            reason: e.to_string(),
+        })?;
+        // matrix ignores a send that reuses the transaction id of an earlier one, so a retry
+        // of an outbox row that was delivered but timed out is not posted twice
+        let txn_id = format!("ci-bot-{}", self.delivery_id);
+
+        url.path_segments_mut()
+            .map_err(|_| ServiceError::ValidateFailure {
+                field: "homeserver",
+                reason: format!("{} cannot be a base url", self.homeserver),
+            })?
+            .pop_if_empty()
+            .extend([
+                "_matrix",
+                "client",
+                "v3",
+                "rooms",
+                &self.room,
+                "send",
+                "m.room.message",
+                &txn_id,
+            ]);
+
+        Ok(url)
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// render a plain text body alongside an html body for clients that support formatting
+fn format_message(event: &JobEvent) -> (String, String) {
+    let language = event.language;
+    let headline = event.headline();
+    let mut body = headline.clone();
+    let mut html = escape(&headline);
+
+    if let Some(elapsed) = event.elapsed() {
+        let elapsed = language.tr_with("job-elapsed", &[("elapsed", elapsed.into())]);
+        body = format!("{body}\n{elapsed}");
+        html = format!("{html}<br>{}", escape(&elapsed));
+    }
+
+    if let Some(by_name) = &event.by_name {
+        let label = language.tr("job-by");
+
+        match event.by.as_deref().and_then(http_url) {
+            Some(by) => {
+                body = format!("{body}\n{label} {by_name} ({by})");
+                html = format!(
+                    "{html}<br>{} <a href=\"{}\">{}</a>",
+                    escape(&label),
+                    escape(by.as_str()),
+                    escape(by_name)
+                );
+            }
+            None => {
+                body = format!("{body}\n{label} {by_name}");
+                html = format!("{html}<br>{} {}", escape(&label), escape(by_name));
+            }
+        }
+    }
+
+    if let Some(url) = event.url.as_deref().and_then(http_url) {
+        let label = language.tr("job-link");
+        body = format!("{body}\n{label} {url}");
+        html = format!(
+            "{html}<br>{} <a href=\"{}\">{}</a>",
+            escape(&label),
+            escape(url.as_str()),
+            escape(&event.repo_name)
+        );
+    }
+
+    (body, html)
+}
+
+impl Notifier for MatrixNotifier {
+    fn notify<'a>(&'a self, event: &'a JobEvent) -> BoxFuture<'a, Result<(), ServiceError>> {
+        async move {
+            let (body, formatted_body) = format_message(event);
+
+            self.client
+                .put(self.endpoint()?)
+                .bearer_auth(&self.token)
+                .json(&json!({
+                    "msgtype": "m.text",
+                    "body": body,
+                    "format": "org.matrix.custom.html",
+                    "formatted_body": formatted_body,
+                }))
+                .send()
+                .await?
+                .error_for_status()?;
+
+            Ok(())
+        }
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::service::notifier::stand_in::{failed_job, serve};
+    use reqwest::StatusCode;
+
+    fn notifier(homeserver: String, delivery_id: i64) -> MatrixNotifier {
+        MatrixNotifier::new(
+            Client::new(),
+            homeserver,
+            "!room:example.com".to_string(),
+            "secret".to_string(),
+            delivery_id,
+        )
+    }
+
+    #[tokio::test]
+    async fn notify_puts_a_message_with_the_access_token() {
+        let stand_in = serve(StatusCode::OK);
+
+        notifier(stand_in.url.clone(), 42)
+            .notify(&failed_job())
+            .await
+            .unwrap();
+
+        let request = stand_in.request();
+        assert_eq!(request.method, "PUT");
+        assert_eq!(
+            request.path,
+            "/_matrix/client/v3/rooms/!room:example.com/send/m.room.message/ci-bot-42"
+        );
+        assert_eq!(request.headers["authorization"], "Bearer secret");
+        assert_eq!(request.body["msgtype"], "m.text");
+        assert_eq!(
+            request.body["body"],
+            "🚨 api's job encountered failure\nelapsed: 1 minute\n\
+            by: octocat (https://github.com/octocat)\n\
+            link: https://ci.example.com/jobs/7"
+        );
+        assert_eq!(
+            request.body["formatted_body"],
+            "🚨 api's job encountered failure<br>elapsed: 1 minute<br>\
+            by: <a href=\"https://github.com/octocat\">octocat</a><br>\
+            link: <a href=\"https://ci.example.com/jobs/7\">api</a>"
+        );
+    }
+
+    #[test]
+    fn endpoint_reuses_the_transaction_id_of_a_delivery() {
+        let notifier = notifier("https://matrix.example.com".to_string(), 42);
+
+        assert_eq!(notifier.endpoint().unwrap(), notifier.endpoint().unwrap());
+    }
+
+    #[tokio::test]
+    async fn notify_fails_on_an_error_status() {
+        let stand_in = serve(StatusCode::FORBIDDEN);
+        let result = notifier(stand_in.url.clone(), 42)
+            .notify(&failed_job())
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(ServiceError::Reqwest(e)) if e.status() == Some(StatusCode::FORBIDDEN)
+        ));
+    }
+
+    #[test]
+    fn format_message_only_links_http_urls() {
+        let event = JobEvent {
+            by: Some("javascript:alert(1)".to_string()),
+            ..failed_job()
+        };
+        let (body, html) = format_message(&event);
+
+        assert!(!body.contains("javascript"));
+        assert!(!html.contains("javascript"));
+        assert!(html.contains("by: octocat<br>"));
+    }
+}
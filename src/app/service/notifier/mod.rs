@@ -0,0 +1,532 @@
+pub mod discord;
+pub mod email;
+pub mod matrix;
+pub mod slack;
+pub mod telegram;
+pub mod webhook;
+
+use super::{
+    bot::state::DeployStatus,
+    i18n::Language,
+    identity::Mention,
+    job::{format_duration, headline_id},
+};
+use crate::app::util::error::ServiceError;
+use chrono::Duration;
+use discord::DiscordNotifier;
+use futures::future::BoxFuture;
+use matrix::MatrixNotifier;
+use reqwest::{Client, Url};
+use serde::{Deserialize, Serialize};
+use slack::SlackNotifier;
+use sqlx::{query_as, Pool, Sqlite};
+use std::{
+    fmt::Display,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+};
+use webhook::WebhookNotifier;
+
+/// a snapshot of a job at the time a notification is emitted. Every notifier renders its own
+/// message from this value so that no sink depends on the formatting of another.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct JobEvent {
+    pub job_id: i32,
+    pub repo_name: String,
+    pub status: DeployStatus,
+    pub elapsed: Option<i64>,
+    pub url: Option<String>,
+    pub description: Option<String>,
+    pub by: Option<String>,
+    pub by_name: Option<String>,
+    /// how many times slower than usual the job was, set only when it crossed the threshold
+    #[serde(default)]
+    pub slowdown: Option<f64>,
+    /// estimated duration in seconds of a job that has just started
+    #[serde(default)]
+    pub eta: Option<i64>,
+    /// a failure that is most likely a flake rather than a broken build
+    #[serde(default)]
+    pub flaky: bool,
+    /// the telegram user linked to whoever triggered a failed job
+    #[serde(default)]
+    pub mention: Option<Mention>,
+    /// telegram handles of the owners of the files changed by a failed job
+    #[serde(default)]
+    pub owners: Vec<String>,
+    #[serde(default)]
+    pub branch: Option<String>,
+    #[serde(default)]
+    pub commit_sha: Option<String>,
+    /// the template of the repo for this notification, replacing the default wording
+    #[serde(default)]
+    pub template: Option<String>,
+    /// the language of the chat the event is sent to
+    #[serde(default)]
+    pub language: Language,
+}
+
+impl JobEvent {
+    /// first line of a plain text notification, worded like the telegram notification
+    pub fn headline(&self) -> String {
+        self.description.clone().unwrap_or_else(|| {
+            self.language.tr_with(
+                headline_id(self.status, self.flaky),
+                &[("repo", self.repo_name.as_str().into())],
+            )
+        })
+    }
+
+    pub fn slowdown(&self) -> Option<String> {
+        self.slowdown.map(|ratio| {
+            self.language
+                .tr_with("job-slowdown", &[("ratio", format!("{ratio:.1}").into())])
+        })
+    }
+
+    pub fn elapsed(&self) -> Option<String> {
+        self.elapsed
+            .map(|elapsed| format_duration(self.language, Duration::seconds(elapsed)))
+    }
+}
+
+/// a link given by CI, which is only rendered as a link when it is an http(s) url so that a
+/// `javascript:` or other url never ends up in a notification
+pub fn http_url(value: &str) -> Option<Url> {
+    Url::parse(value)
+        .ok()
+        .filter(|url| matches!(url.scheme(), "http" | "https"))
+}
+
+fn is_public_v4(ip: Ipv4Addr) -> bool {
+    let [first, second, ..] = ip.octets();
+
+    !(ip.is_private()
+        || ip.is_loopback()
+        || ip.is_link_local()
+        || ip.is_unspecified()
+        || ip.is_broadcast()
+        || ip.is_documentation()
+        || first == 0
+        // shared address space of carrier-grade NAT, 100.64.0.0/10
+        || (first == 100 && (64..128).contains(&second)))
+}
+
+fn is_public_v6(ip: Ipv6Addr) -> bool {
+    let [first, ..] = ip.segments();
+
+    match ip.to_ipv4_mapped() {
+        Some(ip) => is_public_v4(ip),
+        None => {
+            !(ip.is_loopback()
+                || ip.is_unspecified()
+                // unique local fc00::/7 and link local fe80::/10
+                || (first & 0xfe00) == 0xfc00
+                || (first & 0xffc0) == 0xfe80)
+        }
+    }
+}
+
+/// whether an address can be reached from the internet, a notifier must never reach the
+/// loopback or private network of the server
+pub fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_v4(ip),
+        IpAddr::V6(ip) => is_public_v6(ip),
+    }
+}
+
+/// check that a notifier target is an https url of a public host. Only the url itself is
+/// checked, `check_target` also checks the addresses the host resolves to
+fn parse_target(target: &str) -> Result<Url, ServiceError> {
+    let url = Url::parse(target).map_err(|_| ServiceError::ValidateFailure {
+        field: "target",
+        reason: format!("{target} is not a valid url"),
+    })?;
+    // an ipv6 host is written in brackets
+    let host = url
+        .host_str()
+        .unwrap_or_default()
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .trim_end_matches('.')
+        .to_lowercase();
+    let private = match host.parse::<IpAddr>() {
+        Ok(ip) => !is_public(ip),
+        Err(_) => {
+            host.is_empty()
+                || host == "localhost"
+                || [".localhost", ".local", ".internal"]
+                    .iter()
+                    .any(|suffix| host.ends_with(suffix))
+        }
+    };
+
+    if url.scheme() != "https" {
+        return Err(ServiceError::ValidateFailure {
+            field: "target",
+            reason: format!("{target} is not an https url"),
+        });
+    }
+
+    if private {
+        return Err(ServiceError::ValidateFailure {
+            field: "target",
+            reason: format!("{target} is not a public host"),
+        });
+    }
+
+    Ok(url)
+}
+
+pub trait Notifier: Send + Sync {
+    fn notify<'a>(&'a self, event: &'a JobEvent) -> BoxFuture<'a, Result<(), ServiceError>>;
+}
+
+#[derive(Deserialize, Serialize, sqlx::Type, PartialEq, Eq, Debug, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "kind", rename_all = "UPPERCASE")]
+pub enum NotifierKind {
+    Slack,
+    Discord,
+    Matrix,
+    Webhook,
+}
+
+impl Display for NotifierKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Slack => "slack",
+                Self::Discord => "discord",
+                Self::Matrix => "matrix",
+                Self::Webhook => "webhook",
+            }
+        )
+    }
+}
+
+impl TryFrom<&str> for NotifierKind {
+    type Error = ServiceError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value.to_lowercase().as_str() {
+            "slack" => Ok(Self::Slack),
+            "discord" => Ok(Self::Discord),
+            "matrix" => Ok(Self::Matrix),
+            "webhook" => Ok(Self::Webhook),
+            _ => Err(ServiceError::TryFrom {
+                field: "kind",
+                from: value.to_string(),
+                into: "NotifierKind",
+                expect: "slack, discord, matrix, or webhook",
+            }),
+        }
+    }
+}
+
+/// a notifier row of `main.notifiers`
+#[derive(Debug, Clone)]
+pub struct NotifierConfig {
+    pub id: i64,
+    pub kind: NotifierKind,
+    pub target: String,
+    pub room: Option<String>,
+    pub token: Option<String>,
+}
+
+impl NotifierConfig {
+    /// parse the argument of `/add_notifier` which is in the form of
+    /// `<slack|discord|webhook> <url> [token]` or `matrix <homeserver> <room_id> <access_token>`.
+    /// The url has to be https and must not name a loopback or private host
+    pub fn parse(args: &str) -> Result<Self, ServiceError> {
+        let mut args = args.split_whitespace();
+        let kind = NotifierKind::try_from(args.next().unwrap_or_default())?;
+        let target = args
+            .next()
+            .ok_or_else(|| ServiceError::ValidateFailure {
+                field: "target",
+                reason: "expect a webhook or homeserver url".to_string(),
+            })?
+            .to_string();
+
+        parse_target(&target)?;
+
+        let (room, token) = match kind {
+            NotifierKind::Matrix => match (args.next(), args.next()) {
+                (Some(room), Some(token)) => (Some(room.to_string()), Some(token.to_string())),
+                _ => {
+                    return Err(ServiceError::ValidateFailure {
+                        field: "room",
+                        reason: "matrix notifier expect a room id and an access token".to_string(),
+                    })
+                }
+            },
+            NotifierKind::Webhook => (None, args.next().map(|token| token.to_string())),
+            NotifierKind::Slack | NotifierKind::Discord => (None, None),
+        };
+
+        Ok(Self {
+            id: 0,
+            kind,
+            target,
+            room,
+            token,
+        })
+    }
+
+    /// parse the argument of `/add_notifier` and check what the host of its target resolves to
+    pub async fn parse_checked(args: &str) -> Result<Self, ServiceError> {
+        let config = Self::parse(args)?;
+        config.check_target().await?;
+
+        Ok(config)
+    }
+
+    /// check that the host of the target only resolves to public addresses. This is checked
+    /// when a notifier is added and again before every delivery, since what a host resolves to
+    /// can change after it was added
+    pub async fn check_target(&self) -> Result<(), ServiceError> {
+        let url = parse_target(&self.target)?;
+        let host = url.host_str().unwrap_or_default();
+        let port = url.port_or_known_default().unwrap_or(443);
+        let addresses = tokio::net::lookup_host((host, port))
+            .await
+            .map_err(|e| ServiceError::ValidateFailure {
+                field: "target",
+                reason: format!("{host} cannot be resolved: {e}"),
+            })?
+            .collect::<Vec<_>>();
+
+        if addresses.iter().any(|address| !is_public(address.ip())) {
+            return Err(ServiceError::ValidateFailure {
+                field: "target",
+                reason: format!("{host} resolves to a private address"),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// the notifier of the config for delivering an outbox row. Retries of the row share its
+    /// id, which lets a sink that deduplicates requests recognize a retry
+    pub fn into_notifier(self, client: Client, delivery_id: i64) -> Box<dyn Notifier> {
+        match self.kind {
+            NotifierKind::Slack => Box::new(SlackNotifier::new(client, self.target)),
+            NotifierKind::Discord => Box::new(DiscordNotifier::new(client, self.target)),
+            NotifierKind::Matrix => Box::new(MatrixNotifier::new(
+                client,
+                self.target,
+                self.room.unwrap_or_default(),
+                self.token.unwrap_or_default(),
+                delivery_id,
+            )),
+            NotifierKind::Webhook => {
+                Box::new(WebhookNotifier::new(client, self.target, self.token))
+            }
+        }
+    }
+}
+
+/// webhook urls and access tokens are secrets, a notifier is only shown with the host of its
+/// target and the last characters of the target
+impl Display for NotifierConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let host = Url::parse(&self.target)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_string))
+            .unwrap_or_default();
+        let last = self.target.chars().rev().take(4).collect::<Vec<char>>();
+        let last = last.into_iter().rev().collect::<String>();
+
+        write!(f, "{}. {} {host} …{last}", self.id, self.kind)?;
+
+        if let Some(room) = &self.room {
+            write!(f, " {room}")?;
+        }
+
+        Ok(())
+    }
+}
+
+pub async fn load_notifiers(
+    pool: &Pool<Sqlite>,
+    repo_id: &str,
+) -> Result<Vec<NotifierConfig>, ServiceError> {
+    Ok(query_as!(
+        NotifierConfig,
+        r#"
+        SELECT id AS "id!", kind AS "kind: NotifierKind", target, room, token
+        FROM main.notifiers
+        WHERE repo_id = ?
+        ORDER BY id
+        "#,
+        repo_id
+    )
+    .fetch_all(pool)
+    .await?)
+}
+
+pub async fn find_notifier(
+    pool: &Pool<Sqlite>,
+    id: i64,
+) -> Result<Option<NotifierConfig>, ServiceError> {
+    Ok(query_as!(
+        NotifierConfig,
+        r#"
+        SELECT id AS "id!", kind AS "kind: NotifierKind", target, room, token
+        FROM main.notifiers
+        WHERE id = ?
+        "#,
+        id
+    )
+    .fetch_optional(pool)
+    .await?)
+}
+
+/// a local http server standing in for the endpoint of a sink in tests
+#[cfg(test)]
+pub mod stand_in {
+    use super::{DeployStatus, JobEvent, Language};
+    use axum::{
+        body::Bytes,
+        http::{HeaderMap, Method, StatusCode, Uri},
+        routing::any,
+        Router,
+    };
+    use std::{
+        net::TcpListener,
+        sync::{Arc, Mutex},
+    };
+
+    /// a request the stand in received
+    pub struct Received {
+        pub method: Method,
+        pub path: String,
+        pub headers: HeaderMap,
+        pub body: serde_json::Value,
+    }
+
+    pub struct StandIn {
+        pub url: String,
+        received: Arc<Mutex<Vec<Received>>>,
+    }
+
+    impl StandIn {
+        /// the only request the stand in received
+        pub fn request(&self) -> Received {
+            let mut received = self.received.lock().unwrap();
+            assert_eq!(received.len(), 1);
+
+            received.remove(0)
+        }
+    }
+
+    /// serve every request with the given status
+    pub fn serve(status: StatusCode) -> StandIn {
+        let received = Arc::new(Mutex::new(vec![]));
+        let app = Router::new().fallback(any({
+            let received = Arc::clone(&received);
+
+            move |method: Method, uri: Uri, headers: HeaderMap, body: Bytes| async move {
+                received.lock().unwrap().push(Received {
+                    method,
+                    path: uri.path().to_string(),
+                    headers,
+                    body: serde_json::from_slice(&body).unwrap_or_default(),
+                });
+
+                status
+            }
+        }));
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+
+        tokio::spawn(
+            axum::Server::from_tcp(listener)
+                .unwrap()
+                .serve(app.into_make_service()),
+        );
+
+        StandIn { url, received }
+    }
+
+    pub fn failed_job() -> JobEvent {
+        JobEvent {
+            job_id: 7,
+            repo_name: "api".to_string(),
+            status: DeployStatus::Failure,
+            elapsed: Some(90),
+            url: Some("https://ci.example.com/jobs/7".to_string()),
+            description: None,
+            by: Some("https://github.com/octocat".to_string()),
+            by_name: Some("octocat".to_string()),
+            slowdown: None,
+            eta: None,
+            flaky: false,
+            mention: None,
+            owners: vec![],
+            branch: Some("main".to_string()),
+            commit_sha: None,
+            template: None,
+            language: Language::En,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn target(target: &str) -> Result<Url, ServiceError> {
+        parse_target(target)
+    }
+
+    #[test]
+    fn parse_target_requires_https() {
+        assert!(target("https://hooks.slack.com/services/T0/B0/x").is_ok());
+        assert!(target("http://hooks.slack.com/services/T0/B0/x").is_err());
+        assert!(target("file:///etc/passwd").is_err());
+    }
+
+    #[test]
+    fn parse_target_rejects_private_hosts() {
+        for private in [
+            "https://localhost/hook",
+            "https://ci.localhost/hook",
+            "https://127.0.0.1/hook",
+            "https://2130706433/hook",
+            "https://10.0.0.1/hook",
+            "https://172.16.0.1/hook",
+            "https://192.168.1.1/hook",
+            "https://169.254.169.254/latest/meta-data",
+            "https://100.64.0.1/hook",
+            "https://0.0.0.0/hook",
+            "https://[::1]/hook",
+            "https://[fd00::1]/hook",
+            "https://[fe80::1]/hook",
+            "https://[::ffff:127.0.0.1]/hook",
+        ] {
+            assert!(target(private).is_err(), "{private} should be rejected");
+        }
+
+        assert!(target("https://93.184.216.34/hook").is_ok());
+        assert!(target("https://[2606:2800:220:1::1]/hook").is_ok());
+    }
+
+    #[test]
+    fn parse_checks_the_target() {
+        assert!(NotifierConfig::parse("slack http://127.0.0.1:8080/hook").is_err());
+        assert!(NotifierConfig::parse("matrix https://10.0.0.1 !room:example.com token").is_err());
+        assert!(NotifierConfig::parse("discord https://discord.com/api/webhooks/1/x").is_ok());
+    }
+
+    #[test]
+    fn http_url_only_accepts_http() {
+        assert!(http_url("https://github.com/octocat").is_some());
+        assert!(http_url("http://ci.example.com/jobs/7").is_some());
+        assert!(http_url("javascript:alert(1)").is_none());
+        assert!(http_url("octocat").is_none());
+    }
+}
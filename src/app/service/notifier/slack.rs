@@ -0,0 +1,128 @@
+use super::{http_url, JobEvent, Notifier};
+use crate::app::util::error::ServiceError;
+use futures::future::{BoxFuture, FutureExt as _};
+use reqwest::Client;
+use serde_json::json;
+
+/// posts to a Slack incoming webhook, see https://api.slack.com/messaging/webhooks
+pub struct SlackNotifier {
+    client: Client,
+    url: String,
+}
+
+impl SlackNotifier {
+    pub fn new(client: Client, url: String) -> Self {
+        Self { client, url }
+    }
+}
+
+/// slack only require `&`, `<` and `>` to be escaped
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// a `<url|text>` link, whose url must not contain a `|` since it would end the url early
+fn link(url: &str, text: &str) -> String {
+    format!("<{}|{}>", escape(&url.replace('|', "%7C")), escape(text))
+}
+
+fn format_message(event: &JobEvent) -> String {
+    let language = event.language;
+    let mut text = escape(&event.headline());
+
+    if let Some(elapsed) = event.elapsed() {
+        let elapsed = language.tr_with("job-elapsed", &[("elapsed", elapsed.into())]);
+        text = format!("{text}\n{}", escape(&elapsed));
+    }
+
+    if let Some(by_name) = &event.by_name {
+        let by = match event.by.as_deref().and_then(http_url) {
+            Some(by) => link(by.as_str(), by_name),
+            None => escape(by_name),
+        };
+        text = format!("{text}\n{} {by}", escape(&language.tr("job-by")));
+    }
+
+    if let Some(url) = event.url.as_deref().and_then(http_url) {
+        text = format!(
+            "{text}\n{} {}",
+            escape(&language.tr("job-link")),
+            link(url.as_str(), &event.repo_name)
+        );
+    }
+
+    text
+}
+
+impl Notifier for SlackNotifier {
+    fn notify<'a>(&'a self, event: &'a JobEvent) -> BoxFuture<'a, Result<(), ServiceError>> {
+        async move {
+            self.client
+                .post(&self.url)
+                .json(&json!({ "text": format_message(event) }))
+                .send()
+                .await?
+                .error_for_status()?;
+
+            Ok(())
+        }
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::service::notifier::stand_in::{failed_job, serve};
+    use reqwest::StatusCode;
+
+    #[tokio::test]
+    async fn notify_posts_text() {
+        let stand_in = serve(StatusCode::OK);
+
+        SlackNotifier::new(Client::new(), format!("{}/services/T0/B0/x", stand_in.url))
+            .notify(&failed_job())
+            .await
+            .unwrap();
+
+        let request = stand_in.request();
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, "/services/T0/B0/x");
+        assert_eq!(request.headers["content-type"], "application/json");
+        assert_eq!(
+            request.body["text"],
+            "🚨 api's job encountered failure\nelapsed: 1 minute\n\
+            by: <https://github.com/octocat|octocat>\n\
+            link: <https://ci.example.com/jobs/7|api>"
+        );
+    }
+
+    #[tokio::test]
+    async fn notify_fails_on_an_error_status() {
+        let stand_in = serve(StatusCode::NOT_FOUND);
+        let result = SlackNotifier::new(Client::new(), stand_in.url.clone())
+            .notify(&failed_job())
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(ServiceError::Reqwest(e)) if e.status() == Some(StatusCode::NOT_FOUND)
+        ));
+    }
+
+    #[test]
+    fn format_message_escapes_links() {
+        let event = JobEvent {
+            by: Some("https://github.com/a|b>c&d".to_string()),
+            by_name: Some("<!channel>".to_string()),
+            url: Some("javascript:alert(1)".to_string()),
+            ..failed_job()
+        };
+        let text = format_message(&event);
+
+        assert!(text.contains("by: <https://github.com/a%7Cb%3Ec&amp;d|&lt;!channel&gt;>"));
+        assert!(!text.contains("javascript"));
+    }
+}
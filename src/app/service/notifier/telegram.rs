@@ -0,0 +1,164 @@
+use super::{JobEvent, Notifier};
+use crate::app::{
+    config::bot::CiBot,
+    service::{
+        bot::state::DeployStatus,
+        escalation::ack_keyboard,
+        job::{format_create_message, format_update_message},
+        message::{send_message, MessageBuilder, MESSAGE_LIMIT},
+        template::{Template, TemplateContext},
+    },
+    util::error::ServiceError,
+};
+use futures::future::{BoxFuture, FutureExt as _};
+use teloxide::{payloads::SendMessageSetters, types::ChatId};
+
+const COALESCE_SEPARATOR: &str = "\n\n";
+const MENTION_PREFIX: &str = "\ncc ";
+
+pub struct TelegramNotifier {
+    bot: CiBot,
+    chat_id: ChatId,
+}
+
+impl TelegramNotifier {
+    pub fn new(bot: CiBot, chat_id: ChatId) -> Self {
+        Self { bot, chat_id }
+    }
+
+    /// send several events of the same chat as one combined message. Failures carry a button
+    /// to acknowledge them
+    pub async fn notify_all(&self, events: &[JobEvent]) -> Result<(), ServiceError> {
+        let message = MessageBuilder::join(
+            events
+                .iter()
+                .map(format_message)
+                .collect::<Result<Vec<MessageBuilder>, ServiceError>>()?,
+            COALESCE_SEPARATOR,
+        );
+        let request = send_message(&self.bot, self.chat_id, &message);
+
+        match ack_keyboard(events) {
+            Some(keyboard) => request.reply_markup(keyboard).await?,
+            None => request.await?,
+        };
+
+        Ok(())
+    }
+}
+
+pub fn format_message(event: &JobEvent) -> Result<MessageBuilder, ServiceError> {
+    let elapsed = event.elapsed().unwrap_or_default();
+    let language = event.language;
+    let slowdown = event.slowdown();
+    let mention = event.mention.clone();
+    let event = event.clone();
+    // a template that no longer parses or comes out blank falls back to the default wording
+    let rendered = event
+        .template
+        .as_deref()
+        .and_then(|template| Template::parse(template).ok())
+        .and_then(|template| template.render(&TemplateContext::from(&event)));
+    let mut message = match (rendered, event.status) {
+        (Some(message), _) => message,
+        (_, DeployStatus::Running) => format_create_message(
+            language,
+            event.repo_name,
+            event.url,
+            event.description,
+            event.by,
+            event.by_name,
+            event.eta,
+        ),
+        (_, status) => format_update_message(
+            language,
+            event.repo_name,
+            status,
+            elapsed,
+            event.url,
+            event.description,
+            event.by,
+            event.by_name,
+            event.flaky,
+        )?,
+    };
+
+    if let Some(slowdown) = slowdown {
+        message = message.text(format!("\n{slowdown}"));
+    }
+
+    if !event.owners.is_empty() {
+        message = message.text(format!(
+            "\n{}",
+            language.tr_with("job-owners", &[("owners", event.owners.join(" ").into())])
+        ));
+    }
+
+    if let Some(mention) = mention {
+        message = message
+            .text(MENTION_PREFIX)
+            .mention(mention.user_id, mention.name);
+    }
+
+    Ok(message)
+}
+
+/// number of leading events that fit into a single coalesced message. At least one event is
+/// always taken so that an oversized message still reaches telegram and fails there
+pub fn coalesce_count(messages: &[MessageBuilder]) -> usize {
+    let mut length = 0;
+
+    messages
+        .iter()
+        .take_while(|message| {
+            let separator = if length == 0 {
+                0
+            } else {
+                COALESCE_SEPARATOR.encode_utf16().count()
+            };
+            length += separator + message.len();
+
+            length <= MESSAGE_LIMIT
+        })
+        .count()
+        .max(1)
+}
+
+impl Notifier for TelegramNotifier {
+    fn notify<'a>(&'a self, event: &'a JobEvent) -> BoxFuture<'a, Result<(), ServiceError>> {
+        async move { self.notify_all(std::slice::from_ref(event)).await }.boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn messages(text: &str, count: usize) -> Vec<MessageBuilder> {
+        vec![MessageBuilder::new().text(text); count]
+    }
+
+    #[test]
+    fn coalesce_count_takes_what_fits() {
+        let text = "a".repeat(2000);
+
+        // 2000 + 2 + 2000 fits, a third event does not
+        assert_eq!(coalesce_count(&messages(&text, 3)), 2);
+        assert_eq!(coalesce_count(&messages("a", 5)), 5);
+    }
+
+    #[test]
+    fn coalesce_count_counts_emoji_twice() {
+        // 1000 emoji are 2000 UTF-16 code units, so an emoji-heavy page fits two events
+        let text = "🚨".repeat(1000);
+
+        assert_eq!(coalesce_count(&messages(&text, 3)), 2);
+    }
+
+    #[test]
+    fn coalesce_count_always_takes_one_event() {
+        let text = "🚨".repeat(MESSAGE_LIMIT);
+
+        assert_eq!(coalesce_count(&messages(&text, 2)), 1);
+    }
+}
@@ -0,0 +1,102 @@
+use super::{JobEvent, Notifier};
+use crate::app::util::error::ServiceError;
+use futures::future::{BoxFuture, FutureExt as _};
+use reqwest::Client;
+use serde::Serialize;
+
+/// posts the raw job event as json to an arbitrary endpoint. When a token is configured it is
+/// sent as a bearer token so the receiver can verify where the event came from
+pub struct WebhookNotifier {
+    client: Client,
+    url: String,
+    token: Option<String>,
+}
+
+impl WebhookNotifier {
+    pub fn new(client: Client, url: String, token: Option<String>) -> Self {
+        Self { client, url, token }
+    }
+}
+
+#[derive(Serialize)]
+struct WebhookPayload<'a> {
+    #[serde(flatten)]
+    event: &'a JobEvent,
+    text: String,
+}
+
+impl Notifier for WebhookNotifier {
+    fn notify<'a>(&'a self, event: &'a JobEvent) -> BoxFuture<'a, Result<(), ServiceError>> {
+        async move {
+            let mut request = self.client.post(&self.url).json(&WebhookPayload {
+                event,
+                text: event.headline(),
+            });
+
+            if let Some(token) = &self.token {
+                request = request.bearer_auth(token);
+            }
+
+            request.send().await?.error_for_status()?;
+
+            Ok(())
+        }
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::service::notifier::stand_in::{failed_job, serve};
+    use reqwest::StatusCode;
+
+    #[tokio::test]
+    async fn notify_posts_the_event_with_its_token() {
+        let stand_in = serve(StatusCode::ACCEPTED);
+
+        WebhookNotifier::new(
+            Client::new(),
+            format!("{}/hook", stand_in.url),
+            Some("secret".to_string()),
+        )
+        .notify(&failed_job())
+        .await
+        .unwrap();
+
+        let request = stand_in.request();
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, "/hook");
+        assert_eq!(request.headers["authorization"], "Bearer secret");
+        assert_eq!(request.headers["content-type"], "application/json");
+        assert_eq!(request.body["job_id"], 7);
+        assert_eq!(request.body["repo_name"], "api");
+        assert_eq!(request.body["status"], "failure");
+        assert_eq!(request.body["text"], "🚨 api's job encountered failure");
+    }
+
+    #[tokio::test]
+    async fn notify_sends_no_token_when_there_is_none() {
+        let stand_in = serve(StatusCode::OK);
+
+        WebhookNotifier::new(Client::new(), stand_in.url.clone(), None)
+            .notify(&failed_job())
+            .await
+            .unwrap();
+
+        assert!(!stand_in.request().headers.contains_key("authorization"));
+    }
+
+    #[tokio::test]
+    async fn notify_fails_on_an_error_status() {
+        let stand_in = serve(StatusCode::BAD_GATEWAY);
+        let result = WebhookNotifier::new(Client::new(), stand_in.url.clone(), None)
+            .notify(&failed_job())
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(ServiceError::Reqwest(e)) if e.status() == Some(StatusCode::BAD_GATEWAY)
+        ));
+    }
+}
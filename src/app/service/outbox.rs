@@ -0,0 +1,629 @@
+use super::{
+    bot::state::DeployStatus,
+    message::MessageBuilder,
+    notifier::{
+        email::{load_recipients, EmailNotifier, EmailSubscription},
+        find_notifier,
+        telegram::{coalesce_count, format_message, TelegramNotifier},
+        JobEvent, Notifier,
+    },
+};
+use crate::app::{
+    config::{
+        bot::CiBot,
+        mailer::{mailer_configured, Mailer},
+    },
+    util::{error::ServiceError, sentry::capture_warning},
+};
+use chrono::{Duration, NaiveDateTime, Utc};
+use reqwest::{Client, StatusCode};
+use sqlx::{query, Pool, Sqlite, Transaction};
+use teloxide::{types::ChatId, RequestError};
+use tracing::{info, warn};
+
+/// how often the outbox is polled when there is nothing left to deliver
+const POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+/// maximum number of rows picked up in one polling round
+const BATCH_SIZE: i64 = 20;
+/// a notification is abandoned after this many failed attempts
+const MAX_ATTEMPTS: i64 = 12;
+const BASE_BACKOFF_SECONDS: i64 = 2;
+const MAX_BACKOFF_SECONDS: i64 = 60 * 60;
+/// a new notification is held back this long so that notifications of the same chat created
+/// shortly after can be merged into it
+const COALESCE_WINDOW: std::time::Duration = std::time::Duration::from_secs(3);
+/// maximum number of notifications merged into one message
+const COALESCE_LIMIT: i64 = 10;
+
+/// where an outbox row is delivered to
+#[derive(sqlx::Type, PartialEq, Eq, Debug, Clone, Copy)]
+#[sqlx(type_name = "sink", rename_all = "UPPERCASE")]
+pub enum Sink {
+    Telegram,
+    /// one of the notifiers of the repo
+    Notifier,
+    /// the failure subscribers of the repo
+    Email,
+}
+
+/// durably record a job event so that it can be delivered after the transaction commits. The
+/// event is written with the caller's transaction, so a notification exists if and only if
+/// the job change it describes does
+pub async fn enqueue(
+    transaction: &mut Transaction<'_, Sqlite>,
+    chat_id: ChatId,
+    repo_id: &str,
+    event: &JobEvent,
+) -> Result<(), ServiceError> {
+    let payload = serde_json::to_string(event)?;
+    let now = Utc::now().naive_utc();
+    let next_attempt_at =
+        now + Duration::from_std(COALESCE_WINDOW).unwrap_or_else(|_| Duration::zero());
+
+    query!(
+        r#"
+        INSERT INTO main.outbox
+        (chat_id, event, repo_id, created_at, next_attempt_at)
+        VALUES (?, ?, ?, ?, ?)
+        "#,
+        chat_id.0,
+        payload,
+        repo_id,
+        now,
+        next_attempt_at
+    )
+    .execute(transaction)
+    .await?;
+
+    Ok(())
+}
+
+/// durably record a job event for every notifier and the failure email subscribers of a repo.
+/// Each sink gets a row of its own so that it is retried or abandoned regardless of telegram and
+/// the other sinks. Only the event of the repo chat is recorded here, a private message of the
+/// same job is not passed on again. Subscribers are left out when email is not set up
+pub async fn enqueue_sinks(
+    transaction: &mut Transaction<'_, Sqlite>,
+    chat_id: ChatId,
+    repo_id: &str,
+    event: &JobEvent,
+) -> Result<(), ServiceError> {
+    let payload = serde_json::to_string(event)?;
+    let now = Utc::now().naive_utc();
+    let notifiers = query!(
+        r#"
+        SELECT id AS "id!"
+        FROM main.notifiers
+        WHERE repo_id = ?
+        "#,
+        repo_id
+    )
+    .fetch_all(&mut *transaction)
+    .await?;
+    let mut sinks = notifiers
+        .into_iter()
+        .map(|notifier| (Sink::Notifier, Some(notifier.id)))
+        .collect::<Vec<(Sink, Option<i64>)>>();
+
+    if event.status == DeployStatus::Failure && mailer_configured() {
+        let record = query!(
+            r#"
+            SELECT COUNT(*) AS "total!: i64"
+            FROM main.email_recipients
+            WHERE repo_id = ?
+            AND subscription IN (?, ?)
+            "#,
+            repo_id,
+            EmailSubscription::Failure,
+            EmailSubscription::All
+        )
+        .fetch_one(&mut *transaction)
+        .await?;
+
+        if record.total > 0 {
+            sinks.push((Sink::Email, None));
+        }
+    }
+
+    for (sink, notifier_id) in sinks {
+        query!(
+            r#"
+            INSERT INTO main.outbox
+            (chat_id, event, repo_id, created_at, next_attempt_at, sink, notifier_id)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            "#,
+            chat_id.0,
+            payload,
+            repo_id,
+            now,
+            now,
+            sink,
+            notifier_id
+        )
+        .execute(&mut *transaction)
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// exponential backoff starting from `BASE_BACKOFF_SECONDS` and capped at `MAX_BACKOFF_SECONDS`
+fn backoff(attempts: i64) -> Duration {
+    Duration::seconds(
+        BASE_BACKOFF_SECONDS
+            .saturating_mul(2_i64.saturating_pow(attempts.clamp(0, 30) as u32))
+            .min(MAX_BACKOFF_SECONDS),
+    )
+}
+
+enum Delivery {
+    Sent,
+    /// try again at the given time without giving up
+    Retry(NaiveDateTime),
+    /// the chat was upgraded to a supergroup and has a new id
+    Migrate(ChatId),
+    Abandon,
+}
+
+fn classify(error: &ServiceError, attempts: i64, now: NaiveDateTime) -> Delivery {
+    match error {
+        ServiceError::TeloxideError(RequestError::RetryAfter(after)) => {
+            Delivery::Retry(now + Duration::from_std(*after).unwrap_or_else(|_| backoff(attempts)))
+        }
+        ServiceError::TeloxideError(RequestError::MigrateToChatId(chat_id)) => {
+            Delivery::Migrate(ChatId(*chat_id))
+        }
+        // a sink understood the request and refused it, sending it again will not help
+        ServiceError::Reqwest(e)
+            if e.status().is_some_and(|status| {
+                status.is_client_error()
+                    && status != StatusCode::REQUEST_TIMEOUT
+                    && status != StatusCode::TOO_MANY_REQUESTS
+            }) =>
+        {
+            Delivery::Abandon
+        }
+        // telegram understood the request and refused it, sending it again will not help
+        ServiceError::TeloxideError(RequestError::Api(_))
+        | ServiceError::ParseMessage(_)
+        | ServiceError::SerdeJson(_)
+        | ServiceError::ConfigNotSet => Delivery::Abandon,
+        _ if attempts + 1 >= MAX_ATTEMPTS => Delivery::Abandon,
+        _ => Delivery::Retry(now + backoff(attempts)),
+    }
+}
+
+struct Pending {
+    id: i64,
+    event: Result<JobEvent, ServiceError>,
+}
+
+/// pending rows of a chat that were created within `COALESCE_WINDOW` of the given row
+async fn pending_group(
+    pool: &Pool<Sqlite>,
+    chat_id: i64,
+    id: i64,
+    created_at: NaiveDateTime,
+) -> Result<Vec<Pending>, ServiceError> {
+    let until =
+        created_at + Duration::from_std(COALESCE_WINDOW).unwrap_or_else(|_| Duration::zero());
+
+    Ok(query!(
+        r#"
+        SELECT id AS "id!", event
+        FROM main.outbox
+        WHERE chat_id = ?
+        AND sink = ?
+        AND id >= ?
+        AND created_at <= ?
+        AND sent_at IS NULL
+        AND failed_at IS NULL
+        ORDER BY id
+        LIMIT ?
+        "#,
+        chat_id,
+        Sink::Telegram,
+        id,
+        until,
+        COALESCE_LIMIT
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|record| Pending {
+        id: record.id,
+        event: serde_json::from_str::<JobEvent>(&record.event).map_err(ServiceError::from),
+    })
+    .collect())
+}
+
+/// apply the outcome of a delivery to every row that took part in it
+async fn settle(
+    pool: &Pool<Sqlite>,
+    ids: &[i64],
+    chat_id: i64,
+    attempts: i64,
+    result: &Result<(), ServiceError>,
+) -> Result<(), ServiceError> {
+    let now = Utc::now().naive_utc();
+    let last_error = result.as_ref().err().map(|e| e.to_string());
+    let delivery = match result {
+        Ok(_) => Delivery::Sent,
+        Err(e) => classify(e, attempts, now),
+    };
+    let mut transaction = pool.begin().await?;
+
+    match delivery {
+        Delivery::Sent => {
+            for id in ids {
+                query!(
+                    r#"
+                    UPDATE main.outbox
+                    SET sent_at = ?,
+                        attempts = attempts + 1
+                    WHERE id = ?
+                    "#,
+                    now,
+                    id
+                )
+                .execute(&mut transaction)
+                .await?;
+            }
+        }
+        Delivery::Retry(next_attempt_at) => {
+            warn!(
+                "outbox notification(s) {ids:?} failed, retrying at {next_attempt_at}: {}",
+                last_error.as_deref().unwrap_or_default()
+            );
+
+            for id in ids {
+                query!(
+                    r#"
+                    UPDATE main.outbox
+                    SET next_attempt_at = ?,
+                        attempts = attempts + 1,
+                        last_error = ?
+                    WHERE id = ?
+                    "#,
+                    next_attempt_at,
+                    last_error,
+                    id
+                )
+                .execute(&mut transaction)
+                .await?;
+            }
+        }
+        Delivery::Migrate(new_chat_id) => {
+            info!("chat {chat_id} migrated to {new_chat_id}, updating every row of the chat");
+            query!(
+                r#"
+                UPDATE main.outbox
+                SET chat_id = ?
+                WHERE chat_id = ?
+                "#,
+                new_chat_id.0,
+                chat_id
+            )
+            .execute(&mut transaction)
+            .await?;
+            query!(
+                r#"
+                UPDATE main.repos
+                SET message_id = ?
+                WHERE message_id = ?
+                "#,
+                new_chat_id.0,
+                chat_id
+            )
+            .execute(&mut transaction)
+            .await?;
+            query!(
+                r#"
+                UPDATE OR IGNORE main.chat_settings
+                SET chat_id = ?
+                WHERE chat_id = ?
+                "#,
+                new_chat_id.0,
+                chat_id
+            )
+            .execute(&mut transaction)
+            .await?;
+            query!(
+                r#"
+                UPDATE OR IGNORE main.identities
+                SET chat_id = ?
+                WHERE chat_id = ?
+                "#,
+                new_chat_id.0,
+                chat_id
+            )
+            .execute(&mut transaction)
+            .await?;
+            query!(
+                r#"
+                UPDATE main.job_searches
+                SET chat_id = ?
+                WHERE chat_id = ?
+                "#,
+                new_chat_id.0,
+                chat_id
+            )
+            .execute(&mut transaction)
+            .await?;
+            query!(
+                r#"
+                UPDATE main.repos
+                SET fallback_chat_id = ?
+                WHERE fallback_chat_id = ?
+                "#,
+                new_chat_id.0,
+                chat_id
+            )
+            .execute(&mut transaction)
+            .await?;
+            query!(
+                r#"
+                UPDATE OR IGNORE main.digests
+                SET chat_id = ?
+                WHERE chat_id = ?
+                "#,
+                new_chat_id.0,
+                chat_id
+            )
+            .execute(&mut transaction)
+            .await?;
+            query!(
+                r#"
+                UPDATE main.audit_events
+                SET chat_id = ?
+                WHERE chat_id = ?
+                "#,
+                new_chat_id.0,
+                chat_id
+            )
+            .execute(&mut transaction)
+            .await?;
+        }
+        Delivery::Abandon => {
+            warn!(
+                "outbox notification(s) {ids:?} abandoned after {} attempt(s): {}",
+                attempts + 1,
+                last_error.as_deref().unwrap_or_default()
+            );
+            capture_warning("outbox abandoned a notification");
+
+            for id in ids {
+                query!(
+                    r#"
+                    UPDATE main.outbox
+                    SET failed_at = ?,
+                        attempts = attempts + 1,
+                        last_error = ?
+                    WHERE id = ?
+                    "#,
+                    now,
+                    last_error,
+                    id
+                )
+                .execute(&mut transaction)
+                .await?;
+            }
+        }
+    }
+
+    transaction.commit().await?;
+
+    Ok(())
+}
+
+/// deliver every due telegram notification once. Rows of a chat are delivered strictly in order so a
+/// later notification is held back while an earlier one of the same chat is still pending.
+/// Rows of a chat that were created close to each other are merged into a single message so a
+/// burst of jobs does not exhaust telegram's per-chat limit
+async fn deliver_pending(pool: &Pool<Sqlite>, bot: &CiBot) -> Result<usize, ServiceError> {
+    let now = Utc::now().naive_utc();
+    let heads = query!(
+        r#"
+        SELECT id AS "id!", 
+            chat_id AS "chat_id!", 
+            attempts AS "attempts!",
+            created_at AS "created_at!"
+        FROM main.outbox
+        WHERE sink = ?
+        AND sent_at IS NULL
+        AND failed_at IS NULL
+        AND next_attempt_at <= ?
+        AND NOT EXISTS (
+            SELECT 1
+            FROM main.outbox AS earlier
+            WHERE earlier.chat_id = outbox.chat_id
+            AND earlier.sink = outbox.sink
+            AND earlier.id < outbox.id
+            AND earlier.sent_at IS NULL
+            AND earlier.failed_at IS NULL
+        )
+        ORDER BY id
+        LIMIT ?
+        "#,
+        Sink::Telegram,
+        now,
+        BATCH_SIZE
+    )
+    .fetch_all(pool)
+    .await?;
+    let delivered = heads.len();
+
+    for head in heads {
+        let mut group = pending_group(pool, head.chat_id, head.id, head.created_at).await?;
+        // an undecodable row is delivered on its own so that it is abandoned without taking
+        // valid notifications down with it
+        let decodable = group
+            .iter()
+            .take_while(|pending| pending.event.is_ok())
+            .count()
+            .max(1);
+        group.truncate(decodable);
+
+        let result = match group
+            .iter()
+            .map(|pending| pending.event.as_ref().cloned())
+            .collect::<Result<Vec<JobEvent>, &ServiceError>>()
+        {
+            Ok(events) => {
+                let messages = events
+                    .iter()
+                    .map(format_message)
+                    .collect::<Result<Vec<MessageBuilder>, ServiceError>>()
+                    .unwrap_or_default();
+                let count = coalesce_count(&messages);
+                group.truncate(count);
+
+                TelegramNotifier::new(bot.clone(), ChatId(head.chat_id))
+                    .notify_all(&events[..count])
+                    .await
+            }
+            Err(e) => Err(ServiceError::ParseMessage(format!(
+                "undecodable outbox notification: {e}"
+            ))),
+        };
+        let ids = group.iter().map(|pending| pending.id).collect::<Vec<i64>>();
+
+        settle(pool, &ids, head.chat_id, head.attempts, &result).await?;
+    }
+
+    Ok(delivered)
+}
+
+/// deliver an event to a sink other than telegram
+#[allow(clippy::too_many_arguments)]
+async fn deliver_to_sink(
+    pool: &Pool<Sqlite>,
+    client: &Client,
+    mailer: &Option<Mailer>,
+    id: i64,
+    repo_id: &str,
+    sink: Sink,
+    notifier_id: Option<i64>,
+    event: &JobEvent,
+) -> Result<(), ServiceError> {
+    match (sink, notifier_id, mailer) {
+        (Sink::Notifier, Some(notifier_id), _) => match find_notifier(pool, notifier_id).await? {
+            Some(config) => {
+                config.check_target().await?;
+                config.into_notifier(client.clone(), id).notify(event).await
+            }
+            // the notifier was removed after the event was recorded
+            None => Ok(()),
+        },
+        (Sink::Email, _, Some(mailer)) => {
+            let recipients = load_recipients(pool, repo_id, EmailSubscription::Failure).await?;
+
+            EmailNotifier::new(mailer.clone(), recipients)
+                .notify(event)
+                .await
+        }
+        (Sink::Email, _, None) => Err(ServiceError::ConfigNotSet),
+        (Sink::Telegram, _, _) | (Sink::Notifier, None, _) => Ok(()),
+    }
+}
+
+/// deliver every due notification of the notifiers and email subscribers once. Like the rows of
+/// a chat, the rows of a sink are delivered in order. A sink never waits for telegram and only
+/// waits for another sink up to the request timeout of the http client and mailer
+async fn deliver_sinks(
+    pool: &Pool<Sqlite>,
+    client: &Client,
+    mailer: &Option<Mailer>,
+) -> Result<usize, ServiceError> {
+    let now = Utc::now().naive_utc();
+    let heads = query!(
+        r#"
+        SELECT id AS "id!",
+            chat_id AS "chat_id!",
+            event AS "event!",
+            repo_id AS "repo_id!",
+            attempts AS "attempts!",
+            sink AS "sink!: Sink",
+            notifier_id
+        FROM main.outbox
+        WHERE sink <> ?
+        AND sent_at IS NULL
+        AND failed_at IS NULL
+        AND next_attempt_at <= ?
+        AND NOT EXISTS (
+            SELECT 1
+            FROM main.outbox AS earlier
+            WHERE earlier.repo_id = outbox.repo_id
+            AND earlier.sink = outbox.sink
+            AND earlier.notifier_id IS outbox.notifier_id
+            AND earlier.id < outbox.id
+            AND earlier.sent_at IS NULL
+            AND earlier.failed_at IS NULL
+        )
+        ORDER BY id
+        LIMIT ?
+        "#,
+        Sink::Telegram,
+        now,
+        BATCH_SIZE
+    )
+    .fetch_all(pool)
+    .await?;
+    let delivered = heads.len();
+
+    for head in heads {
+        let result = match serde_json::from_str::<JobEvent>(&head.event) {
+            Ok(event) => {
+                deliver_to_sink(
+                    pool,
+                    client,
+                    mailer,
+                    head.id,
+                    &head.repo_id,
+                    head.sink,
+                    head.notifier_id,
+                    &event,
+                )
+                .await
+            }
+            Err(e) => Err(e.into()),
+        };
+
+        settle(pool, &[head.id], head.chat_id, head.attempts, &result).await?;
+    }
+
+    Ok(delivered)
+}
+
+/// log a failed polling round, returning whether anything was delivered
+fn delivered_any(result: Result<usize, ServiceError>) -> bool {
+    match result {
+        Ok(count) => count > 0,
+        Err(e) => {
+            warn!("failed to deliver outbox notifications: {e}");
+            capture_warning("outbox failed to deliver notifications");
+            false
+        }
+    }
+}
+
+/// deliver telegram notifications of the outbox in the background. This task never returns and,
+/// like the other background tasks, is dropped along with the runtime. A notification that was
+/// sent right before shutdown but not yet marked will be sent again on the next start
+pub async fn run_outbox(pool: Pool<Sqlite>, bot: CiBot) {
+    loop {
+        if !delivered_any(deliver_pending(&pool, &bot).await) {
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+}
+
+/// deliver notifier and email notifications of the outbox in the background. It runs apart from
+/// `run_outbox` so that a slow sink never holds back telegram notifications
+pub async fn run_sink_outbox(pool: Pool<Sqlite>, client: Client, mailer: Option<Mailer>) {
+    loop {
+        if !delivered_any(deliver_sinks(&pool, &client, &mailer).await) {
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+}
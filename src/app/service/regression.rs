@@ -0,0 +1,126 @@
+use super::{bot::state::DeployStatus, dora::median};
+use crate::app::util::error::ServiceError;
+use sqlx::{query, SqliteConnection};
+
+/// number of recent successful jobs the rolling median is taken from
+const BASELINE_SIZE: i64 = 20;
+/// a baseline of fewer samples than this is not trusted. A branch without enough history
+/// falls back to the baseline of the whole repo
+const MIN_SAMPLES: usize = 5;
+
+/// median elapsed seconds of the latest successful jobs of a branch, or of the whole repo
+/// when the branch does not have enough history yet. This is both the baseline a finished job
+/// is compared against and the estimated duration of a running job
+pub async fn rolling_baseline(
+    connection: &mut SqliteConnection,
+    repo_id: &str,
+    branch: Option<&str>,
+    job_id: i32,
+) -> Result<Option<i64>, ServiceError> {
+    if branch.is_some() {
+        let samples = query!(
+            r#"
+            SELECT elapsed AS "elapsed!"
+            FROM main.jobs
+            WHERE repo_id = ?
+            AND branch = ?
+            AND id != ?
+            AND status = ?
+            AND elapsed IS NOT NULL
+            ORDER BY started_at DESC
+            LIMIT ?
+            "#,
+            repo_id,
+            branch,
+            job_id,
+            DeployStatus::Success,
+            BASELINE_SIZE
+        )
+        .fetch_all(&mut *connection)
+        .await?
+        .into_iter()
+        .map(|record| record.elapsed)
+        .collect::<Vec<i64>>();
+
+        if samples.len() >= MIN_SAMPLES {
+            return Ok(median(samples));
+        }
+    }
+
+    let samples = query!(
+        r#"
+        SELECT elapsed AS "elapsed!"
+        FROM main.jobs
+        WHERE repo_id = ?
+        AND id != ?
+        AND status = ?
+        AND elapsed IS NOT NULL
+        ORDER BY started_at DESC
+        LIMIT ?
+        "#,
+        repo_id,
+        job_id,
+        DeployStatus::Success,
+        BASELINE_SIZE
+    )
+    .fetch_all(&mut *connection)
+    .await?
+    .into_iter()
+    .map(|record| record.elapsed)
+    .collect::<Vec<i64>>();
+
+    Ok(if samples.len() >= MIN_SAMPLES {
+        median(samples)
+    } else {
+        None
+    })
+}
+
+/// how many times slower a finished job was than its baseline, if that crosses the repo's
+/// threshold. A repo without a threshold is never alerted
+pub async fn detect_slowdown(
+    connection: &mut SqliteConnection,
+    repo_id: &str,
+    branch: Option<&str>,
+    job_id: i32,
+    elapsed: i64,
+) -> Result<Option<f64>, ServiceError> {
+    let record = query!(
+        r#"
+        SELECT slowdown_threshold
+        FROM main.repos
+        WHERE id = ?
+        "#,
+        repo_id
+    )
+    .fetch_one(&mut *connection)
+    .await?;
+    let Some(threshold) = record.slowdown_threshold else {
+        return Ok(None);
+    };
+
+    Ok(
+        match rolling_baseline(connection, repo_id, branch, job_id).await? {
+            Some(baseline) if baseline > 0 => {
+                let ratio = elapsed as f64 / baseline as f64;
+
+                (ratio >= threshold).then_some(ratio)
+            }
+            _ => None,
+        },
+    )
+}
+
+/// parse the argument of `/slowdown` which is either a ratio greater than 1 or `off`
+pub fn parse_threshold(value: &str) -> Result<Option<f64>, ServiceError> {
+    match value.trim().trim_end_matches(['x', '×']) {
+        "off" => Ok(None),
+        ratio => match ratio.parse::<f64>() {
+            Ok(ratio) if ratio > 1.0 && ratio.is_finite() => Ok(Some(ratio)),
+            _ => Err(ServiceError::ValidateFailure {
+                field: "threshold",
+                reason: format!("{value} is not a ratio greater than 1 i.e. 2.5"),
+            }),
+        },
+    }
+}
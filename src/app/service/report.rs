@@ -0,0 +1,238 @@
+use super::bot::state::DeployStatus;
+use crate::app::util::error::ServiceError;
+use chrono::NaiveDateTime;
+use sqlx::{query, query_as, Pool, Sqlite};
+
+/// aggregated job counts of a repo over a period of time
+#[derive(sqlx::FromRow, Debug, Default)]
+pub struct JobSummary {
+    pub total: i64,
+    pub success: i64,
+    pub failure: i64,
+    pub cancelled: i64,
+    pub running: i64,
+}
+
+impl JobSummary {
+    /// percentage of successful jobs out of all finished jobs. Cancelled jobs are neither a pass
+    /// nor a failure so they are left out
+    pub fn pass_rate(&self) -> Option<f64> {
+        match self.success + self.failure {
+            0 => None,
+            finished => Some(self.success as f64 * 100.0 / finished as f64),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct FailedJob {
+    pub id: i64,
+    pub description: Option<String>,
+    pub triggered_by: Option<String>,
+    pub callback_url: Option<String>,
+}
+
+pub async fn summarize_repo(
+    pool: &Pool<Sqlite>,
+    repo_id: &str,
+    since: NaiveDateTime,
+) -> Result<JobSummary, ServiceError> {
+    Ok(query_as::<_, JobSummary>(
+        r#"
+        SELECT COUNT(*) AS total,
+            COALESCE(SUM(status = 'SUCCESS'), 0) AS success,
+            COALESCE(SUM(status = 'FAILURE'), 0) AS failure,
+            COALESCE(SUM(status = 'CANCELLED'), 0) AS cancelled,
+            COALESCE(SUM(status = 'RUNNING'), 0) AS running
+        FROM main.jobs
+        WHERE repo_id = ?
+        AND started_at >= ?
+        "#,
+    )
+    .bind(repo_id)
+    .bind(since)
+    .fetch_one(pool)
+    .await?)
+}
+
+pub async fn failed_jobs(
+    pool: &Pool<Sqlite>,
+    repo_id: &str,
+    since: NaiveDateTime,
+) -> Result<Vec<FailedJob>, ServiceError> {
+    Ok(query!(
+        r#"
+        SELECT id, description, triggered_by, callback_url
+        FROM main.jobs
+        WHERE repo_id = ?
+        AND status = ?
+        AND started_at >= ?
+        ORDER BY started_at
+        "#,
+        repo_id,
+        DeployStatus::Failure,
+        since
+    )
+    .map(|record| FailedJob {
+        id: record.id,
+        description: record.description,
+        triggered_by: record.triggered_by,
+        callback_url: record.callback_url,
+    })
+    .fetch_all(pool)
+    .await?)
+}
+
+/// aggregated job counts of every repo of a chat over a period of time
+pub async fn summarize_chat(
+    pool: &Pool<Sqlite>,
+    chat_id: i64,
+    since: NaiveDateTime,
+) -> Result<JobSummary, ServiceError> {
+    Ok(query_as::<_, JobSummary>(
+        r#"
+        SELECT COUNT(*) AS total,
+            COALESCE(SUM(jobs.status = 'SUCCESS'), 0) AS success,
+            COALESCE(SUM(jobs.status = 'FAILURE'), 0) AS failure,
+            COALESCE(SUM(jobs.status = 'CANCELLED'), 0) AS cancelled,
+            COALESCE(SUM(jobs.status = 'RUNNING'), 0) AS running
+        FROM main.jobs
+        JOIN repos ON jobs.repo_id = repos.id
+        WHERE repos.message_id = ?
+        AND repos.deleted_at IS NULL
+        AND jobs.started_at >= ?
+        "#,
+    )
+    .bind(chat_id)
+    .bind(since)
+    .fetch_one(pool)
+    .await?)
+}
+
+#[derive(Debug)]
+pub struct SlowJob {
+    pub id: i64,
+    pub repo_name: String,
+    pub elapsed: i64,
+}
+
+pub async fn slowest_jobs(
+    pool: &Pool<Sqlite>,
+    chat_id: i64,
+    since: NaiveDateTime,
+    limit: i64,
+) -> Result<Vec<SlowJob>, ServiceError> {
+    Ok(query!(
+        r#"
+        SELECT jobs.id AS "id!", 
+            repos.name AS "name!", 
+            jobs.elapsed AS "elapsed!"
+        FROM main.jobs
+        JOIN repos ON jobs.repo_id = repos.id
+        WHERE repos.message_id = ?
+        AND repos.deleted_at IS NULL
+        AND jobs.started_at >= ?
+        AND jobs.elapsed IS NOT NULL
+        ORDER BY jobs.elapsed DESC
+        LIMIT ?
+        "#,
+        chat_id,
+        since,
+        limit
+    )
+    .map(|record| SlowJob {
+        id: record.id,
+        repo_name: record.name,
+        elapsed: record.elapsed,
+    })
+    .fetch_all(pool)
+    .await?)
+}
+
+#[derive(Debug)]
+pub struct RedStreak {
+    pub repo_name: String,
+    pub length: usize,
+}
+
+/// the longest run of consecutive failed jobs of a single repo. Cancelled and running jobs
+/// neither extend nor break a streak
+pub async fn longest_red_streak(
+    pool: &Pool<Sqlite>,
+    chat_id: i64,
+    since: NaiveDateTime,
+) -> Result<Option<RedStreak>, ServiceError> {
+    let records = query!(
+        r#"
+        SELECT repos.name AS "name!", 
+            jobs.status AS "status!: DeployStatus"
+        FROM main.jobs
+        JOIN repos ON jobs.repo_id = repos.id
+        WHERE repos.message_id = ?
+        AND repos.deleted_at IS NULL
+        AND jobs.started_at >= ?
+        AND jobs.status IN (?, ?)
+        ORDER BY repos.name, jobs.started_at
+        "#,
+        chat_id,
+        since,
+        DeployStatus::Success,
+        DeployStatus::Failure
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut longest: Option<RedStreak> = None;
+    let mut current = (String::new(), 0);
+
+    for record in records {
+        if current.0 != record.name {
+            current = (record.name.clone(), 0);
+        }
+
+        if record.status == DeployStatus::Failure {
+            current.1 += 1;
+        } else {
+            current.1 = 0;
+        }
+
+        if current.1 > longest.as_ref().map_or(0, |streak| streak.length) {
+            longest = Some(RedStreak {
+                repo_name: current.0.clone(),
+                length: current.1,
+            });
+        }
+    }
+
+    Ok(longest)
+}
+
+/// people who triggered the most jobs along with their job count
+pub async fn top_committers(
+    pool: &Pool<Sqlite>,
+    chat_id: i64,
+    since: NaiveDateTime,
+    limit: i64,
+) -> Result<Vec<(String, i64)>, ServiceError> {
+    Ok(query!(
+        r#"
+        SELECT jobs.triggered_by AS "triggered_by!", 
+            COUNT(*) AS "count!: i64"
+        FROM main.jobs
+        JOIN repos ON jobs.repo_id = repos.id
+        WHERE repos.message_id = ?
+        AND repos.deleted_at IS NULL
+        AND jobs.started_at >= ?
+        AND jobs.triggered_by IS NOT NULL
+        GROUP BY jobs.triggered_by
+        ORDER BY COUNT(*) DESC, jobs.triggered_by
+        LIMIT ?
+        "#,
+        chat_id,
+        since,
+        limit
+    )
+    .map(|record| (record.triggered_by, record.count))
+    .fetch_all(pool)
+    .await?)
+}
@@ -0,0 +1,286 @@
+//! Per repo roles of chat members. A role granted with `/grant` takes precedence, anyone else
+//! gets the role of their chat membership: the creator of a group is an owner, its
+//! administrators are maintainers and everyone else is a viewer.
+use super::{bot::state::RepoCommand, escalation::display_name};
+use crate::app::{config::bot::CiBot, util::error::ServiceError};
+use serde::{Deserialize, Serialize};
+use sqlx::{query, Pool, Sqlite};
+use std::fmt::Display;
+use teloxide::{
+    requests::Requester,
+    types::{Chat, Message, User, UserId},
+};
+
+/// roles are ordered by privilege so that a role satisfies any requirement below it
+#[derive(
+    Deserialize, Serialize, sqlx::Type, PartialEq, Eq, PartialOrd, Ord, Debug, Clone, Copy,
+)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "role", rename_all = "UPPERCASE")]
+pub enum Role {
+    Viewer,
+    Maintainer,
+    Owner,
+}
+
+impl Display for Role {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Viewer => "viewer",
+                Self::Maintainer => "maintainer",
+                Self::Owner => "owner",
+            }
+        )
+    }
+}
+
+impl TryFrom<&str> for Role {
+    type Error = ServiceError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value.trim().to_lowercase().as_str() {
+            "viewer" => Ok(Self::Viewer),
+            "maintainer" => Ok(Self::Maintainer),
+            "owner" => Ok(Self::Owner),
+            _ => Err(ServiceError::TryFrom {
+                field: "role",
+                from: value.to_string(),
+                into: "Role",
+                expect: "owner, maintainer, or viewer",
+            }),
+        }
+    }
+}
+
+/// the role needed to run a repo command. Commands that change or remove the repo, or reveal
+/// its secrets, need more than viewing
+pub fn required_role(cmd: &RepoCommand) -> Role {
+    match cmd {
+        RepoCommand::Delete | RepoCommand::Grant(_) | RepoCommand::Revoke(_) => Role::Owner,
+        RepoCommand::GetInfo
+        | RepoCommand::Rename(_)
+        | RepoCommand::AddNotifier(_)
+        | RepoCommand::Notifiers
+        | RepoCommand::RemoveNotifier(_)
+        | RepoCommand::AddEmail(_)
+        | RepoCommand::RemoveEmail(_)
+        | RepoCommand::Audit(_) => Role::Maintainer,
+        RepoCommand::Slowdown(args)
+        | RepoCommand::Oncall(args)
+        | RepoCommand::Escalation(args)
+        | RepoCommand::Codeowners(args)
+        | RepoCommand::Template(args)
+            if !args.trim().is_empty() =>
+        {
+            Role::Maintainer
+        }
+        _ => Role::Viewer,
+    }
+}
+
+/// the role of the sender of a message on a repo
+pub async fn sender_role(
+    bot: &CiBot,
+    pool: &Pool<Sqlite>,
+    repo_id: &str,
+    msg: &Message,
+) -> Result<Role, ServiceError> {
+    // an anonymous administrator sends on behalf of the group itself
+    if msg.sender_chat().map(|chat| chat.id) == Some(msg.chat.id) {
+        return Ok(Role::Maintainer);
+    }
+
+    match msg.from() {
+        Some(user) => member_role(bot, pool, repo_id, &msg.chat, user).await,
+        None => Ok(Role::Viewer),
+    }
+}
+
+/// the role of the sender of a message on the whole chat, which is their lowest role on the
+/// repos of the chat. A chat without repos has nothing to protect so anyone owns it
+pub async fn sender_chat_role(
+    bot: &CiBot,
+    pool: &Pool<Sqlite>,
+    msg: &Message,
+) -> Result<Role, ServiceError> {
+    let records = query!(
+        r#"
+        SELECT id
+        FROM main.repos
+        WHERE message_id = ?
+        AND deleted_at IS NULL
+        "#,
+        msg.chat.id.0
+    )
+    .fetch_all(pool)
+    .await?;
+    let mut role = Role::Owner;
+
+    for record in records {
+        role = role.min(sender_role(bot, pool, &record.id, msg).await?);
+    }
+
+    Ok(role)
+}
+
+/// the role of a user on a repo of a chat. Nobody else can use a private chat so its user owns
+/// every repo of it
+pub async fn member_role(
+    bot: &CiBot,
+    pool: &Pool<Sqlite>,
+    repo_id: &str,
+    chat: &Chat,
+    user: &User,
+) -> Result<Role, ServiceError> {
+    if chat.is_private() {
+        return Ok(Role::Owner);
+    }
+
+    let user_id = user.id.0 as i64;
+    let record = query!(
+        r#"
+        SELECT role AS "role: Role"
+        FROM main.repo_roles
+        WHERE repo_id = ?
+        AND user_id = ?
+        "#,
+        repo_id,
+        user_id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    if let Some(record) = record {
+        return Ok(record.role);
+    }
+
+    let member = bot.get_chat_member(chat.id, user.id).await?;
+
+    Ok(if member.is_owner() {
+        Role::Owner
+    } else if member.is_administrator() {
+        Role::Maintainer
+    } else {
+        Role::Viewer
+    })
+}
+
+/// the user a `/grant` or `/revoke` is about, either the sender of the replied message or a
+/// member of the chat by user id
+pub async fn target_user(
+    bot: &CiBot,
+    msg: &Message,
+    user_id: Option<&str>,
+) -> Result<User, ServiceError> {
+    match (
+        user_id,
+        msg.reply_to_message().and_then(|reply| reply.from()),
+    ) {
+        (Some(user_id), _) => {
+            let user_id = user_id
+                .parse::<u64>()
+                .map_err(|_| ServiceError::ValidateFailure {
+                    field: "user_id",
+                    reason: format!("{user_id} is not a telegram user id"),
+                })?;
+
+            Ok(bot
+                .get_chat_member(msg.chat.id, UserId(user_id))
+                .await?
+                .user)
+        }
+        (None, Some(user)) => Ok(user.clone()),
+        (None, None) => Err(ServiceError::ValidateFailure {
+            field: "user",
+            reason: "reply to a message of the user or give their user id".to_string(),
+        }),
+    }
+}
+
+/// parse the argument of `/grant` which is in the form of `<owner|maintainer|viewer> [user_id]`
+pub fn parse_grant(args: &str) -> Result<(Role, Option<&str>), ServiceError> {
+    let mut args = args.split_whitespace();
+    let role = Role::try_from(args.next().unwrap_or_default())?;
+
+    match (args.next(), args.next()) {
+        (user_id, None) => Ok((role, user_id)),
+        _ => Err(ServiceError::ValidateFailure {
+            field: "grant",
+            reason: "expect <owner|maintainer|viewer> [user_id]".to_string(),
+        }),
+    }
+}
+
+pub async fn grant(
+    pool: &Pool<Sqlite>,
+    repo_id: &str,
+    user: &User,
+    role: Role,
+) -> Result<(), ServiceError> {
+    let user_id = user.id.0 as i64;
+    let name = display_name(user);
+
+    query!(
+        r#"
+        INSERT INTO main.repo_roles
+        (repo_id, user_id, name, role)
+        VALUES (?, ?, ?, ?)
+        ON CONFLICT (repo_id, user_id)
+        DO UPDATE SET name = excluded.name, role = excluded.role
+        "#,
+        repo_id,
+        user_id,
+        name,
+        role
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// remove a granted role so that the user falls back to the role of their chat membership.
+/// Returns whether the user had a granted role
+pub async fn revoke(pool: &Pool<Sqlite>, repo_id: &str, user: &User) -> Result<bool, ServiceError> {
+    let user_id = user.id.0 as i64;
+    let result = query!(
+        r#"
+        DELETE FROM main.repo_roles
+        WHERE repo_id = ?
+        AND user_id = ?
+        "#,
+        repo_id,
+        user_id
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// roles granted on a repo, the most privileged first
+pub async fn granted_roles(
+    pool: &Pool<Sqlite>,
+    repo_id: &str,
+) -> Result<Vec<(String, Role)>, ServiceError> {
+    let mut roles = query!(
+        r#"
+        SELECT name, role AS "role: Role"
+        FROM main.repo_roles
+        WHERE repo_id = ?
+        ORDER BY name
+        "#,
+        repo_id
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|record| (record.name, record.role))
+    .collect::<Vec<(String, Role)>>();
+    roles.sort_by(|(_, a), (_, b)| b.cmp(a));
+
+    Ok(roles)
+}
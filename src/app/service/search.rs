@@ -0,0 +1,340 @@
+//! Searches of the job history by status, triggerer, branch, dates and description. A search is
+//! kept in `main.job_searches` so that the buttons paging through its results only carry its id.
+use super::{bot::state::DeployStatus, format::JobListing, timezone::start_of_date};
+use crate::app::util::error::ServiceError;
+use chrono::{Duration, NaiveDate};
+use chrono_tz::Tz;
+use sqlx::{query, Pool, QueryBuilder, Sqlite};
+
+/// number of searches kept per chat, the buttons of older searches stop working
+const KEPT_SEARCHES: i64 = 20;
+/// names of the filters of a search, written as `<name>:<value>`
+pub const FILTERS: [&str; 5] = ["status", "by", "branch", "since", "until"];
+
+/// what the jobs of a search are limited to. Dates are days in the timezone of the chat
+#[derive(Default, PartialEq, Eq, Debug)]
+pub struct JobFilter {
+    pub status: Option<DeployStatus>,
+    pub triggered_by: Option<String>,
+    pub branch: Option<String>,
+    pub since: Option<NaiveDate>,
+    /// the last day of the search, inclusive
+    pub until: Option<NaiveDate>,
+    /// text the description of a job contains
+    pub text: Option<String>,
+}
+
+fn parse_date(value: &str) -> Result<NaiveDate, ServiceError> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d").map_err(|_| ServiceError::ValidateFailure {
+        field: "date",
+        reason: format!("{value} is not a date, expect YYYY-MM-DD"),
+    })
+}
+
+/// the day after the last day of a search, which the jobs of the search started before
+fn day_after(until: NaiveDate) -> Result<NaiveDate, ServiceError> {
+    until
+        .checked_add_signed(Duration::days(1))
+        .ok_or_else(|| ServiceError::ValidateFailure {
+            field: "until",
+            reason: format!("{until} is too far in the future"),
+        })
+}
+
+impl JobFilter {
+    /// parse filters in the form of `status:failure by:alice branch:main since:2022-12-01
+    /// until:2022-12-31`. Every other word is text to look for in descriptions
+    pub fn parse(args: &str) -> Result<Self, ServiceError> {
+        let mut filter = Self::default();
+        let mut words = vec![];
+
+        for word in args.split_whitespace() {
+            let Some((name, value)) = word
+                .split_once(':')
+                .filter(|(name, _)| FILTERS.contains(name))
+            else {
+                words.push(word);
+                continue;
+            };
+
+            if value.is_empty() {
+                return Err(ServiceError::ValidateFailure {
+                    field: "filter",
+                    reason: format!("{name}: has no value"),
+                });
+            }
+
+            match name {
+                "status" => {
+                    filter.status = Some(DeployStatus::try_from(value.to_uppercase().as_str())?)
+                }
+                "by" => filter.triggered_by = Some(value.to_string()),
+                "branch" => filter.branch = Some(value.to_string()),
+                "since" => filter.since = Some(parse_date(value)?),
+                _ => {
+                    let until = parse_date(value)?;
+                    day_after(until)?;
+                    filter.until = Some(until);
+                }
+            }
+        }
+
+        if let (Some(since), Some(until)) = (filter.since, filter.until) {
+            if since > until {
+                return Err(ServiceError::ValidateFailure {
+                    field: "until",
+                    reason: format!("{until} is before {since}"),
+                });
+            }
+        }
+
+        if !words.is_empty() {
+            filter.text = Some(words.join(" "));
+        }
+
+        Ok(filter)
+    }
+}
+
+/// parse the argument of `/history` which only has filters
+pub fn parse_history(args: &str) -> Result<JobFilter, ServiceError> {
+    let filter = JobFilter::parse(args)?;
+
+    match &filter.text {
+        Some(text) => Err(ServiceError::ValidateFailure {
+            field: "filter",
+            reason: format!(
+                "unknown filter {text}, expect {} or use /search to look for text",
+                FILTERS.map(|name| format!("{name}:")).join(", ")
+            ),
+        }),
+        None => Ok(filter),
+    }
+}
+
+/// parse the argument of `/search` which is text to look for followed by optional filters
+pub fn parse_search(args: &str) -> Result<JobFilter, ServiceError> {
+    let filter = JobFilter::parse(args)?;
+
+    match filter.text {
+        Some(_) => Ok(filter),
+        None => Err(ServiceError::ValidateFailure {
+            field: "text",
+            reason: "expect text to look for in the description of jobs".to_string(),
+        }),
+    }
+}
+
+/// a search of a chat over one of its repos, or all of them when there is no repo
+#[derive(sqlx::FromRow, Debug)]
+pub struct JobSearch {
+    pub id: i64,
+    pub chat_id: i64,
+    pub repo_id: Option<String>,
+    pub query: String,
+}
+
+impl JobSearch {
+    /// the filters of the search. Searches are checked before they are kept, so this only fails
+    /// on searches kept before a change of what filters are accepted
+    pub fn filter(&self) -> Result<JobFilter, ServiceError> {
+        JobFilter::parse(&self.query)
+    }
+}
+
+/// keep a search of a chat, forgetting the oldest searches of the chat past `KEPT_SEARCHES`
+pub async fn save_search(
+    pool: &Pool<Sqlite>,
+    chat_id: i64,
+    repo_id: Option<&str>,
+    search: &str,
+) -> Result<JobSearch, ServiceError> {
+    let mut transaction = pool.begin().await?;
+    let record = query!(
+        r#"
+        INSERT INTO main.job_searches
+        (chat_id, repo_id, query)
+        VALUES (?, ?, ?)
+        RETURNING id AS "id!"
+        "#,
+        chat_id,
+        repo_id,
+        search
+    )
+    .fetch_one(&mut transaction)
+    .await?;
+    query!(
+        r#"
+        DELETE FROM main.job_searches
+        WHERE chat_id = ?
+        AND id NOT IN (
+            SELECT id
+            FROM main.job_searches
+            WHERE chat_id = ?
+            ORDER BY id DESC
+            LIMIT ?
+        )
+        "#,
+        chat_id,
+        chat_id,
+        KEPT_SEARCHES
+    )
+    .execute(&mut transaction)
+    .await?;
+    transaction.commit().await?;
+
+    Ok(JobSearch {
+        id: record.id,
+        chat_id,
+        repo_id: repo_id.map(str::to_string),
+        query: search.to_string(),
+    })
+}
+
+/// a kept search of a chat
+pub async fn find_search(
+    pool: &Pool<Sqlite>,
+    id: i64,
+    chat_id: i64,
+) -> Result<Option<JobSearch>, ServiceError> {
+    Ok(query!(
+        r#"
+        SELECT id AS "id!", chat_id, repo_id, query
+        FROM main.job_searches
+        WHERE id = ?
+        AND chat_id = ?
+        "#,
+        id,
+        chat_id
+    )
+    .fetch_optional(pool)
+    .await?
+    .map(|record| JobSearch {
+        id: record.id,
+        chat_id: record.chat_id,
+        repo_id: record.repo_id,
+        query: record.query,
+    }))
+}
+
+/// `%` and `_` in text looked up with `LIKE` match themselves
+fn escape_like(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+/// append the conditions of a search to a query of `main.jobs` joined with `main.repos`. Only
+/// the filters of the search are compared so that the indexes on `main.jobs` can be used
+fn push_conditions(
+    builder: &mut QueryBuilder<Sqlite>,
+    search: &JobSearch,
+    filter: &JobFilter,
+    timezone: Tz,
+) -> Result<(), ServiceError> {
+    builder
+        .push(" WHERE repos.message_id = ")
+        .push_bind(search.chat_id)
+        .push(" AND repos.deleted_at IS NULL");
+
+    if let Some(repo_id) = &search.repo_id {
+        builder.push(" AND repos.id = ").push_bind(repo_id.clone());
+    }
+
+    if let Some(status) = filter.status {
+        builder.push(" AND jobs.status = ").push_bind(status);
+    }
+
+    if let Some(triggered_by) = &filter.triggered_by {
+        builder
+            .push(" AND jobs.triggered_by = ")
+            .push_bind(triggered_by.clone());
+    }
+
+    if let Some(branch) = &filter.branch {
+        builder
+            .push(" AND jobs.branch = ")
+            .push_bind(branch.clone());
+    }
+
+    if let Some(since) = filter.since {
+        let since = start_of_date(timezone, since).ok_or(ServiceError::ChronoDatetime)?;
+        builder.push(" AND jobs.started_at >= ").push_bind(since);
+    }
+
+    if let Some(until) = filter.until {
+        let until =
+            start_of_date(timezone, day_after(until)?).ok_or(ServiceError::ChronoDatetime)?;
+        builder.push(" AND jobs.started_at < ").push_bind(until);
+    }
+
+    if let Some(text) = &filter.text {
+        builder
+            .push(" AND jobs.description LIKE ")
+            .push_bind(format!("%{}%", escape_like(text)))
+            .push(r" ESCAPE '\'");
+    }
+
+    Ok(())
+}
+
+/// the number of jobs a search matches
+pub async fn count_search_jobs(
+    pool: &Pool<Sqlite>,
+    search: &JobSearch,
+    filter: &JobFilter,
+    timezone: Tz,
+) -> Result<i64, ServiceError> {
+    let mut builder = QueryBuilder::new(
+        r#"
+        SELECT COUNT(*)
+        FROM main.jobs
+        JOIN main.repos ON jobs.repo_id = repos.id
+        "#,
+    );
+    push_conditions(&mut builder, search, filter, timezone)?;
+    let (total,) = builder.build_query_as::<(i64,)>().fetch_one(pool).await?;
+
+    Ok(total)
+}
+
+/// a page of the jobs a search matches, the latest first
+pub async fn search_jobs(
+    pool: &Pool<Sqlite>,
+    search: &JobSearch,
+    filter: &JobFilter,
+    timezone: Tz,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<JobListing>, ServiceError> {
+    // the repo of every job is the same in a search of a single repo
+    let repo_name = match search.repo_id {
+        Some(_) => "NULL",
+        None => "repos.name",
+    };
+    let mut builder = QueryBuilder::new(format!(
+        r#"
+        SELECT jobs.id,
+            jobs.status,
+            {repo_name} AS repo_name,
+            jobs.branch,
+            jobs.triggered_by,
+            jobs.callback_url AS url,
+            jobs.started_at,
+            jobs.elapsed
+        FROM main.jobs
+        JOIN main.repos ON jobs.repo_id = repos.id
+        "#
+    ));
+    push_conditions(&mut builder, search, filter, timezone)?;
+    builder
+        .push(" ORDER BY jobs.started_at DESC, jobs.id DESC LIMIT ")
+        .push_bind(limit)
+        .push(" OFFSET ")
+        .push_bind(offset);
+
+    Ok(builder
+        .build_query_as::<JobListing>()
+        .fetch_all(pool)
+        .await?)
+}
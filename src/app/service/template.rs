@@ -0,0 +1,455 @@
+//! Per repo templates of job notifications. A template is text with `{{variable}}` placeholders
+//! and nothing else, so it can only rearrange what the bot already knows about a job. Values are
+//! escaped like any other segment of a message.
+use super::{
+    bot::state::DeployStatus,
+    i18n::Language,
+    job::{format_duration, format_eta},
+    message::MessageBuilder,
+    notifier::JobEvent,
+};
+use crate::app::util::error::ServiceError;
+use chrono::Duration;
+use serde::{Deserialize, Serialize};
+use sqlx::{query, Pool, Sqlite, SqliteConnection};
+use std::fmt::Display;
+
+/// the longest template a repo can set
+const MAX_TEMPLATE_LENGTH: usize = 1024;
+/// names of the variables a template can use, in the order `/template` lists them
+pub const VARIABLES: [&str; 10] = [
+    "repo",
+    "status",
+    "elapsed",
+    "by",
+    "url",
+    "branch",
+    "sha",
+    "description",
+    "flaky",
+    "eta",
+];
+/// variables that have a value for every job, so that a template using one is never blank
+const ALWAYS_SET: [&str; 2] = ["repo", "status"];
+
+/// the notification a template is used for
+#[derive(Deserialize, Serialize, sqlx::Type, PartialEq, Eq, Debug, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "kind", rename_all = "UPPERCASE")]
+pub enum TemplateKind {
+    Created,
+    Success,
+    Failure,
+    Cancelled,
+}
+
+impl TemplateKind {
+    /// the status of the job a notification of this kind is about
+    pub fn status(&self) -> DeployStatus {
+        match self {
+            Self::Created => DeployStatus::Running,
+            Self::Success => DeployStatus::Success,
+            Self::Failure => DeployStatus::Failure,
+            Self::Cancelled => DeployStatus::Cancelled,
+        }
+    }
+}
+
+impl From<DeployStatus> for TemplateKind {
+    fn from(status: DeployStatus) -> Self {
+        match status {
+            DeployStatus::Running => Self::Created,
+            DeployStatus::Success => Self::Success,
+            DeployStatus::Failure => Self::Failure,
+            DeployStatus::Cancelled => Self::Cancelled,
+        }
+    }
+}
+
+impl Display for TemplateKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Created => "created",
+                Self::Success => "success",
+                Self::Failure => "failure",
+                Self::Cancelled => "cancelled",
+            }
+        )
+    }
+}
+
+impl TryFrom<&str> for TemplateKind {
+    type Error = ServiceError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value.to_lowercase().as_str() {
+            "created" => Ok(Self::Created),
+            "success" => Ok(Self::Success),
+            "failure" => Ok(Self::Failure),
+            "cancelled" => Ok(Self::Cancelled),
+            _ => Err(ServiceError::TryFrom {
+                field: "kind",
+                from: value.to_string(),
+                into: "TemplateKind",
+                expect: "created, success, failure, or cancelled",
+            }),
+        }
+    }
+}
+
+/// what a template is filled in with
+pub struct TemplateContext {
+    pub repo: String,
+    pub status: DeployStatus,
+    pub elapsed: Option<String>,
+    pub by: Option<String>,
+    pub url: Option<String>,
+    pub branch: Option<String>,
+    pub sha: Option<String>,
+    pub description: Option<String>,
+    /// whether the failure looks flaky, shown as 🎲
+    pub flaky: bool,
+    pub eta: Option<String>,
+}
+
+impl From<&JobEvent> for TemplateContext {
+    fn from(event: &JobEvent) -> Self {
+        Self {
+            repo: event.repo_name.clone(),
+            status: event.status,
+            elapsed: event.elapsed(),
+            by: event.by_name.clone(),
+            url: event.url.clone(),
+            branch: event.branch.clone(),
+            sha: event.commit_sha.clone(),
+            description: event.description.clone(),
+            flaky: event.flaky,
+            eta: event.eta.map(|eta| format_eta(event.language, eta)),
+        }
+    }
+}
+
+enum Piece {
+    Literal(String),
+    Variable(&'static str),
+}
+
+pub struct Template {
+    pieces: Vec<Piece>,
+}
+
+impl Template {
+    /// parse a template, rejecting unknown variables, unclosed placeholders and templates that
+    /// can come out blank
+    pub fn parse(text: &str) -> Result<Self, ServiceError> {
+        if text.trim().is_empty() || text.chars().count() > MAX_TEMPLATE_LENGTH {
+            return Err(ServiceError::ValidateFailure {
+                field: "template",
+                reason: format!("expect between 1 and {MAX_TEMPLATE_LENGTH} characters"),
+            });
+        }
+
+        let mut pieces = vec![];
+        let mut rest = text;
+
+        while let Some(start) = rest.find("{{") {
+            if start > 0 {
+                pieces.push(Piece::Literal(rest[..start].to_string()));
+            }
+
+            let Some(end) = rest[start..].find("}}") else {
+                return Err(ServiceError::ValidateFailure {
+                    field: "template",
+                    reason: "a {{ is never closed with }}".to_string(),
+                });
+            };
+            let name = rest[start + 2..start + end].trim();
+            let Some(variable) = VARIABLES.iter().find(|variable| **variable == name) else {
+                return Err(ServiceError::ValidateFailure {
+                    field: "template",
+                    reason: format!(
+                        "unknown variable {name}, expect one of {}",
+                        VARIABLES.join(", ")
+                    ),
+                });
+            };
+
+            pieces.push(Piece::Variable(variable));
+            rest = &rest[start + end + 2..];
+        }
+
+        if !rest.is_empty() {
+            pieces.push(Piece::Literal(rest.to_string()));
+        }
+
+        if !pieces.iter().any(|piece| match piece {
+            Piece::Literal(text) => !text.trim().is_empty(),
+            Piece::Variable(variable) => ALWAYS_SET.contains(variable),
+        }) {
+            return Err(ServiceError::ValidateFailure {
+                field: "template",
+                reason: format!(
+                    "expect some text or one of {} so that a notification is never blank",
+                    ALWAYS_SET
+                        .map(|variable| format!("{{{{{variable}}}}}"))
+                        .join(", ")
+                ),
+            });
+        }
+
+        Ok(Self { pieces })
+    }
+
+    /// fill in the template. A variable without a value is left empty and the url becomes a
+    /// link. A template that comes out blank gives nothing so that the default wording is used
+    pub fn render(&self, context: &TemplateContext) -> Option<MessageBuilder> {
+        let message =
+            self.pieces
+                .iter()
+                .fold(MessageBuilder::new(), |message, piece| match piece {
+                    Piece::Literal(text) => message.text(text),
+                    Piece::Variable("url") => match &context.url {
+                        Some(url) => message.link(url, url),
+                        None => message,
+                    },
+                    Piece::Variable(variable) => {
+                        let value = match *variable {
+                            "repo" => Some(context.repo.clone()),
+                            "status" => Some(context.status.to_string().to_lowercase()),
+                            "elapsed" => context.elapsed.clone(),
+                            "by" => context.by.clone(),
+                            "branch" => context.branch.clone(),
+                            "sha" => context.sha.clone(),
+                            "description" => context.description.clone(),
+                            "flaky" => context.flaky.then(|| "🎲".to_string()),
+                            "eta" => context.eta.clone(),
+                            _ => None,
+                        };
+
+                        message.text(value.unwrap_or_default())
+                    }
+                });
+
+        (!message.plain().trim().is_empty()).then_some(message)
+    }
+}
+
+/// what `/template` is asked to do
+pub enum TemplateCommand {
+    List,
+    Preview(TemplateKind),
+    Set(TemplateKind, String),
+    Reset(TemplateKind),
+}
+
+impl TemplateCommand {
+    /// parse the argument of `/template` which is in the form of `<kind> [template|reset]`. The
+    /// template is everything after the kind, including any following lines
+    pub fn parse(args: &str) -> Result<Self, ServiceError> {
+        let args = args.trim();
+
+        if args.is_empty() {
+            return Ok(Self::List);
+        }
+
+        let (kind, template) = args
+            .split_once(char::is_whitespace)
+            .map_or((args, ""), |(kind, template)| (kind, template.trim()));
+        let kind = TemplateKind::try_from(kind)?;
+
+        Ok(match template {
+            "" => Self::Preview(kind),
+            "reset" => Self::Reset(kind),
+            template => {
+                Template::parse(template)?;
+                Self::Set(kind, template.to_string())
+            }
+        })
+    }
+}
+
+/// the template of a repo for a kind of notification, if the repo set one
+pub async fn find_template(
+    connection: &mut SqliteConnection,
+    repo_id: &str,
+    kind: TemplateKind,
+) -> Result<Option<String>, ServiceError> {
+    Ok(query!(
+        r#"
+        SELECT template
+        FROM main.repo_templates
+        WHERE repo_id = ?
+        AND kind = ?
+        "#,
+        repo_id,
+        kind
+    )
+    .fetch_optional(connection)
+    .await?
+    .map(|record| record.template))
+}
+
+pub async fn repo_templates(
+    pool: &Pool<Sqlite>,
+    repo_id: &str,
+) -> Result<Vec<(TemplateKind, String)>, ServiceError> {
+    Ok(query!(
+        r#"
+        SELECT kind AS "kind!: TemplateKind", template
+        FROM main.repo_templates
+        WHERE repo_id = ?
+        "#,
+        repo_id
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|record| (record.kind, record.template))
+    .collect())
+}
+
+/// set the template of a repo for a kind of notification, returning the one it replaced
+pub async fn set_template(
+    connection: &mut SqliteConnection,
+    repo_id: &str,
+    kind: TemplateKind,
+    template: Option<&str>,
+) -> Result<Option<String>, ServiceError> {
+    let previous = find_template(&mut *connection, repo_id, kind).await?;
+
+    match template {
+        Some(template) => {
+            query!(
+                r#"
+                INSERT INTO main.repo_templates
+                (repo_id, kind, template)
+                VALUES (?, ?, ?)
+                ON CONFLICT (repo_id, kind)
+                DO UPDATE SET template = excluded.template
+                "#,
+                repo_id,
+                kind,
+                template
+            )
+            .execute(&mut *connection)
+            .await?;
+        }
+        None => {
+            query!(
+                r#"
+                DELETE FROM main.repo_templates
+                WHERE repo_id = ?
+                AND kind = ?
+                "#,
+                repo_id,
+                kind
+            )
+            .execute(&mut *connection)
+            .await?;
+        }
+    }
+
+    Ok(previous)
+}
+
+/// the context of the latest job of a repo as if it had the status of the given kind, for
+/// `/template` to preview a template against
+pub async fn latest_context(
+    language: Language,
+    pool: &Pool<Sqlite>,
+    repo_id: &str,
+    kind: TemplateKind,
+) -> Result<Option<TemplateContext>, ServiceError> {
+    Ok(query!(
+        r#"
+        SELECT repos.name,
+            jobs.elapsed,
+            jobs.triggered_by,
+            jobs.callback_url,
+            jobs.branch,
+            jobs.commit_sha,
+            jobs.description
+        FROM main.jobs
+        JOIN repos ON jobs.repo_id = repos.id
+        WHERE repos.id = ?
+        ORDER BY jobs.started_at DESC
+        LIMIT 1
+        "#,
+        repo_id
+    )
+    .fetch_optional(pool)
+    .await?
+    .map(|record| TemplateContext {
+        repo: record.name,
+        status: kind.status(),
+        elapsed: record
+            .elapsed
+            .map(|elapsed| format_duration(language, Duration::seconds(elapsed))),
+        by: record.triggered_by,
+        url: record.callback_url,
+        branch: record.branch,
+        sha: record.commit_sha,
+        description: record.description,
+        flaky: false,
+        // the latest job took about as long as the next one is estimated to
+        eta: record
+            .elapsed
+            .filter(|_| kind == TemplateKind::Created)
+            .map(|elapsed| format_eta(language, elapsed)),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context() -> TemplateContext {
+        TemplateContext {
+            repo: "web".to_string(),
+            status: DeployStatus::Failure,
+            elapsed: None,
+            by: None,
+            url: None,
+            branch: None,
+            sha: None,
+            description: None,
+            flaky: true,
+            eta: Some("~7 min".to_string()),
+        }
+    }
+
+    fn render(template: &str) -> Option<String> {
+        Template::parse(template)
+            .unwrap()
+            .render(&context())
+            .map(|message| message.plain())
+    }
+
+    #[test]
+    fn parse_rejects_templates_that_can_come_out_blank() {
+        assert!(Template::parse("{{sha}}").is_err());
+        assert!(Template::parse("{{by}} {{branch}}").is_err());
+        assert!(Template::parse("{{sha}} broke").is_ok());
+        assert!(Template::parse("{{repo}}").is_ok());
+    }
+
+    #[test]
+    fn render_fills_in_flaky_and_eta() {
+        assert_eq!(
+            render("{{flaky}} {{repo}} {{status}} ETA {{eta}}").as_deref(),
+            Some("🎲 web failure ETA ~7 min")
+        );
+    }
+
+    #[test]
+    fn render_gives_nothing_for_a_blank_message() {
+        let template = Template {
+            pieces: vec![Piece::Literal(" ".to_string()), Piece::Variable("sha")],
+        };
+
+        assert!(template.render(&context()).is_none());
+    }
+}
@@ -0,0 +1,88 @@
+//! The timezone of each chat. Times are stored in UTC and only converted to the timezone of a
+//! chat when a day starts or a time is shown.
+use crate::app::util::error::ServiceError;
+use chrono::{Duration, NaiveDate, NaiveDateTime, TimeZone};
+use chrono_tz::Tz;
+use sqlx::{query, Executor, Sqlite};
+
+pub fn parse_timezone(value: &str) -> Result<Tz, ServiceError> {
+    value
+        .parse::<Tz>()
+        .map_err(|reason| ServiceError::ValidateFailure {
+            field: "timezone",
+            reason,
+        })
+}
+
+/// the timezone of a chat, UTC unless the chat set another one
+pub async fn chat_timezone<'c, E>(executor: E, chat_id: i64) -> Result<Tz, ServiceError>
+where
+    E: Executor<'c, Database = Sqlite>,
+{
+    let record = query!(
+        r#"
+        SELECT timezone
+        FROM main.chat_settings
+        WHERE chat_id = ?
+        "#,
+        chat_id
+    )
+    .fetch_optional(executor)
+    .await?;
+
+    record.map_or(Ok(Tz::UTC), |record| parse_timezone(&record.timezone))
+}
+
+/// set the timezone of a chat
+pub async fn set_chat_timezone<'c, E>(
+    executor: E,
+    chat_id: i64,
+    timezone: Tz,
+) -> Result<(), ServiceError>
+where
+    E: Executor<'c, Database = Sqlite>,
+{
+    let timezone = timezone.name();
+    query!(
+        r#"
+        INSERT INTO main.chat_settings
+        (chat_id, timezone)
+        VALUES (?, ?)
+        ON CONFLICT (chat_id)
+        DO UPDATE SET timezone = excluded.timezone
+        "#,
+        chat_id,
+        timezone
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+/// the UTC time at which the day of `now` began in a timezone
+pub fn start_of_day(timezone: Tz, now: NaiveDateTime) -> Option<NaiveDateTime> {
+    start_of_date(
+        timezone,
+        timezone.from_utc_datetime(&now).naive_local().date(),
+    )
+}
+
+/// the UTC time at which a date begins in a timezone
+pub fn start_of_date(timezone: Tz, date: NaiveDate) -> Option<NaiveDateTime> {
+    let midnight = date.and_hms_opt(0, 0, 0)?;
+
+    // a timezone that moves its clocks forward at midnight skips the first hour of that day
+    [midnight, midnight + Duration::hours(1)]
+        .into_iter()
+        .find_map(|time| timezone.from_local_datetime(&time).earliest())
+        .map(|start| start.naive_utc())
+}
+
+/// a UTC time as shown to a chat i.e. `2022-12-31 09:00 +07`
+pub fn format_time(timezone: Tz, time: NaiveDateTime) -> String {
+    timezone
+        .from_utc_datetime(&time)
+        .format("%Y-%m-%d %H:%M %Z")
+        .to_string()
+}
@@ -0,0 +1,238 @@
+//! Soft deletion of repos. A deleted repo stays in the database with its job history for
+//! `DELETE_RETENTION_HOURS` so that the chat can bring it back with `/undo`, after which it is
+//! purged for good in the background. Its name is kept as `<id>:<name>` in the meantime so that a
+//! new repo can take the name.
+use super::i18n::Language;
+use crate::{
+    app::util::{error::ServiceError, sentry::capture_warning},
+    DELETE_RETENTION_HOURS,
+};
+use chrono::{Duration, Utc};
+use sqlx::{query, Pool, Sqlite};
+use teloxide::types::{InlineKeyboardButton, InlineKeyboardMarkup};
+use tracing::{info, warn};
+
+/// how often expired repos are purged
+const PURGE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60 * 60);
+/// prefix of the callback data of a confirmation button
+const CONFIRM_PREFIX: &str = "confirm:";
+
+/// a destructive command waiting for the chat to confirm it
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum Confirmation {
+    Delete(String),
+    Reset,
+    Cancel,
+}
+
+impl Confirmation {
+    fn data(&self) -> String {
+        match self {
+            Self::Delete(repo_id) => format!("{CONFIRM_PREFIX}delete:{repo_id}"),
+            Self::Reset => format!("{CONFIRM_PREFIX}reset"),
+            Self::Cancel => format!("{CONFIRM_PREFIX}cancel"),
+        }
+    }
+
+    /// the confirmation of a button's callback data
+    pub fn parse(data: &str) -> Option<Self> {
+        match data.strip_prefix(CONFIRM_PREFIX)? {
+            "reset" => Some(Self::Reset),
+            "cancel" => Some(Self::Cancel),
+            action => action
+                .strip_prefix("delete:")
+                .map(|repo_id| Self::Delete(repo_id.to_string())),
+        }
+    }
+
+    pub fn keyboard(&self, language: Language) -> InlineKeyboardMarkup {
+        InlineKeyboardMarkup::new([[
+            InlineKeyboardButton::callback(language.tr("confirm-delete"), self.data()),
+            InlineKeyboardButton::callback(language.tr("confirm-keep"), Self::Cancel.data()),
+        ]])
+    }
+}
+
+/// how long a deleted repo can be restored for
+pub fn retention() -> Duration {
+    Duration::hours(*DELETE_RETENTION_HOURS)
+}
+
+/// soft delete a repo of a chat, returning its name unless it was already deleted
+pub async fn delete_repo(
+    pool: &Pool<Sqlite>,
+    repo_id: &str,
+    chat_id: i64,
+) -> Result<Option<String>, ServiceError> {
+    let now = Utc::now().naive_utc();
+
+    Ok(query!(
+        r#"
+        UPDATE main.repos
+        SET deleted_at = ?, name = id || ':' || name
+        WHERE id = ?
+        AND message_id = ?
+        AND deleted_at IS NULL
+        RETURNING substr(name, length(id) + 2) AS "name!: String"
+        "#,
+        now,
+        repo_id,
+        chat_id
+    )
+    .fetch_optional(pool)
+    .await?
+    .map(|record| record.name))
+}
+
+/// soft delete every repo of a chat at once so that a single `/undo` restores all of them.
+/// Returns the ids and names of the deleted repos
+pub async fn delete_chat_repos(
+    pool: &Pool<Sqlite>,
+    chat_id: i64,
+) -> Result<Vec<(String, String)>, ServiceError> {
+    let now = Utc::now().naive_utc();
+
+    Ok(query!(
+        r#"
+        UPDATE main.repos
+        SET deleted_at = ?, name = id || ':' || name
+        WHERE message_id = ?
+        AND deleted_at IS NULL
+        RETURNING id AS "id!", substr(name, length(id) + 2) AS "name!: String"
+        "#,
+        now,
+        chat_id
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|record| (record.id, record.name))
+    .collect())
+}
+
+/// the repos `/undo` brought back
+pub struct Restore {
+    /// ids and names of the restored repos
+    pub restored: Vec<(String, String)>,
+    /// names of the repos left deleted because a repo of the same name was created since
+    pub taken: Vec<String>,
+}
+
+/// restore the repos of a chat that were deleted last, as long as they are still within the
+/// retention window and their name is still free
+pub async fn restore_latest(pool: &Pool<Sqlite>, chat_id: i64) -> Result<Restore, ServiceError> {
+    let since = Utc::now().naive_utc() - retention();
+    let mut transaction = pool.begin().await?;
+    let deleted = query!(
+        r#"
+        SELECT substr(name, length(id) + 2) AS "name!: String"
+        FROM main.repos
+        WHERE message_id = ?
+        AND deleted_at = (
+            SELECT MAX(deleted_at)
+            FROM main.repos
+            WHERE message_id = ?
+            AND deleted_at >= ?
+        )
+        "#,
+        chat_id,
+        chat_id,
+        since
+    )
+    .fetch_all(&mut transaction)
+    .await?;
+    let restored = query!(
+        r#"
+        UPDATE OR IGNORE main.repos
+        SET deleted_at = NULL, name = substr(name, length(id) + 2)
+        WHERE message_id = ?
+        AND deleted_at = (
+            SELECT MAX(deleted_at)
+            FROM main.repos
+            WHERE message_id = ?
+            AND deleted_at >= ?
+        )
+        RETURNING id AS "id!", name AS "name!"
+        "#,
+        chat_id,
+        chat_id,
+        since
+    )
+    .fetch_all(&mut transaction)
+    .await?
+    .into_iter()
+    .map(|record| (record.id, record.name))
+    .collect::<Vec<(String, String)>>();
+    transaction.commit().await?;
+
+    let taken = deleted
+        .into_iter()
+        .map(|record| record.name)
+        .filter(|name| restored.iter().all(|(_, restored)| restored != name))
+        .collect();
+
+    Ok(Restore { restored, taken })
+}
+
+/// ids of the repos of a chat deleted last, which `/undo` would restore
+pub async fn latest_deleted(
+    pool: &Pool<Sqlite>,
+    chat_id: i64,
+) -> Result<Vec<String>, ServiceError> {
+    let since = Utc::now().naive_utc() - retention();
+
+    Ok(query!(
+        r#"
+        SELECT id
+        FROM main.repos
+        WHERE message_id = ?
+        AND deleted_at = (
+            SELECT MAX(deleted_at)
+            FROM main.repos
+            WHERE message_id = ?
+            AND deleted_at >= ?
+        )
+        "#,
+        chat_id,
+        chat_id,
+        since
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|record| record.id)
+    .collect())
+}
+
+/// permanently delete repos past the retention window. Their jobs go along with them
+async fn purge_expired(pool: &Pool<Sqlite>) -> Result<(), ServiceError> {
+    let before = Utc::now().naive_utc() - retention();
+    let result = query!(
+        r#"
+        DELETE FROM main.repos
+        WHERE deleted_at < ?
+        "#,
+        before
+    )
+    .execute(pool)
+    .await?;
+
+    if result.rows_affected() > 0 {
+        info!("purged {} deleted repo(s)", result.rows_affected());
+    }
+
+    Ok(())
+}
+
+/// purge expired repos in the background. This task never returns and is dropped along with
+/// the runtime
+pub async fn run_purge(pool: Pool<Sqlite>) {
+    loop {
+        if let Err(e) = purge_expired(&pool).await {
+            warn!("failed to purge deleted repos: {e}");
+            capture_warning("purge failed to delete expired repos");
+        }
+
+        tokio::time::sleep(PURGE_INTERVAL).await;
+    }
+}
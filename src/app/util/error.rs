@@ -13,8 +13,9 @@ use tracing::{error, warn};
 #[non_exhaustive]
 #[allow(dead_code)]
 pub enum ServiceError {
-    // #[error(transparent)]
-    // Reqwest(#[from] reqwest::Error),
+    #[error(transparent)]
+    #[serde(serialize_with = "as_json_string::serialize")]
+    Reqwest(#[from] reqwest::Error),
     // #[error(transparent)]
     // Redis(#[from] redis::RedisError),
     #[error(transparent)]
@@ -43,6 +44,9 @@ pub enum ServiceError {
     #[error(transparent)]
     #[serde(serialize_with = "as_json_string::serialize")]
     Uuid(#[from] uuid::Error),
+    #[error(transparent)]
+    #[serde(serialize_with = "as_json_string::serialize")]
+    SerdeJson(#[from] serde_json::Error),
     #[error("failed to convert from '{field}' value '{from}' into '{into}' expecting {expect}")]
     TryFrom {
         field: &'static str,
@@ -88,6 +92,23 @@ pub enum ServiceError {
     #[error(transparent)]
     #[serde(serialize_with = "as_json_string::serialize")]
     ServerError(#[from] axum::Error),
+    #[error(transparent)]
+    #[serde(serialize_with = "as_json_string::serialize")]
+    Smtp(#[from] lettre::transport::smtp::Error),
+    #[error(transparent)]
+    #[serde(serialize_with = "as_json_string::serialize")]
+    Email(#[from] lettre::error::Error),
+    #[error(transparent)]
+    #[serde(serialize_with = "as_json_string::serialize")]
+    EmailAddress(#[from] lettre::address::AddressError),
+    #[error(transparent)]
+    #[serde(serialize_with = "as_json_string::serialize")]
+    Cron(#[from] cron::error::Error),
+    #[error("failed to render chart: {0}")]
+    Chart(String),
+    #[error(transparent)]
+    #[serde(serialize_with = "as_json_string::serialize")]
+    Image(#[from] image::ImageError),
 }
 
 impl<T> From<tokio::sync::mpsc::error::SendError<T>> for ServiceError {
@@ -96,6 +117,14 @@ impl<T> From<tokio::sync::mpsc::error::SendError<T>> for ServiceError {
     }
 }
 
+impl<E: std::error::Error + Send + Sync> From<plotters::drawing::DrawingAreaErrorKind<E>>
+    for ServiceError
+{
+    fn from(e: plotters::drawing::DrawingAreaErrorKind<E>) -> Self {
+        ServiceError::Chart(e.to_string())
+    }
+}
+
 impl From<()> for ServiceError {
     fn from(_: ()) -> Self {
         ServiceError::SendError
@@ -105,51 +134,51 @@ impl From<()> for ServiceError {
 impl ServiceError {
     pub fn get_code(&self) -> StatusCode {
         match self {
-            // Self::Reqwest(e) if e.is_body() => {
-            //     warn!("reqwest body failed: {:?}", e);
-            //     capture_warning("HTTP client failed to parse payload body");
-            //     Code::InvalidArgument
-            // }
-            // Self::Reqwest(e) if e.is_builder() => {
-            //     error!("reqwest builder failed: {:?}", e);
-            //     capture_fatal("HTTP client failed to be initialized");
-            //     Code::Internal
-            // }
-            // Self::Reqwest(e) if e.is_connect() => {
-            //     error!("reqwest connection failed: {:?}", e);
-            //     capture_error("HTTP client failed to initiate connection");
-            //     Code::Unavailable
-            // }
-            // Self::Reqwest(e) if e.is_decode() => {
-            //     warn!("reqwest decode failed: {:?}", e);
-            //     capture_warning("HTTP client failed to decode payload instance");
-            //     Code::FailedPrecondition
-            // }
-            // Self::Reqwest(e) if e.is_redirect() => {
-            //     warn!("reqwest redirect failed: {:?}", e);
-            //     capture_warning("HTTP client failed to perform redirection");
-            //     Code::Internal
-            // }
-            // Self::Reqwest(e) if e.is_timeout() => {
-            //     warn!("reqwest timed out: {:?}", e);
-            //     capture_warning("HTTP client timed-out");
-            //     Code::DeadlineExceeded
-            // }
-            // Self::Reqwest(e) if e.is_request() => {
-            //     error!("bad reqwest: {:?}", e);
-            //     capture_error("HTTP client threw internal exception");
-            //     Code::FailedPrecondition
-            // }
-            // Self::Reqwest(e) if e.is_status() => {
-            //     warn!("reqwest status failed: {:?}", e);
-            //     capture_warning("HTTP client received bad protocol status");
-            //     Code::FailedPrecondition
-            // }
-            // Self::Reqwest(e) => {
-            //     error!("general reqwest error: {:?}", e);
-            //     capture_error("HTTP client returned general failure");
-            //     Code::Internal
-            // }
+            Self::Reqwest(e) if e.is_body() => {
+                warn!("reqwest body failed: {:?}", e);
+                capture_warning("HTTP client failed to parse payload body");
+                StatusCode::BAD_GATEWAY
+            }
+            Self::Reqwest(e) if e.is_builder() => {
+                error!("reqwest builder failed: {:?}", e);
+                capture_fatal("HTTP client failed to be initialized");
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+            Self::Reqwest(e) if e.is_connect() => {
+                error!("reqwest connection failed: {:?}", e);
+                capture_error("HTTP client failed to initiate connection");
+                StatusCode::BAD_GATEWAY
+            }
+            Self::Reqwest(e) if e.is_decode() => {
+                warn!("reqwest decode failed: {:?}", e);
+                capture_warning("HTTP client failed to decode payload instance");
+                StatusCode::BAD_GATEWAY
+            }
+            Self::Reqwest(e) if e.is_redirect() => {
+                warn!("reqwest redirect failed: {:?}", e);
+                capture_warning("HTTP client failed to perform redirection");
+                StatusCode::BAD_GATEWAY
+            }
+            Self::Reqwest(e) if e.is_timeout() => {
+                warn!("reqwest timed out: {:?}", e);
+                capture_warning("HTTP client timed-out");
+                StatusCode::GATEWAY_TIMEOUT
+            }
+            Self::Reqwest(e) if e.is_request() => {
+                error!("bad reqwest: {:?}", e);
+                capture_error("HTTP client threw internal exception");
+                StatusCode::BAD_GATEWAY
+            }
+            Self::Reqwest(e) if e.is_status() => {
+                warn!("reqwest status failed: {:?}", e);
+                capture_warning("HTTP client received bad protocol status");
+                StatusCode::BAD_GATEWAY
+            }
+            Self::Reqwest(e) => {
+                error!("general reqwest error: {:?}", e);
+                capture_error("HTTP client returned general failure");
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
             // Self::Redis(e) if e.is_timeout() => {
             //     warn!("redis timed out: {:?}", e);
             //     capture_warning("Redis client timed-out");
@@ -236,7 +265,7 @@ impl ServiceError {
                 );
                 StatusCode::INTERNAL_SERVER_ERROR
             }
-            // Self::ValidateFailure { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+            Self::ValidateFailure { .. } => StatusCode::UNPROCESSABLE_ENTITY,
             Self::ParseInt(e) => {
                 warn!("failed integer parsing: {:?}", e);
                 capture_warning(
@@ -266,6 +295,13 @@ impl ServiceError {
                 );
                 StatusCode::UNPROCESSABLE_ENTITY
             }
+            Self::SerdeJson(e) => {
+                error!("json serialization failure: {:?}", e);
+                capture_error(
+                    "Service encountered failure while attempting to serialize or deserialize json",
+                );
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
             Self::TryFrom { .. } => StatusCode::BAD_REQUEST,
             // Self::Validation(_) => Code::InvalidArgument,
             // Self::Validations(_) => Code::InvalidArgument,
@@ -368,6 +404,42 @@ impl ServiceError {
                 );
                 StatusCode::INTERNAL_SERVER_ERROR
             }
+            Self::Smtp(e) => {
+                error!("smtp error: {:?}", e);
+                capture_warning(
+                    "Service encountered unexpected failure while interacting with SMTP server",
+                );
+                StatusCode::BAD_GATEWAY
+            }
+            Self::Email(e) => {
+                error!("email error: {:?}", e);
+                capture_error("Service encountered failure while attempting to build an email");
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+            Self::EmailAddress(e) => {
+                warn!("email address parsing failure: {:?}", e);
+                capture_warning(
+                    "Service encountered failure while attempting to parse input to email address",
+                );
+                StatusCode::UNPROCESSABLE_ENTITY
+            }
+            Self::Cron(e) => {
+                warn!("cron expression parsing failure: {:?}", e);
+                capture_warning(
+                    "Service encountered failure while attempting to parse cron expression",
+                );
+                StatusCode::UNPROCESSABLE_ENTITY
+            }
+            Self::Chart(e) => {
+                error!("chart rendering failure: {}", e);
+                capture_error("Service encountered failure while attempting to render a chart");
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+            Self::Image(e) => {
+                error!("image encoding failure: {:?}", e);
+                capture_error("Service encountered failure while attempting to encode an image");
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
             #[allow(unreachable_patterns)]
             _ => {
                 error!("undocumented error: {}", &self.to_string());
@@ -1,13 +1,27 @@
 use crate::app::{
-    config::{database::init_sqlite, task::spawn_with_name},
+    config::{
+        bot::init_bot, database::init_sqlite, http::init_http_client, mailer::init_mailer,
+        task::spawn_with_name,
+    },
     middleware::auth::layer::SessionLayer,
     service::{
+        audit::audit_handler,
         bot::{
-            handler::{config_mode_handler, invalid_command, normal_mode_handler, start},
+            handler::{
+                browser_handler, callback_handler, config_mode_handler, invalid_command,
+                normal_mode_handler, start, upgrade_state,
+            },
             state::{BotState, GeneralCommand, RepoCommand},
         },
+        browser::BrowserAction,
+        digest::run_digest_scheduler,
+        dora::dora_handler,
+        escalation::run_escalation,
         job::{create_job_handler, update_job_handler},
+        notifier::email::run_daily_digest,
+        outbox::{run_outbox, run_sink_outbox},
         root::{root_failure_handler, root_handler},
+        trash::run_purge,
     },
 };
 use axum::{
@@ -55,6 +69,14 @@ lazy_static::lazy_static! {
     static ref APP_PORT: String = var("APP_PORT").expect("expect an APP_PORT to be set. app port define virtual port for app to bind to");
     static ref SENTRY_URL: String = var("SENTRY_URL").expect("expect SENTRY_URL to be set");
     static ref DATABASE_URL: String = var("DATABASE_URL").expect("expect DATABASE_URL to be set");
+    static ref SMTP_HOST: Option<String> = var("SMTP_HOST").ok();
+    static ref SMTP_PORT: Option<u16> = var("SMTP_PORT").ok().map(|port| port.parse().expect("expect SMTP_PORT to be a valid port number"));
+    static ref SMTP_SECURITY: String = var("SMTP_SECURITY").unwrap_or_else(|_| "starttls".to_string());
+    static ref SMTP_USERNAME: Option<String> = var("SMTP_USERNAME").ok();
+    static ref SMTP_PASSWORD: Option<String> = var("SMTP_PASSWORD").ok();
+    static ref SMTP_FROM: Option<String> = var("SMTP_FROM").ok();
+    static ref DELETE_RETENTION_HOURS: i64 = var("DELETE_RETENTION_HOURS").map_or(24, |hours| hours.parse().ok().filter(|hours| *hours > 0).expect("expect DELETE_RETENTION_HOURS to be a positive number of hours"));
+    static ref EMAIL_DIGEST_HOUR: u32 = var("EMAIL_DIGEST_HOUR").map_or(9, |hour| hour.parse().ok().filter(|hour| *hour < 24).expect("expect EMAIL_DIGEST_HOUR to be an hour of the day (0-23)"));
 }
 
 mod app;
@@ -125,7 +147,10 @@ async fn main() {
         .await
         .expect("expect a migration to complete successfully");
 
-    let bot = Bot::from_env();
+    let mailer = init_mailer().expect("expect smtp mailer to be setup successfully");
+    let http_client = init_http_client().expect("expect http client to be setup successfully");
+
+    let bot = init_bot();
 
     let teloxide_handler = spawn_with_name(
         {
@@ -135,24 +160,41 @@ async fn main() {
             async move {
                 Dispatcher::builder(
                     bot,
-                    Update::filter_message()
-                        .enter_dialogue::<Message, ErasedStorage<BotState>, BotState>()
-                        .branch(dptree::case![BotState::Start].endpoint(start))
+                    dptree::entry()
                         .branch(
-                            dptree::case![BotState::ConfigMode(list, key)]
+                            Update::filter_message()
+                                .enter_dialogue::<Message, ErasedStorage<BotState>, BotState>()
+                                .map_async(upgrade_state)
+                                .branch(dptree::case![BotState::Start].endpoint(start))
                                 .branch(
-                                    dptree::entry()
-                                        .filter_command::<RepoCommand>()
-                                        .endpoint(config_mode_handler),
+                                    dptree::case![BotState::Config(key)]
+                                        .branch(
+                                            dptree::entry()
+                                                .filter_command::<RepoCommand>()
+                                                .endpoint(config_mode_handler),
+                                        )
+                                        .branch(dptree::endpoint(invalid_command)),
                                 )
-                                .branch(dptree::endpoint(invalid_command)),
+                                .branch(
+                                    dptree::case![BotState::Normal].branch(
+                                        dptree::entry()
+                                            .filter_command::<GeneralCommand>()
+                                            .endpoint(normal_mode_handler),
+                                    ),
+                                ),
                         )
                         .branch(
-                            dptree::case![BotState::NormalMode(list)].branch(
-                                dptree::entry()
-                                    .filter_command::<GeneralCommand>()
-                                    .endpoint(normal_mode_handler),
-                            ),
+                            Update::filter_callback_query()
+                                .enter_dialogue::<CallbackQuery, ErasedStorage<BotState>, BotState>(
+                                )
+                                .map_async(upgrade_state)
+                                .branch(
+                                    dptree::filter_map(|q: CallbackQuery| {
+                                        q.data.as_deref().and_then(BrowserAction::parse)
+                                    })
+                                    .endpoint(browser_handler),
+                                )
+                                .branch(dptree::endpoint(callback_handler)),
                         ),
                 )
                 .dependencies(dptree::deps![storage, sqlite_pool])
@@ -165,6 +207,35 @@ async fn main() {
         },
         "teloxide",
     );
+    // the digest task sleeps for most of the day so it is left out of the graceful shutdown and
+    // simply dropped along with the runtime
+    if let Some(mailer) = mailer.clone() {
+        spawn_with_name(
+            run_daily_digest(sqlite_pool.clone(), mailer).instrument(info_span!("email digest")),
+            "email digest",
+        );
+    }
+    spawn_with_name(
+        run_digest_scheduler(sqlite_pool.clone(), bot.clone()).instrument(info_span!("digest")),
+        "digest scheduler",
+    );
+    spawn_with_name(
+        run_escalation(sqlite_pool.clone(), bot.clone()).instrument(info_span!("escalation")),
+        "escalation",
+    );
+    spawn_with_name(
+        run_purge(sqlite_pool.clone()).instrument(info_span!("purge")),
+        "purge",
+    );
+    spawn_with_name(
+        run_outbox(sqlite_pool.clone(), bot).instrument(info_span!("outbox")),
+        "outbox",
+    );
+    spawn_with_name(
+        run_sink_outbox(sqlite_pool.clone(), http_client, mailer)
+            .instrument(info_span!("sink outbox")),
+        "sink outbox",
+    );
     // thread safe application shutdown signal notifier
     let shutdown_signal_notifier = Arc::new(Notify::new());
     // graceful shutdown handler
@@ -199,6 +270,8 @@ async fn main() {
             .route("/", post(root_failure_handler))
             .route("/job", post(create_job_handler))
             .route("/job", put(update_job_handler))
+            .route("/dora", get(dora_handler))
+            .route("/audit", get(audit_handler))
             .layer(
                 ServiceBuilder::new()
                     .layer(
@@ -245,7 +318,6 @@ async fn main() {
                     )
                     .layer(TimeoutLayer::new(Duration::from_secs(30)))
                     .layer(Extension(sqlite_pool))
-                    .layer(Extension(bot))
                     .layer(SessionLayer),
             );
     // .fallback(unknown_route_handler);